@@ -0,0 +1,43 @@
+//! Times a recursive fib on both backends, which mostly exercises local variable reads and calls.
+//!
+//! Run with `cargo run --release --example fib [n]`.
+
+use std::{
+    cell::RefCell,
+    env,
+    io::{BufReader, Cursor},
+    rc::Rc,
+    time::Instant,
+};
+
+use interpreter_starter_rust::{interpreter::Interpreter, vm::Vm};
+
+fn main() {
+    let n = env::args()
+        .nth(1)
+        .and_then(|i| i.parse::<u32>().ok())
+        .unwrap_or(25);
+    let script = format!(
+        "fun fib(n) {{
+    if (n < 2) return n;
+    return fib(n - 1) + fib(n - 2);
+}}
+print fib({});",
+        n
+    );
+
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let reader = BufReader::new(Cursor::new(script.clone()));
+    let mut interpreter = Interpreter::build(reader, output.clone()).unwrap();
+    let start = Instant::now();
+    interpreter.run().unwrap();
+    println!("interpreter: fib({}) in {:?}", n, start.elapsed());
+
+    let reader = BufReader::new(Cursor::new(script));
+    let mut vm = Vm::build(reader, output.clone()).unwrap();
+    let start = Instant::now();
+    vm.run().unwrap();
+    println!("vm: fib({}) in {:?}", n, start.elapsed());
+
+    print!("{}", String::from_utf8_lossy(output.borrow().as_slice()));
+}
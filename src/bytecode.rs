@@ -0,0 +1,119 @@
+use std::{fmt::Display, rc::Rc};
+
+use crate::{interner::Symbol, span::Span};
+
+/// A single instruction. Operands index into the chunk's constants, the frame's local slots, the
+/// closure's upvalues or the global variables, or are jump offsets counted in instructions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Constant(u32),
+    Nil,
+    True,
+    False,
+    Pop,
+    GetLocal(u32),
+    SetLocal(u32),
+    GetGlobal(u32),
+    DefineGlobal(u32),
+    SetGlobal(u32),
+    GetUpvalue(u32),
+    SetUpvalue(u32),
+    CloseUpvalue,
+    GetProperty(u32),
+    SetProperty(u32),
+    /// Pops the super class and `this`, and pushes the super class' method named by the constant
+    /// bound to `this`.
+    GetSuper(u32),
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Range {
+        inclusive: bool,
+    },
+    Index,
+    Not,
+    Negate,
+    Print,
+    Jump(u32),
+    JumpIfFalse(u32),
+    Loop(u32),
+    /// Replaces the iterable on top of the stack with an iterator over it.
+    Iterate,
+    /// Pops an iterator and pushes its next item, or jumps forward when it is exhausted.
+    Next(u32),
+    Call(u32),
+    /// Calls like `Call`, reusing the frame of the running function when the callee is a Lox
+    /// function. Always followed by a `Return`, for callees that get no frame of their own.
+    TailCall(u32),
+    Closure(u32),
+    Return,
+    Class(u32),
+    /// Copies the methods of the super class, below the class on the stack, into the class.
+    Inherit,
+    Method(u32),
+}
+
+#[derive(Debug)]
+pub enum Constant {
+    Number(f64),
+    String(Rc<String>),
+    Name(Symbol),
+    Function(Rc<FunctionProto>),
+}
+
+/// Compiled code, with the source code of every instruction for error messages.
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<Op>,
+    pub spans: Vec<Span>,
+    pub constants: Vec<Constant>,
+}
+
+impl Chunk {
+    pub fn write(&mut self, op: Op, span: Span) -> usize {
+        self.code.push(op);
+        self.spans.push(span);
+        self.code.len() - 1
+    }
+
+    pub fn add_constant(&mut self, constant: Constant) -> u32 {
+        self.constants.push(constant);
+        (self.constants.len() - 1) as u32
+    }
+
+    /// The name at `index`, for instructions whose operand is a variable, property or class name.
+    pub fn name(&self, index: u32) -> Symbol {
+        match &self.constants[index as usize] {
+            Constant::Name(name) => *name,
+            constant => panic!("constant {:?} is not a name", constant),
+        }
+    }
+}
+
+/// Whether a captured variable is a local of the enclosing function, or one of its upvalues.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UpvalueSource {
+    pub is_local: bool,
+    pub index: u32,
+}
+
+#[derive(Debug, Default)]
+pub struct FunctionProto {
+    pub name: Symbol,
+    pub arity: usize,
+    pub chunk: Chunk,
+    pub upvalues: Vec<UpvalueSource>,
+}
+
+impl Display for FunctionProto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<fn {}>", self.name)
+    }
+}
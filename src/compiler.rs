@@ -0,0 +1,599 @@
+use std::{collections::HashMap, ops::Deref, rc::Rc};
+
+use crate::{
+    bytecode::{Chunk, Constant, FunctionProto, Op, UpvalueSource},
+    error_kind::ErrorKind,
+    errors::{ErrorMessage, InterpreterError},
+    interner::Symbol,
+    parser::{self, Expr, Statement},
+    scanner::{Literal, Token, TokenType},
+    span::Span,
+};
+
+/// Name of the hidden local holding the iterator of a `for in` loop; being a keyword, it can't
+/// clash with a variable.
+const ITERATOR_LOCAL: &str = "for";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FunctionKind {
+    Script,
+    Function,
+    Method,
+    Initializer,
+}
+
+struct LocalVariable {
+    name: Symbol,
+    depth: usize,
+    is_captured: bool,
+}
+
+/// A function being compiled. Its locals mirror the stack slots of its call frame: slot 0 holds
+/// the function itself, or `this` for methods, and the others the parameters and variables.
+struct FunctionState {
+    proto: FunctionProto,
+    locals: Vec<LocalVariable>,
+    scope_depth: usize,
+}
+
+impl FunctionState {
+    fn new(name: Symbol, kind: FunctionKind) -> Self {
+        let receiver = match kind {
+            FunctionKind::Method | FunctionKind::Initializer => "this",
+            FunctionKind::Script | FunctionKind::Function => "",
+        };
+
+        Self {
+            proto: FunctionProto {
+                name,
+                ..Default::default()
+            },
+            locals: vec![LocalVariable {
+                name: Symbol::intern(receiver),
+                depth: 0,
+                is_captured: false,
+            }],
+            scope_depth: 0,
+        }
+    }
+
+    fn resolve_local(&self, name: Symbol) -> Option<u32> {
+        self.locals
+            .iter()
+            .rposition(|i| i.name == name)
+            .map(|i| i as u32)
+    }
+
+    fn add_upvalue(&mut self, source: UpvalueSource) -> u32 {
+        match self.proto.upvalues.iter().position(|i| *i == source) {
+            Some(index) => index as u32,
+            None => {
+                self.proto.upvalues.push(source);
+                (self.proto.upvalues.len() - 1) as u32
+            }
+        }
+    }
+}
+
+/// Compiles resolved statements to bytecode for the [`Vm`](crate::vm::Vm).
+///
+/// Variables of the top level are globals, addressed by their index in the global names shared
+/// with the VM. Everything else lives on the stack, and variables captured by closures are
+/// accessed through upvalues.
+pub struct Compiler<'a> {
+    functions: Vec<FunctionState>,
+    globals: HashMap<Symbol, u32>,
+    global_names: &'a mut Vec<Symbol>,
+    span: Span,
+}
+
+impl<'a> Compiler<'a> {
+    pub fn compile(
+        statements: &[Statement],
+        global_names: &'a mut Vec<Symbol>,
+    ) -> Result<Rc<FunctionProto>, InterpreterError> {
+        let mut compiler = Compiler {
+            functions: vec![FunctionState::new(
+                Symbol::intern("script"),
+                FunctionKind::Script,
+            )],
+            globals: global_names
+                .iter()
+                .enumerate()
+                .map(|(index, name)| (*name, index as u32))
+                .collect(),
+            global_names,
+            span: Span::default(),
+        };
+
+        for statement in statements {
+            compiler.statement(statement)?;
+        }
+        compiler.emit(Op::Nil);
+        compiler.emit(Op::Return);
+
+        let function = compiler.functions.pop().expect("script should be compiled");
+        Ok(Rc::new(function.proto))
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Result<(), InterpreterError> {
+        match statement {
+            Statement::Class(name, methods, super_class) => {
+                self.class(name, methods, super_class.as_ref())
+            }
+            Statement::Variable(name, initializer) => {
+                self.span = name.span;
+                if self.state().scope_depth > 0 {
+                    self.add_local(name.symbol());
+                }
+                match initializer {
+                    Some(initializer) => self.expression(initializer)?,
+                    None => {
+                        self.emit(Op::Nil);
+                    }
+                }
+                if self.state().scope_depth == 0 {
+                    let global = self.global(name.symbol());
+                    self.emit_at(Op::DefineGlobal(global), name.span);
+                }
+                Ok(())
+            }
+            Statement::Print(_, expr) => {
+                self.expression(expr)?;
+                self.emit_at(Op::Print, expr.span());
+                Ok(())
+            }
+            Statement::Return(_, expr) => {
+                match expr {
+                    // Initializers can't return a value, so a tail call never has to return the
+                    // instance being constructed.
+                    Some(expr) => match expr.deref() {
+                        Expr::Call(callee, right_paren, arguments) => {
+                            self.expression(callee)?;
+                            for argument in arguments.iter() {
+                                self.expression(argument)?;
+                            }
+                            self.emit_at(Op::TailCall(arguments.len() as u32), right_paren.span);
+                        }
+                        _ => self.expression(expr)?,
+                    },
+                    None => {
+                        self.emit(Op::Nil);
+                    }
+                }
+                self.emit(Op::Return);
+                Ok(())
+            }
+            Statement::Expression(expr) => {
+                self.expression(expr)?;
+                self.emit(Op::Pop);
+                Ok(())
+            }
+            Statement::Block(_, statements) => {
+                self.begin_scope();
+                for statement in statements.iter() {
+                    self.statement(statement)?;
+                }
+                self.end_scope();
+                Ok(())
+            }
+            Statement::If(_, condition, then_branch, else_branch) => {
+                self.expression(condition)?;
+                let then_jump = self.emit(Op::JumpIfFalse(0));
+                self.emit(Op::Pop);
+                self.statement(then_branch)?;
+                let else_jump = self.emit(Op::Jump(0));
+                self.patch_jump(then_jump);
+                self.emit(Op::Pop);
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch)?;
+                }
+                self.patch_jump(else_jump);
+                Ok(())
+            }
+            Statement::While(_, condition, body) => {
+                let loop_start = self.chunk().code.len();
+                self.expression(condition)?;
+                let exit_jump = self.emit(Op::JumpIfFalse(0));
+                self.emit(Op::Pop);
+                self.statement(body)?;
+                self.emit_loop(loop_start);
+                self.patch_jump(exit_jump);
+                self.emit(Op::Pop);
+                Ok(())
+            }
+            Statement::ForIn(_, name, iterable, body) => {
+                self.expression(iterable)?;
+                self.emit_at(Op::Iterate, iterable.span());
+
+                self.begin_scope();
+                let iterator = self.add_local(Symbol::intern(ITERATOR_LOCAL));
+
+                let loop_start = self.chunk().code.len();
+                self.emit(Op::GetLocal(iterator));
+                let exit_jump = self.emit(Op::Next(0));
+
+                self.begin_scope();
+                self.add_local(name.symbol());
+                self.statement(body)?;
+                self.end_scope();
+
+                self.emit_loop(loop_start);
+                self.patch_jump(exit_jump);
+                self.end_scope();
+                Ok(())
+            }
+        }
+    }
+
+    fn class(
+        &mut self,
+        name: &Token,
+        methods: &[Option<Rc<Expr>>],
+        super_class: Option<&Rc<Expr>>,
+    ) -> Result<(), InterpreterError> {
+        self.span = name.span;
+        let name_constant = self.name_constant(name.symbol());
+        self.emit(Op::Class(name_constant));
+        self.define_variable(name.symbol());
+
+        if let Some(super_class) = super_class {
+            self.expression(super_class)?;
+            self.begin_scope();
+            self.add_local(Symbol::intern("super"));
+            self.named_variable(name.symbol(), name.span);
+            self.emit_at(Op::Inherit, name.span);
+        }
+
+        self.named_variable(name.symbol(), name.span);
+        for method in methods.iter().filter_map(|i| i.as_ref()) {
+            match method.deref() {
+                Expr::Function(Some(method_name), function) => {
+                    let kind = if method_name.symbol() == "init" {
+                        FunctionKind::Initializer
+                    } else {
+                        FunctionKind::Method
+                    };
+                    self.function(method_name.symbol(), function, kind, method_name.span)?;
+                    let method_constant = self.name_constant(method_name.symbol());
+                    self.emit_at(Op::Method(method_constant), method_name.span);
+                }
+                _ => {
+                    return Err(InterpreterError::InterpreterError(ErrorMessage::new(
+                        ErrorKind::Internal,
+                        "class can only contain functions",
+                        Some(method.span()),
+                    )))
+                }
+            }
+        }
+        self.emit(Op::Pop);
+
+        if super_class.is_some() {
+            self.end_scope();
+        }
+
+        Ok(())
+    }
+
+    fn function(
+        &mut self,
+        name: Symbol,
+        function: &parser::Function,
+        kind: FunctionKind,
+        span: Span,
+    ) -> Result<(), InterpreterError> {
+        let mut state = FunctionState::new(name, kind);
+        state.proto.arity = function.parameters.len();
+        self.functions.push(state);
+
+        self.begin_scope();
+        for parameter in function.parameters.iter() {
+            self.add_local(parameter.symbol());
+        }
+        self.statement(&function.body)?;
+        self.emit(Op::Nil);
+        self.emit(Op::Return);
+
+        let state = self.functions.pop().expect("function should be compiled");
+        let constant = self
+            .chunk()
+            .add_constant(Constant::Function(Rc::new(state.proto)));
+        self.emit_at(Op::Closure(constant), span);
+        Ok(())
+    }
+
+    fn expression(&mut self, expr: &Rc<Expr>) -> Result<(), InterpreterError> {
+        match expr.deref() {
+            Expr::Literal(token) => {
+                self.span = token.span;
+                match (token.token_type, token.literal.as_deref()) {
+                    (TokenType::True, _) => self.emit(Op::True),
+                    (TokenType::False, _) => self.emit(Op::False),
+                    (TokenType::Nil, _) => self.emit(Op::Nil),
+                    (_, Some(Literal::Digit(n))) => {
+                        let constant = self.chunk().add_constant(Constant::Number(*n));
+                        self.emit(Op::Constant(constant))
+                    }
+                    (_, Some(Literal::String(s))) => {
+                        let constant = self.chunk().add_constant(Constant::String(s.clone()));
+                        self.emit(Op::Constant(constant))
+                    }
+                    _ => panic!("token should have a literal"),
+                };
+                Ok(())
+            }
+            Expr::Logical(token, left, right) => {
+                self.expression(left)?;
+                match token.token_type {
+                    TokenType::And => {
+                        let end_jump = self.emit_at(Op::JumpIfFalse(0), token.span);
+                        self.emit(Op::Pop);
+                        self.expression(right)?;
+                        self.patch_jump(end_jump);
+                    }
+                    TokenType::Or => {
+                        let else_jump = self.emit_at(Op::JumpIfFalse(0), token.span);
+                        let end_jump = self.emit(Op::Jump(0));
+                        self.patch_jump(else_jump);
+                        self.emit(Op::Pop);
+                        self.expression(right)?;
+                        self.patch_jump(end_jump);
+                    }
+                    _ => {
+                        return Err(InterpreterError::InterpreterError(ErrorMessage::new(
+                            ErrorKind::Internal,
+                            "Logical operator should be 'or' or 'and'",
+                            Some(token.span),
+                        )))
+                    }
+                }
+                Ok(())
+            }
+            Expr::Grouping(_, expr) => self.expression(expr),
+            Expr::Unary(token, expr) => {
+                self.expression(expr)?;
+                match token.token_type {
+                    TokenType::Minus => self.emit_at(Op::Negate, token.span),
+                    TokenType::Bang => self.emit_at(Op::Not, token.span),
+                    _ => panic!("oh no..."),
+                };
+                Ok(())
+            }
+            Expr::Binary(token, left, right) => {
+                self.expression(left)?;
+                self.expression(right)?;
+                let op = match token.token_type {
+                    TokenType::Plus => Op::Add,
+                    TokenType::Minus => Op::Subtract,
+                    TokenType::Star => Op::Multiply,
+                    TokenType::Slash => Op::Divide,
+                    TokenType::EqualEqual => Op::Equal,
+                    TokenType::BangEqual => Op::NotEqual,
+                    TokenType::Greater => Op::Greater,
+                    TokenType::GreaterEqual => Op::GreaterEqual,
+                    TokenType::Less => Op::Less,
+                    TokenType::LessEqual => Op::LessEqual,
+                    TokenType::DotDot => Op::Range { inclusive: false },
+                    TokenType::DotDotEqual => Op::Range { inclusive: true },
+                    _ => {
+                        return Err(InterpreterError::evaluating(
+                            ErrorKind::Internal,
+                            "Unrecognized binary expression",
+                            token.span,
+                        ))
+                    }
+                };
+                self.emit_at(op, token.span);
+                Ok(())
+            }
+            Expr::Variable(token) | Expr::This(token) => {
+                self.named_variable(token.symbol(), token.span);
+                Ok(())
+            }
+            Expr::Assignment(token, value) => {
+                self.expression(value)?;
+                let op = match self.resolve(token.symbol()) {
+                    Variable::Local(slot) => Op::SetLocal(slot),
+                    Variable::Upvalue(index) => Op::SetUpvalue(index),
+                    Variable::Global(index) => Op::SetGlobal(index),
+                };
+                self.emit_at(op, token.span);
+                Ok(())
+            }
+            Expr::Call(callee, right_paren, arguments) => {
+                self.expression(callee)?;
+                for argument in arguments.iter() {
+                    self.expression(argument)?;
+                }
+                self.emit_at(Op::Call(arguments.len() as u32), right_paren.span);
+                Ok(())
+            }
+            Expr::Function(name, function) => {
+                let span = name.as_ref().map_or(function.span, |i| i.span);
+                let name = name
+                    .as_ref()
+                    .map_or_else(|| Symbol::intern("__<fun_anon>"), |i| i.symbol());
+                self.function(name, function, FunctionKind::Function, span)
+            }
+            Expr::Get(object, name) => {
+                self.expression(object)?;
+                let constant = self.name_constant(name.symbol());
+                self.emit_at(Op::GetProperty(constant), name.span);
+                Ok(())
+            }
+            Expr::Set(object, name, value) => {
+                self.expression(object)?;
+                self.expression(value)?;
+                let constant = self.name_constant(name.symbol());
+                self.emit_at(Op::SetProperty(constant), name.span);
+                Ok(())
+            }
+            Expr::Index(target, right_bracket, index) => {
+                self.expression(target)?;
+                self.expression(index)?;
+                self.emit_at(Op::Index, right_bracket.span);
+                Ok(())
+            }
+            Expr::Super(keyword, method) => {
+                self.named_variable(Symbol::intern("this"), keyword.span);
+                self.named_variable(Symbol::intern("super"), keyword.span);
+                let constant = self.name_constant(method.symbol());
+                self.emit_at(Op::GetSuper(constant), method.span);
+                Ok(())
+            }
+        }
+    }
+
+    fn named_variable(&mut self, name: Symbol, span: Span) {
+        let op = match self.resolve(name) {
+            Variable::Local(slot) => Op::GetLocal(slot),
+            Variable::Upvalue(index) => Op::GetUpvalue(index),
+            Variable::Global(index) => Op::GetGlobal(index),
+        };
+        self.emit_at(op, span);
+    }
+
+    /// Defines a variable whose value is on top of the stack.
+    fn define_variable(&mut self, name: Symbol) {
+        if self.state().scope_depth > 0 {
+            self.add_local(name);
+        } else {
+            let global = self.global(name);
+            self.emit(Op::DefineGlobal(global));
+        }
+    }
+
+    fn resolve(&mut self, name: Symbol) -> Variable {
+        let current = self.functions.len() - 1;
+        if let Some(slot) = self.functions[current].resolve_local(name) {
+            return Variable::Local(slot);
+        }
+        match self.resolve_upvalue(current, name) {
+            Some(index) => Variable::Upvalue(index),
+            None => Variable::Global(self.global(name)),
+        }
+    }
+
+    fn resolve_upvalue(&mut self, function: usize, name: Symbol) -> Option<u32> {
+        if function == 0 {
+            return None;
+        }
+
+        let enclosing = function - 1;
+        if let Some(slot) = self.functions[enclosing].resolve_local(name) {
+            self.functions[enclosing].locals[slot as usize].is_captured = true;
+            return Some(self.functions[function].add_upvalue(UpvalueSource {
+                is_local: true,
+                index: slot,
+            }));
+        }
+
+        let index = self.resolve_upvalue(enclosing, name)?;
+        Some(self.functions[function].add_upvalue(UpvalueSource {
+            is_local: false,
+            index,
+        }))
+    }
+
+    fn global(&mut self, name: Symbol) -> u32 {
+        if let Some(index) = self.globals.get(&name) {
+            return *index;
+        }
+
+        let index = self.global_names.len() as u32;
+        self.global_names.push(name);
+        self.globals.insert(name, index);
+        index
+    }
+
+    fn add_local(&mut self, name: Symbol) -> u32 {
+        let state = self.state_mut();
+        state.locals.push(LocalVariable {
+            name,
+            depth: state.scope_depth,
+            is_captured: false,
+        });
+        (state.locals.len() - 1) as u32
+    }
+
+    fn begin_scope(&mut self) {
+        self.state_mut().scope_depth += 1;
+    }
+
+    fn end_scope(&mut self) {
+        let state = self.state_mut();
+        state.scope_depth -= 1;
+        let depth = state.scope_depth;
+
+        let mut ops = Vec::new();
+        while let Some(local) = state.locals.last() {
+            if local.depth <= depth {
+                break;
+            }
+            ops.push(if local.is_captured {
+                Op::CloseUpvalue
+            } else {
+                Op::Pop
+            });
+            state.locals.pop();
+        }
+
+        for op in ops {
+            self.emit(op);
+        }
+    }
+
+    fn name_constant(&mut self, name: Symbol) -> u32 {
+        self.chunk().add_constant(Constant::Name(name))
+    }
+
+    fn emit(&mut self, op: Op) -> usize {
+        let span = self.span;
+        self.chunk().write(op, span)
+    }
+
+    fn emit_at(&mut self, op: Op, span: Span) -> usize {
+        self.span = span;
+        self.chunk().write(op, span)
+    }
+
+    fn emit_loop(&mut self, loop_start: usize) {
+        let offset = self.chunk().code.len() + 1 - loop_start;
+        self.emit(Op::Loop(offset as u32));
+    }
+
+    /// Points the jump at `index` to the next instruction to be emitted.
+    fn patch_jump(&mut self, index: usize) {
+        let chunk = self.chunk();
+        let offset = (chunk.code.len() - index - 1) as u32;
+        chunk.code[index] = match chunk.code[index] {
+            Op::Jump(_) => Op::Jump(offset),
+            Op::JumpIfFalse(_) => Op::JumpIfFalse(offset),
+            Op::Next(_) => Op::Next(offset),
+            op => panic!("{:?} is not a jump", op),
+        };
+    }
+
+    fn state(&self) -> &FunctionState {
+        self.functions
+            .last()
+            .expect("a function should be compiling")
+    }
+
+    fn state_mut(&mut self) -> &mut FunctionState {
+        self.functions
+            .last_mut()
+            .expect("a function should be compiling")
+    }
+
+    fn chunk(&mut self) -> &mut Chunk {
+        &mut self.state_mut().proto.chunk
+    }
+}
+
+enum Variable {
+    Local(u32),
+    Upvalue(u32),
+    Global(u32),
+}
@@ -0,0 +1,352 @@
+use std::{fmt::Write, io, io::IsTerminal, str::FromStr};
+
+use crate::{
+    errors::{ErrorMessage, InterpreterError},
+    span::{Position, Span},
+};
+
+/// Whether diagnostics are colored, as chosen with `--color`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    Never,
+    Always,
+    /// Colors when stderr, where diagnostics are written, is a terminal.
+    #[default]
+    Auto,
+}
+
+impl ColorChoice {
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Never => false,
+            ColorChoice::Always => true,
+            ColorChoice::Auto => io::stderr().is_terminal(),
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "never" => Ok(ColorChoice::Never),
+            "always" => Ok(ColorChoice::Always),
+            "auto" => Ok(ColorChoice::Auto),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How errors are written, as chosen with `--error-format`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// For people: see [`Renderer`].
+    #[default]
+    Human,
+    /// For tools: one JSON object per line and per error.
+    ///
+    /// ```text
+    /// {"severity":"error","phase":"parse","code":"L0201","message":"Unexpected token: SEMICOLON",
+    ///  "span":{"file":"script.lox","start":{"line":1,"column":9,"offset":8},
+    ///  "end":{"line":1,"column":10,"offset":9}},"notes":[],"help":null}
+    /// ```
+    ///
+    /// `span` is null for errors about no code in particular, like running out of fuel. Notes
+    /// have a `message` and a `span`.
+    Json,
+}
+
+impl FromStr for ErrorFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(ErrorFormat::Human),
+            "json" => Ok(ErrorFormat::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Style {
+    Error,
+    Note,
+    Help,
+    Gutter,
+    Message,
+}
+
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Style::Error => "\x1b[1;31m",
+            Style::Note => "\x1b[1;32m",
+            Style::Help => "\x1b[1;36m",
+            Style::Gutter => "\x1b[1;34m",
+            Style::Message => "\x1b[1m",
+        }
+    }
+}
+
+/// Code to underline in an excerpt: `^` under the code an error is about, `-` under the code a
+/// note points at, followed by the note.
+struct Annotation<'a> {
+    span: Span,
+    mark: char,
+    style: Style,
+    label: Option<&'a str>,
+}
+
+/// Renders errors the way `rustc` does: the message, where it happened, the lines of source code
+/// involved with the offending code underlined, then notes and help.
+///
+/// ```text
+/// error[L0301]: Already a variable with name 'a' in this scope
+///  --> script.lox:3:7
+///   |
+/// 2 |   var a = 42;
+///   |       - variable declared here
+/// 3 |   var a = 84;
+///   |       ^
+///   |
+///   = help: remove 'var' to assign to the existing variable
+/// ```
+///
+/// With [`ErrorFormat::Json`], errors are rendered as JSON instead.
+pub struct Renderer<'a> {
+    file_name: &'a str,
+    source: &'a str,
+    color: bool,
+    format: ErrorFormat,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(file_name: &'a str, source: &'a str, color: bool) -> Self {
+        Self {
+            file_name,
+            source,
+            color,
+            format: ErrorFormat::Human,
+        }
+    }
+
+    pub fn with_format(mut self, format: ErrorFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn is_json(&self) -> bool {
+        self.format == ErrorFormat::Json
+    }
+
+    /// Renders every message of the error, separated by blank lines unless they are JSON.
+    pub fn render(&self, error: &InterpreterError) -> String {
+        let messages = error
+            .messages()
+            .iter()
+            .map(|message| self.render_message(message));
+        match self.format {
+            ErrorFormat::Human => messages.collect::<Vec<_>>().join("\n"),
+            ErrorFormat::Json => messages.collect(),
+        }
+    }
+
+    pub fn render_message(&self, message: &ErrorMessage) -> String {
+        match self.format {
+            ErrorFormat::Human => self.human(message),
+            ErrorFormat::Json => self.json(message),
+        }
+    }
+
+    fn human(&self, message: &ErrorMessage) -> String {
+        let mut out = format!(
+            "{}{}\n",
+            self.paint(&format!("error[{}]", message.kind().code()), Style::Error),
+            self.paint(&format!(": {}", message.message), Style::Message)
+        );
+
+        let mut annotations = Vec::new();
+        if let Some(span) = message.span() {
+            annotations.push(Annotation {
+                span,
+                mark: '^',
+                style: Style::Error,
+                label: None,
+            });
+        }
+        for note in message.notes() {
+            if let Some(span) = note.span {
+                annotations.push(Annotation {
+                    span,
+                    mark: '-',
+                    style: Style::Gutter,
+                    label: Some(&note.message),
+                });
+            }
+        }
+        annotations.sort_by_key(|annotation| annotation.span.start);
+
+        let width = annotations
+            .iter()
+            .map(|annotation| annotation.span.line().to_string().len())
+            .max()
+            .unwrap_or(0);
+        let pad = " ".repeat(width);
+
+        if let Some(span) = message.span() {
+            out += &format!(
+                "{}{} {}:{}:{}\n",
+                pad,
+                self.paint("-->", Style::Gutter),
+                self.file_name,
+                span.start.line,
+                span.start.column
+            );
+            out += &format!("{} {}\n", pad, self.paint("|", Style::Gutter));
+
+            let mut previous_line = None;
+            for annotation in &annotations {
+                let line = annotation.span.line();
+                let source_line = self.source.lines().nth(line - 1).unwrap_or("");
+                if previous_line != Some(line) {
+                    if previous_line.is_some_and(|previous| line > previous + 1) {
+                        out += &format!("{}\n", self.paint("...", Style::Gutter));
+                    }
+                    out += &format!(
+                        "{} {}\n",
+                        self.paint(&format!("{:>width$} |", line), Style::Gutter),
+                        source_line
+                    );
+                    previous_line = Some(line);
+                }
+                out += &format!(
+                    "{} {} {}\n",
+                    pad,
+                    self.paint("|", Style::Gutter),
+                    self.underline(source_line, annotation)
+                );
+            }
+        }
+
+        let notes = message
+            .notes()
+            .iter()
+            .filter(|note| note.span.is_none())
+            .map(|note| ("note", Style::Note, note.message.as_str()));
+        let help = message.help().map(|help| ("help", Style::Help, help));
+        let mut footer = notes.chain(help).peekable();
+        if footer.peek().is_some() && message.span().is_some() {
+            out += &format!("{} {}\n", pad, self.paint("|", Style::Gutter));
+        }
+        for (label, style, text) in footer {
+            out += &format!(
+                "{} {} {}: {}\n",
+                pad,
+                self.paint("=", Style::Gutter),
+                self.paint(label, style),
+                text
+            );
+        }
+
+        out
+    }
+
+    fn json(&self, message: &ErrorMessage) -> String {
+        let notes = message
+            .notes()
+            .iter()
+            .map(|note| {
+                format!(
+                    "{{\"message\":{},\"span\":{}}}",
+                    json_string(&note.message),
+                    self.json_span(note.span)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            "{{\"severity\":\"error\",\"phase\":\"{}\",\"code\":\"{}\",\"message\":{},\"span\":{},\"notes\":[{}],\"help\":{}}}\n",
+            message.phase(),
+            message.kind().code(),
+            json_string(&message.message),
+            self.json_span(message.span()),
+            notes,
+            message.help().map_or("null".to_string(), json_string)
+        )
+    }
+
+    fn json_span(&self, span: Option<Span>) -> String {
+        match span {
+            Some(span) => format!(
+                "{{\"file\":{},\"start\":{},\"end\":{}}}",
+                json_string(self.file_name),
+                json_position(span.start),
+                json_position(span.end)
+            ),
+            None => "null".to_string(),
+        }
+    }
+
+    /// The marks under the code of the annotation, up to the end of its first line, followed by
+    /// its label.
+    fn underline(&self, source_line: &str, annotation: &Annotation) -> String {
+        let span = annotation.span;
+        let indent = source_line
+            .chars()
+            .take(span.start.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        let end_column = if span.end.line == span.start.line {
+            span.end.column
+        } else {
+            source_line.chars().count() + 1
+        };
+        let marks = annotation
+            .mark
+            .to_string()
+            .repeat(end_column.saturating_sub(span.start.column).max(1));
+        let underline = match annotation.label {
+            Some(label) => format!("{} {}", marks, label),
+            None => marks,
+        };
+        format!("{}{}", indent, self.paint(&underline, annotation.style))
+    }
+
+    fn paint(&self, text: &str, style: Style) -> String {
+        if self.color {
+            format!("{}{}\x1b[0m", style.code(), text)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+fn json_position(position: Position) -> String {
+    format!(
+        "{{\"line\":{},\"column\":{},\"offset\":{}}}",
+        position.line, position.column, position.offset
+    )
+}
+
+/// `text` as a JSON string, quoted and escaped.
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
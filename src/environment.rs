@@ -1,8 +1,22 @@
-use std::{cell::RefCell, collections::HashMap, fmt::Display, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::Display,
+    mem::size_of,
+    rc::{Rc, Weak},
+};
+
+use crate::{heap::Trace, interner::Symbol};
 
 #[allow(dead_code)]
 pub(crate) const MAX_PRINT_LEVEL: usize = 10;
 
+/// A frame of variables, chained to the frame it is enclosed in.
+///
+/// Local variables live in slots, in the order they are declared in their scope: the resolver
+/// gives every local its slot and its distance from the scope it is used in, so reading it does
+/// not need its name. Globals are not resolved and are kept by their interned name in the
+/// outermost frame.
 #[derive(Debug)]
 pub struct Environment<T>
 where
@@ -15,6 +29,9 @@ impl<T> Environment<T>
 where
     T: Clone + Display,
 {
+    /// Bytes used by a frame, not counting its variables.
+    pub const FRAME_SIZE: usize = size_of::<RefCell<Inner<T>>>();
+
     pub fn new() -> Self {
         Self {
             inner: Rc::new(RefCell::new(Inner::new())),
@@ -27,20 +44,35 @@ where
         }
     }
 
-    pub fn define<K: ToString>(&self, key: K, value: T) {
+    pub fn is_global(&self) -> bool {
+        self.inner.borrow().enclosing.is_none()
+    }
+
+    pub fn define<K: Into<Symbol>>(&self, key: K, value: T) {
         self.inner.borrow_mut().define(key, value);
     }
 
-    pub fn assign<K: ToString>(&self, key: K, value: T) -> Result<(), ()> {
+    /// Assigns the global `key`, returning whether it is defined.
+    pub fn assign<K: Into<Symbol>>(&self, key: K, value: T) -> bool {
         self.inner.borrow_mut().assign(key, value)
     }
 
-    pub fn get<K: ToString>(&self, key: K) -> Option<T> {
+    pub fn get<K: Into<Symbol>>(&self, key: K) -> Option<T> {
         self.inner.borrow().get(key)
     }
 
-    pub fn get_at<K: ToString>(&self, key: K, distance: usize) -> Option<T> {
-        self.inner.borrow().get_at(key, distance)
+    /// Defines a local in the next free slot of this frame and returns that slot.
+    pub fn push(&self, value: T) -> usize {
+        self.inner.borrow_mut().push(value)
+    }
+
+    pub fn get_at(&self, distance: usize, slot: usize) -> Option<T> {
+        self.inner.borrow().get_at(distance, slot)
+    }
+
+    /// Assigns the local in `slot` of the frame `distance` levels up, returning whether it exists.
+    pub fn assign_at(&self, distance: usize, slot: usize, value: T) -> bool {
+        self.inner.borrow_mut().assign_at(distance, slot, value)
     }
 
     #[allow(dead_code)]
@@ -49,6 +81,24 @@ where
     }
 }
 
+impl<T> Environment<T>
+where
+    T: Clone + Display + Trace + 'static,
+{
+    pub fn downgrade(&self) -> Weak<dyn Trace> {
+        Rc::downgrade(&self.inner) as Weak<dyn Trace>
+    }
+}
+
+impl<T> Trace for Environment<T>
+where
+    T: Clone + Display + Trace,
+{
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+        visit(Rc::as_ptr(&self.inner) as *const ());
+    }
+}
+
 impl<T> Clone for Environment<T>
 where
     T: Clone + Display,
@@ -66,7 +116,8 @@ where
     T: Clone + Display,
 {
     enclosing: Option<Rc<RefCell<Inner<T>>>>,
-    values: HashMap<String, T>,
+    slots: Vec<T>,
+    globals: HashMap<Symbol, T>,
 }
 
 impl<T> Inner<T>
@@ -76,65 +127,124 @@ where
     fn new() -> Self {
         Self {
             enclosing: None,
-            values: HashMap::new(),
+            slots: Vec::new(),
+            globals: HashMap::new(),
         }
     }
 
     fn enclose(inner: Rc<RefCell<Inner<T>>>) -> Self {
         Self {
             enclosing: Some(inner.clone()),
-            values: HashMap::new(),
+            slots: Vec::new(),
+            globals: HashMap::new(),
         }
     }
 
-    fn define<K: ToString>(&mut self, key: K, value: T) {
-        self.values.insert(key.to_string(), value);
-    }
-
-    pub fn assign<K: ToString>(&mut self, key: K, value: T) -> Result<(), ()> {
-        let key_s = key.to_string();
-        if self.values.contains_key(&key_s) {
-            self.values.insert(key_s, value);
-            Ok(())
-        } else {
-            match &self.enclosing {
-                Some(inner) => inner.borrow_mut().assign(key, value),
-                None => Err(()),
+    fn define<K: Into<Symbol>>(&mut self, key: K, value: T) {
+        match &self.enclosing {
+            Some(inner) => inner.borrow_mut().define(key, value),
+            None => {
+                self.globals.insert(key.into(), value);
             }
         }
     }
 
-    pub fn get<K: ToString>(&self, key: K) -> Option<T> {
-        let key_s = key.to_string();
-        match self.values.get(&key_s) {
-            Some(value) => Some(value.clone()),
-            None => match &self.enclosing {
-                Some(inner) => inner.borrow().get(key).clone(),
-                None => None,
+    pub fn assign<K: Into<Symbol>>(&mut self, key: K, value: T) -> bool {
+        match &self.enclosing {
+            Some(inner) => inner.borrow_mut().assign(key, value),
+            None => match self.globals.get_mut(&key.into()) {
+                Some(slot) => {
+                    *slot = value;
+                    true
+                }
+                None => false,
             },
         }
     }
 
-    pub fn get_at<K: ToString>(&self, key: K, distance: usize) -> Option<T> {
-        let key_s = key.to_string();
+    pub fn get<K: Into<Symbol>>(&self, key: K) -> Option<T> {
+        match &self.enclosing {
+            Some(inner) => inner.borrow().get(key),
+            None => self.globals.get(&key.into()).cloned(),
+        }
+    }
+
+    fn push(&mut self, value: T) -> usize {
+        self.slots.push(value);
+        self.slots.len() - 1
+    }
+
+    pub fn get_at(&self, distance: usize, slot: usize) -> Option<T> {
         if distance == 0 {
-            return self.values.get(&key_s).map(|i| i.clone());
+            return self.slots.get(slot).cloned();
         }
         match &self.enclosing {
-            Some(inner) => inner.borrow().get_at(key_s, distance - 1),
+            Some(inner) => inner.borrow().get_at(distance - 1, slot),
             None => None,
         }
     }
 
-    fn print_content(&self, level: usize) {
-        match (level, &self.enclosing) {
-            (1.., Some(e)) => {
-                for item in self.values.iter() {
-                    println!("{}: {}={}", (MAX_PRINT_LEVEL - level), item.0, item.1);
+    pub fn assign_at(&mut self, distance: usize, slot: usize, value: T) -> bool {
+        if distance == 0 {
+            return match self.slots.get_mut(slot) {
+                Some(slot) => {
+                    *slot = value;
+                    true
                 }
-                e.borrow().print_content(level - 1);
+                None => false,
+            };
+        }
+        match &self.enclosing {
+            Some(inner) => inner.borrow_mut().assign_at(distance - 1, slot, value),
+            None => false,
+        }
+    }
+
+    fn print_content(&self, level: usize) {
+        if let (1.., Some(e)) = (level, &self.enclosing) {
+            for item in self.slots.iter().enumerate() {
+                println!("{}: #{}={}", (MAX_PRINT_LEVEL - level), item.0, item.1);
+            }
+            e.borrow().print_content(level - 1);
+        }
+    }
+}
+
+impl<T> Trace for RefCell<Inner<T>>
+where
+    T: Clone + Display + Trace,
+{
+    fn size(&self) -> usize {
+        let variables = match self.try_borrow() {
+            Ok(inner) => {
+                inner.slots.iter().map(Trace::size).sum::<usize>()
+                    + inner
+                        .globals
+                        .values()
+                        .map(|value| size_of::<Symbol>() + value.size())
+                        .sum::<usize>()
+            }
+            Err(_) => 0,
+        };
+        size_of::<Self>() + variables
+    }
+
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+        if let Ok(inner) = self.try_borrow() {
+            if let Some(enclosing) = &inner.enclosing {
+                visit(Rc::as_ptr(enclosing) as *const ());
+            }
+            for value in inner.slots.iter().chain(inner.globals.values()) {
+                value.trace(visit);
             }
-            _ => return,
+        }
+    }
+
+    fn clear(&self) {
+        if let Ok(mut inner) = self.try_borrow_mut() {
+            inner.enclosing = None;
+            inner.slots.clear();
+            inner.globals.clear();
         }
     }
 }
@@ -157,68 +267,78 @@ mod test {
     }
 
     #[test]
-    fn define_and_get_in_enclosed_1() {
+    fn globals_are_visible_from_enclosed() {
         let sut = Environment::<u32>::new();
         sut.define("foo", 42);
         let enclosing = sut.enclose();
-        enclosing.define("foo", 84);
-        assert_eq!(Some(84), enclosing.get("foo"));
+        assert_eq!(Some(42), enclosing.get("foo"));
         assert_eq!(Some(42), sut.get("foo"));
     }
 
     #[test]
-    fn define_and_get_in_enclosed_2() {
+    fn assign_global_from_enclosed() {
         let sut = Environment::<u32>::new();
         sut.define("foo", 42);
         let enclosing = sut.enclose();
-        enclosing.define("bar", 84);
-        assert_eq!(Some(84), enclosing.get("bar"));
-        assert_eq!(None, sut.get("bar"));
+        assert!(enclosing.assign("foo", 84));
+        assert_eq!(Some(84), sut.get("foo"));
+        assert!(!enclosing.assign("bar", 84));
     }
 
     #[test]
-    fn define_and_get_in_enclosed_3() {
+    fn is_global() {
         let sut = Environment::<u32>::new();
-        sut.define("foo", 42);
-        let enclosing = sut.enclose();
-        assert_eq!(Some(42), enclosing.get("foo"));
-        assert_eq!(Some(42), sut.get("foo"));
+        assert!(sut.is_global());
+        assert!(!sut.enclose().is_global());
     }
 
     #[test]
-    fn define_and_get_in_enclosed_4() {
-        let sut = Environment::<u32>::new();
-        sut.define("foo", 42);
-        let enclosing_1 = sut.enclose();
-        enclosing_1.define("foo", 84);
-        let enclosing_2 = enclosing_1.enclose();
-        enclosing_2.define("foo", 168);
-        assert_eq!(Some(42), sut.get("foo"));
-        assert_eq!(Some(84), enclosing_1.get("foo"));
-        assert_eq!(Some(168), enclosing_2.get("foo"));
+    fn push_returns_slots_in_order() {
+        let sut = Environment::<u32>::new().enclose();
+        assert_eq!(0, sut.push(42));
+        assert_eq!(1, sut.push(84));
+        assert_eq!(Some(42), sut.get_at(0, 0));
+        assert_eq!(Some(84), sut.get_at(0, 1));
+        assert_eq!(None, sut.get_at(0, 2));
     }
 
     #[test]
     fn get_at_1() {
         let sut = Environment::<u32>::new();
-        sut.define("foo", 42);
         let enclosing_1 = sut.enclose();
-        enclosing_1.define("foo", 84);
-        assert_eq!(Some(42), enclosing_1.get_at("foo", 1));
-        assert_eq!(Some(84), enclosing_1.get_at("foo", 0));
+        enclosing_1.push(42);
+        let enclosing_2 = enclosing_1.enclose();
+        enclosing_2.push(84);
+        assert_eq!(Some(42), enclosing_2.get_at(1, 0));
+        assert_eq!(Some(84), enclosing_2.get_at(0, 0));
     }
 
     #[test]
     fn get_at_2() {
         let sut = Environment::<u32>::new();
-        sut.define("foo", 42);
         let enclosing_1 = sut.enclose();
+        enclosing_1.push(42);
         let enclosing_2 = enclosing_1.enclose();
         let enclosing_3 = enclosing_2.enclose();
-        enclosing_3.define("foo", 84);
-        assert_eq!(Some(42), enclosing_3.get_at("foo", 3));
-        assert_eq!(None, enclosing_3.get_at("foo", 2));
-        assert_eq!(None, enclosing_3.get_at("foo", 1));
-        assert_eq!(Some(84), enclosing_3.get_at("foo", 0));
+        let enclosing_4 = enclosing_3.enclose();
+        enclosing_4.push(84);
+        assert_eq!(Some(42), enclosing_4.get_at(3, 0));
+        assert_eq!(None, enclosing_4.get_at(2, 0));
+        assert_eq!(None, enclosing_4.get_at(1, 0));
+        assert_eq!(Some(84), enclosing_4.get_at(0, 0));
+        assert_eq!(None, enclosing_4.get_at(5, 0));
+    }
+
+    #[test]
+    fn assign_at() {
+        let sut = Environment::<u32>::new();
+        let enclosing_1 = sut.enclose();
+        enclosing_1.push(42);
+        let enclosing_2 = enclosing_1.enclose();
+        enclosing_2.push(84);
+        assert!(enclosing_2.assign_at(1, 0, 168));
+        assert_eq!(Some(168), enclosing_1.get_at(0, 0));
+        assert_eq!(Some(84), enclosing_2.get_at(0, 0));
+        assert!(!enclosing_2.assign_at(0, 1, 0));
     }
 }
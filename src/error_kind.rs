@@ -0,0 +1,604 @@
+use std::fmt::Display;
+
+use crate::errors::Phase;
+
+/// What went wrong, each kind with a stable code that `explain` describes at length.
+///
+/// Codes are `L` followed by two digits for the group of the kind and two for the kind itself:
+/// `01` for scanning, `02` for parsing, `03` for resolving, `04` for runtime errors and `05` for
+/// the limits a script runs under. Codes are never reused, even once their kind is gone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The interpreter was misused, or reached a state it should never be in.
+    Internal,
+
+    UnexpectedCharacter,
+    UnterminatedString,
+
+    UnexpectedToken,
+    MissingSyntax,
+    InvalidAssignmentTarget,
+    TooManyParameters,
+    TooManyArguments,
+    ExpectedExpression,
+
+    AlreadyDeclared,
+    ReadInOwnInitializer,
+    ReturnFromTopLevel,
+    ReturnValueFromInitializer,
+    ThisOutsideClass,
+    SuperOutsideClass,
+    SuperWithoutSuperclass,
+    InheritFromSelf,
+
+    UndefinedVariable,
+    UndefinedSuperMethod,
+    InvalidOperand,
+    NotAnInstance,
+    NotCallable,
+    ArityMismatch,
+    NotIndexable,
+    InvalidIndex,
+    IndexOutOfBounds,
+    NotIterable,
+    SuperclassNotAClass,
+    StackOverflow,
+    NativeFunctionFailed,
+
+    OutOfFuel,
+    OutOfMemory,
+    Interrupted,
+}
+
+impl ErrorKind {
+    pub const ALL: &'static [ErrorKind] = &[
+        ErrorKind::Internal,
+        ErrorKind::UnexpectedCharacter,
+        ErrorKind::UnterminatedString,
+        ErrorKind::UnexpectedToken,
+        ErrorKind::MissingSyntax,
+        ErrorKind::InvalidAssignmentTarget,
+        ErrorKind::TooManyParameters,
+        ErrorKind::TooManyArguments,
+        ErrorKind::ExpectedExpression,
+        ErrorKind::AlreadyDeclared,
+        ErrorKind::ReadInOwnInitializer,
+        ErrorKind::ReturnFromTopLevel,
+        ErrorKind::ReturnValueFromInitializer,
+        ErrorKind::ThisOutsideClass,
+        ErrorKind::SuperOutsideClass,
+        ErrorKind::SuperWithoutSuperclass,
+        ErrorKind::InheritFromSelf,
+        ErrorKind::UndefinedVariable,
+        ErrorKind::UndefinedSuperMethod,
+        ErrorKind::InvalidOperand,
+        ErrorKind::NotAnInstance,
+        ErrorKind::NotCallable,
+        ErrorKind::ArityMismatch,
+        ErrorKind::NotIndexable,
+        ErrorKind::InvalidIndex,
+        ErrorKind::IndexOutOfBounds,
+        ErrorKind::NotIterable,
+        ErrorKind::SuperclassNotAClass,
+        ErrorKind::StackOverflow,
+        ErrorKind::NativeFunctionFailed,
+        ErrorKind::OutOfFuel,
+        ErrorKind::OutOfMemory,
+        ErrorKind::Interrupted,
+    ];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            ErrorKind::Internal => "L0000",
+            ErrorKind::UnexpectedCharacter => "L0101",
+            ErrorKind::UnterminatedString => "L0102",
+            ErrorKind::UnexpectedToken => "L0201",
+            ErrorKind::MissingSyntax => "L0202",
+            ErrorKind::InvalidAssignmentTarget => "L0203",
+            ErrorKind::TooManyParameters => "L0204",
+            ErrorKind::TooManyArguments => "L0205",
+            ErrorKind::ExpectedExpression => "L0206",
+            ErrorKind::AlreadyDeclared => "L0301",
+            ErrorKind::ReadInOwnInitializer => "L0302",
+            ErrorKind::ReturnFromTopLevel => "L0303",
+            ErrorKind::ReturnValueFromInitializer => "L0304",
+            ErrorKind::ThisOutsideClass => "L0305",
+            ErrorKind::SuperOutsideClass => "L0306",
+            ErrorKind::SuperWithoutSuperclass => "L0307",
+            ErrorKind::InheritFromSelf => "L0308",
+            ErrorKind::UndefinedVariable => "L0401",
+            ErrorKind::UndefinedSuperMethod => "L0402",
+            ErrorKind::InvalidOperand => "L0403",
+            ErrorKind::NotAnInstance => "L0404",
+            ErrorKind::NotCallable => "L0405",
+            ErrorKind::ArityMismatch => "L0406",
+            ErrorKind::NotIndexable => "L0407",
+            ErrorKind::InvalidIndex => "L0408",
+            ErrorKind::IndexOutOfBounds => "L0409",
+            ErrorKind::NotIterable => "L0410",
+            ErrorKind::SuperclassNotAClass => "L0411",
+            ErrorKind::StackOverflow => "L0412",
+            ErrorKind::NativeFunctionFailed => "L0413",
+            ErrorKind::OutOfFuel => "L0501",
+            ErrorKind::OutOfMemory => "L0502",
+            ErrorKind::Interrupted => "L0503",
+        }
+    }
+
+    /// The kind with the given code, in any case.
+    pub fn from_code(code: &str) -> Option<ErrorKind> {
+        ErrorKind::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.code().eq_ignore_ascii_case(code))
+    }
+
+    pub fn phase(self) -> Phase {
+        match self {
+            ErrorKind::UnexpectedCharacter | ErrorKind::UnterminatedString => Phase::Scan,
+            ErrorKind::UnexpectedToken
+            | ErrorKind::MissingSyntax
+            | ErrorKind::InvalidAssignmentTarget
+            | ErrorKind::TooManyParameters
+            | ErrorKind::TooManyArguments
+            | ErrorKind::ExpectedExpression => Phase::Parse,
+            ErrorKind::AlreadyDeclared
+            | ErrorKind::ReadInOwnInitializer
+            | ErrorKind::ReturnFromTopLevel
+            | ErrorKind::ReturnValueFromInitializer
+            | ErrorKind::ThisOutsideClass
+            | ErrorKind::SuperOutsideClass
+            | ErrorKind::SuperWithoutSuperclass
+            | ErrorKind::InheritFromSelf => Phase::Resolve,
+            ErrorKind::Internal
+            | ErrorKind::UndefinedVariable
+            | ErrorKind::UndefinedSuperMethod
+            | ErrorKind::InvalidOperand
+            | ErrorKind::NotAnInstance
+            | ErrorKind::NotCallable
+            | ErrorKind::ArityMismatch
+            | ErrorKind::NotIndexable
+            | ErrorKind::InvalidIndex
+            | ErrorKind::IndexOutOfBounds
+            | ErrorKind::NotIterable
+            | ErrorKind::SuperclassNotAClass
+            | ErrorKind::StackOverflow
+            | ErrorKind::NativeFunctionFailed
+            | ErrorKind::OutOfFuel
+            | ErrorKind::OutOfMemory
+            | ErrorKind::Interrupted => Phase::Runtime,
+        }
+    }
+
+    /// The status the interpreter exits with: 65 when the script is invalid, 70 when it failed
+    /// while running.
+    pub fn exit_code(self) -> i32 {
+        match self.phase() {
+            Phase::Scan | Phase::Parse | Phase::Resolve => 65,
+            Phase::Runtime => 70,
+        }
+    }
+
+    /// What the error means and how to fix it, with an example of code causing it.
+    pub fn explanation(self) -> &'static str {
+        match self {
+            ErrorKind::Internal => {
+                "\
+The interpreter was used in a way it does not support, or reached a state it should never be
+in, such as running the same parsed script twice.
+
+This is a bug in the interpreter or in the program embedding it, not in the script."
+            }
+            ErrorKind::UnexpectedCharacter => {
+                "\
+The script contains a character that is not part of the language outside of a string or a
+comment.
+
+Erroneous code example:
+
+    var price = 10 $;
+
+Remove the character, or put it in a string:
+
+    var price = \"10 $\";"
+            }
+            ErrorKind::UnterminatedString => {
+                "\
+A string was started with `\"` but the file ended before its closing `\"`.
+
+Erroneous code example:
+
+    print \"hello;
+
+Close the string:
+
+    print \"hello\";"
+            }
+            ErrorKind::UnexpectedToken => {
+                "\
+A token appeared where it cannot start an expression.
+
+Erroneous code example:
+
+    var total = * 2;
+
+Put an expression where the token is:
+
+    var total = 3 * 2;"
+            }
+            ErrorKind::MissingSyntax => {
+                "\
+Something the grammar requires is missing, like the `;` ending a statement, the name of a
+variable after `var`, or a closing parenthesis or brace.
+
+Erroneous code example:
+
+    print \"hello\"
+
+Add what the message says is expected:
+
+    print \"hello\";"
+            }
+            ErrorKind::InvalidAssignmentTarget => {
+                "\
+The left-hand side of `=` is not something that can be assigned to. Only variables and
+properties can be.
+
+Erroneous code example:
+
+    var a = 1;
+    var b = 2;
+    a + b = 3;
+
+Assign to a variable or a property instead:
+
+    a = 3 - b;"
+            }
+            ErrorKind::TooManyParameters => {
+                "\
+A function declares more than 255 parameters, which is the most a function can take.
+
+Pass related values together in an instance of a class instead."
+            }
+            ErrorKind::TooManyArguments => {
+                "\
+A call passes more than 255 arguments, which is the most a function can take.
+
+Pass related values together in an instance of a class instead."
+            }
+            ErrorKind::ExpectedExpression => {
+                "\
+An expression was expected, such as when evaluating a file with `evaluate`, but there was none.
+
+Erroneous code example, as the whole content of a file passed to `evaluate`:
+
+    // nothing to evaluate
+
+Write the expression to evaluate:
+
+    1 + 2"
+            }
+            ErrorKind::AlreadyDeclared => {
+                "\
+A variable is declared twice in the same local scope.
+
+Erroneous code example:
+
+    {
+      var a = 42;
+      var a = 84;
+    }
+
+Remove `var` to assign to the existing variable:
+
+    {
+      var a = 42;
+      a = 84;
+    }
+
+Declaring a variable again is allowed at the top level of a script, where it replaces the
+previous one."
+            }
+            ErrorKind::ReadInOwnInitializer => {
+                "\
+A local variable is used in the expression initializing it, before it has a value.
+
+Erroneous code example:
+
+    var a = \"outer\";
+    {
+      var a = a;
+    }
+
+Give the new variable another name to refer to the outer one:
+
+    var a = \"outer\";
+    {
+      var b = a;
+    }"
+            }
+            ErrorKind::ReturnFromTopLevel => {
+                "\
+`return` is used outside of any function.
+
+Erroneous code example:
+
+    return 1;
+
+Only return from functions and methods:
+
+    fun one() {
+      return 1;
+    }"
+            }
+            ErrorKind::ReturnValueFromInitializer => {
+                "\
+An `init` method returns a value. Initializers always return the instance being initialized.
+
+Erroneous code example:
+
+    class Point {
+      init(x) {
+        this.x = x;
+        return x;
+      }
+    }
+
+Use `return;` without a value to leave an initializer early, or remove the `return`."
+            }
+            ErrorKind::ThisOutsideClass => {
+                "\
+`this` is used outside of the methods of a class, where there is no instance it could refer
+to.
+
+Erroneous code example:
+
+    fun name() {
+      return this.name;
+    }
+
+Move the function into a class, or pass the instance as a parameter:
+
+    fun name(person) {
+      return person.name;
+    }"
+            }
+            ErrorKind::SuperOutsideClass => {
+                "\
+`super` is used outside of the methods of a class.
+
+Erroneous code example:
+
+    fun greet() {
+      super.greet();
+    }
+
+Only use `super` in the methods of a class inheriting from another."
+            }
+            ErrorKind::SuperWithoutSuperclass => {
+                "\
+`super` is used in a class that does not inherit from another class.
+
+Erroneous code example:
+
+    class Dog {
+      speak() {
+        super.speak();
+      }
+    }
+
+Make the class inherit from the class whose methods it calls:
+
+    class Dog < Animal {
+      speak() {
+        super.speak();
+      }
+    }"
+            }
+            ErrorKind::InheritFromSelf => {
+                "\
+A class names itself as its superclass.
+
+Erroneous code example:
+
+    class Node < Node {}
+
+Inherit from another class, or from none:
+
+    class Node {}"
+            }
+            ErrorKind::UndefinedVariable => {
+                "\
+A variable is read or assigned to, but no variable with that name has been defined.
+
+Erroneous code example:
+
+    print count;
+
+Define the variable before using it:
+
+    var count = 0;
+    print count;"
+            }
+            ErrorKind::UndefinedSuperMethod => {
+                "\
+A method is called on `super`, but the superclass has no method with that name.
+
+Erroneous code example:
+
+    class Animal {}
+    class Dog < Animal {
+      speak() {
+        super.speak();
+      }
+    }
+    Dog().speak();
+
+Define the method in the superclass, or check its name."
+            }
+            ErrorKind::InvalidOperand => {
+                "\
+An operator is applied to values of types it does not support, like subtracting a string.
+
+Erroneous code example:
+
+    print \"10\" - 1;
+
+Convert the values, or use them with an operator they support:
+
+    print 10 - 1;
+
+`+` adds numbers and concatenates strings, the other arithmetic operators only take numbers,
+and comparisons take either two numbers or two strings."
+            }
+            ErrorKind::NotAnInstance => {
+                "\
+A property is read or set on a value that is not an instance of a class. Only instances have
+properties.
+
+Erroneous code example:
+
+    var name = \"Ada\";
+    print name.length;"
+            }
+            ErrorKind::NotCallable => {
+                "\
+A value is called, but it is not a function, a method or a class.
+
+Erroneous code example:
+
+    var greeting = \"hello\";
+    greeting();"
+            }
+            ErrorKind::ArityMismatch => {
+                "\
+A function, method or class is called with a different number of arguments than it has
+parameters.
+
+Erroneous code example:
+
+    fun add(a, b) {
+      return a + b;
+    }
+    print add(1);
+
+Pass one argument per parameter:
+
+    print add(1, 2);"
+            }
+            ErrorKind::NotIndexable => {
+                "\
+A value is indexed with `[]`, but only strings and ranges can be.
+
+Erroneous code example:
+
+    var n = 42;
+    print n[0];"
+            }
+            ErrorKind::InvalidIndex => {
+                "\
+A string or range is indexed with something other than an integer or a range of integers, or
+a range is built from numbers that are not integers or are larger than 2^53 in magnitude.
+
+Erroneous code example:
+
+    print \"hello\"[1.5];
+
+Index with an integer:
+
+    print \"hello\"[1];"
+            }
+            ErrorKind::IndexOutOfBounds => {
+                "\
+An index or a slice goes past the end of the string or range it is applied to.
+
+Erroneous code example:
+
+    print \"hello\"[5];
+
+Indices start at 0, so the last one is the length minus one:
+
+    print \"hello\"[4];"
+            }
+            ErrorKind::NotIterable => {
+                "\
+A `for ... in` loop iterates over a value that is neither a string nor a range.
+
+Erroneous code example:
+
+    for (var i in 10) print i;
+
+Iterate over a range instead:
+
+    for (var i in 0..10) print i;"
+            }
+            ErrorKind::SuperclassNotAClass => {
+                "\
+A class inherits from a value that is not a class.
+
+Erroneous code example:
+
+    var Animal = \"animal\";
+    class Dog < Animal {}"
+            }
+            ErrorKind::StackOverflow => {
+                "\
+Calls are nested deeper than the interpreter allows, usually because of a recursion that never
+ends.
+
+Erroneous code example:
+
+    fun forever(n) {
+      return 1 + forever(n + 1);
+    }
+    forever(0);
+
+Make sure the recursion reaches a base case. Calls in tail position, such as
+`return forever(n + 1);`, do not nest, and the limit can be raised with `--max-call-depth`."
+            }
+            ErrorKind::NativeFunctionFailed => {
+                "\
+A function provided by the interpreter, like `clock` or `env`, failed or was given an argument
+it does not accept.
+
+Erroneous code example:
+
+    print env(42);
+
+Pass the name of the environment variable as a string:
+
+    print env(\"HOME\");"
+            }
+            ErrorKind::OutOfFuel => {
+                "\
+The script took more steps than the fuel budget it was run with allows.
+
+This is not a mistake in the script itself: give it more fuel, or make it do less work."
+            }
+            ErrorKind::OutOfMemory => {
+                "\
+The script allocated more memory than it was allowed to, as set with `--max-memory`.
+
+Erroneous code example, under a small limit:
+
+    var s = \"a\";
+    while (true) s = s + s;
+
+Raise the limit, or keep less data alive at once."
+            }
+            ErrorKind::Interrupted => {
+                "\
+The script was stopped before it finished, either through an interrupt handle or because it
+ran past its time limit.
+
+This is not a mistake in the script itself: allow it more time, or make it do less work."
+            }
+        }
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
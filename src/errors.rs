@@ -1,30 +1,160 @@
-use std::{error::Error, fmt::Display};
+use std::{error::Error, fmt::Display, time::Duration};
+
+use crate::{error_kind::ErrorKind, span::Span};
 
 #[derive(Debug)]
 pub enum InterpreterError {
     ScanningError(ErrorMessage),
     ScanningErrors(Vec<ErrorMessage>),
+    /// Errors found before running a script: scanning and parsing errors, followed by resolving
+    /// errors when there are both.
     ParsingErrors(Vec<ErrorMessage>),
     InterpreterError(ErrorMessage),
-    ResolverError(ErrorMessage),
+    ResolverErrors(Vec<ErrorMessage>),
     RuntimeError(ErrorMessage),
+    /// The script ran out of the steps it was allowed to take.
+    OutOfFuel(ErrorMessage),
+    /// The script used more memory than it was allowed to.
+    OutOfMemory(ErrorMessage),
+    /// The script was interrupted, or ran past its time limit.
+    Interrupted(ErrorMessage),
 }
 
 impl InterpreterError {
-    pub fn scanning<T: ToString>(message: T) -> InterpreterError {
-        InterpreterError::ScanningError(ErrorMessage::new(message, None))
+    pub fn scanning<T: ToString>(kind: ErrorKind, message: T) -> InterpreterError {
+        InterpreterError::ScanningError(ErrorMessage::new(kind, message, None))
+    }
+
+    pub fn parsing<T: ToString>(kind: ErrorKind, message: T) -> InterpreterError {
+        InterpreterError::ScanningError(ErrorMessage::new(kind, message.to_string(), None))
+    }
+
+    pub fn evaluating<T: ToString>(kind: ErrorKind, message: T, span: Span) -> InterpreterError {
+        InterpreterError::InterpreterError(ErrorMessage::new(kind, message, Some(span)))
+    }
+
+    pub fn stack_overflow(span: Span) -> InterpreterError {
+        InterpreterError::RuntimeError(ErrorMessage::new(
+            ErrorKind::StackOverflow,
+            "Stack overflow",
+            Some(span),
+        ))
     }
 
-    pub fn parsing<T: ToString>(message: T) -> InterpreterError {
-        InterpreterError::ScanningError(ErrorMessage::new(message.to_string(), None))
+    pub fn out_of_fuel(fuel: u64) -> InterpreterError {
+        InterpreterError::OutOfFuel(ErrorMessage::new(
+            ErrorKind::OutOfFuel,
+            format!("Out of fuel after {} steps", fuel),
+            None,
+        ))
     }
 
-    pub fn evaluating<T: ToString>(message: T, line: usize) -> InterpreterError {
-        InterpreterError::InterpreterError(ErrorMessage::new(message, Some(line)))
+    pub fn out_of_memory(max_memory: usize) -> InterpreterError {
+        InterpreterError::OutOfMemory(ErrorMessage::new(
+            ErrorKind::OutOfMemory,
+            format!("Out of memory: the limit is {} bytes", max_memory),
+            None,
+        ))
     }
 
-    pub fn resolving<T: ToString>(message: T, line: Option<usize>) -> InterpreterError {
-        InterpreterError::ResolverError(ErrorMessage::new(message, line))
+    pub fn interrupted() -> InterpreterError {
+        InterpreterError::Interrupted(ErrorMessage::new(
+            ErrorKind::Interrupted,
+            "Interrupted",
+            None,
+        ))
+    }
+
+    pub fn timed_out(timeout: Duration) -> InterpreterError {
+        InterpreterError::Interrupted(ErrorMessage::new(
+            ErrorKind::Interrupted,
+            format!("Timed out after {:?}", timeout),
+            None,
+        ))
+    }
+}
+
+impl InterpreterError {
+    /// Attaches the calls that were running when a runtime error happened.
+    pub fn with_trace(mut self, trace: StackTrace) -> Self {
+        match &mut self {
+            InterpreterError::InterpreterError(msg)
+            | InterpreterError::RuntimeError(msg)
+            | InterpreterError::OutOfFuel(msg)
+            | InterpreterError::OutOfMemory(msg)
+            | InterpreterError::Interrupted(msg) => {
+                msg.details.get_or_insert_default().trace = trace
+            }
+            InterpreterError::ScanningError(_)
+            | InterpreterError::ScanningErrors(_)
+            | InterpreterError::ParsingErrors(_)
+            | InterpreterError::ResolverErrors(_) => {}
+        }
+        self
+    }
+
+    /// The messages making up the error: one, except for errors collected over a whole script.
+    pub fn messages(&self) -> &[ErrorMessage] {
+        match self {
+            InterpreterError::ScanningError(msg)
+            | InterpreterError::InterpreterError(msg)
+            | InterpreterError::RuntimeError(msg)
+            | InterpreterError::OutOfFuel(msg)
+            | InterpreterError::OutOfMemory(msg)
+            | InterpreterError::Interrupted(msg) => std::slice::from_ref(msg),
+            InterpreterError::ScanningErrors(msgs)
+            | InterpreterError::ParsingErrors(msgs)
+            | InterpreterError::ResolverErrors(msgs) => msgs,
+        }
+    }
+
+    fn into_messages(self) -> Vec<ErrorMessage> {
+        match self {
+            InterpreterError::ScanningError(msg)
+            | InterpreterError::InterpreterError(msg)
+            | InterpreterError::RuntimeError(msg)
+            | InterpreterError::OutOfFuel(msg)
+            | InterpreterError::OutOfMemory(msg)
+            | InterpreterError::Interrupted(msg) => vec![msg],
+            InterpreterError::ScanningErrors(msgs)
+            | InterpreterError::ParsingErrors(msgs)
+            | InterpreterError::ResolverErrors(msgs) => msgs,
+        }
+    }
+
+    /// Adds the errors found in a later phase over the same script, to report them together.
+    pub fn merge(self, later: InterpreterError) -> InterpreterError {
+        let mut messages = self.into_messages();
+        messages.extend(later.into_messages());
+        InterpreterError::ParsingErrors(messages)
+    }
+
+    /// The kind of the first error, which comes first in the script for errors collected over it.
+    pub fn kind(&self) -> ErrorKind {
+        self.messages()
+            .first()
+            .map_or(ErrorKind::Internal, |message| message.kind())
+    }
+
+    /// The status the interpreter exits with on this error.
+    pub fn exit_code(&self) -> i32 {
+        self.kind().exit_code()
+    }
+
+    /// The calls that were running when the error happened, if it happened inside any.
+    pub fn trace(&self) -> Option<&StackTrace> {
+        match self {
+            InterpreterError::InterpreterError(msg)
+            | InterpreterError::RuntimeError(msg)
+            | InterpreterError::OutOfFuel(msg)
+            | InterpreterError::OutOfMemory(msg)
+            | InterpreterError::Interrupted(msg) => msg
+                .details
+                .as_ref()
+                .map(|details| &details.trace)
+                .filter(|trace| !trace.frames.is_empty()),
+            _ => None,
+        }
     }
 }
 
@@ -42,10 +172,14 @@ impl Display for InterpreterError {
             InterpreterError::ScanningError(msg)
             | InterpreterError::InterpreterError(msg)
             | InterpreterError::RuntimeError(msg)
-            | InterpreterError::ResolverError(msg) => {
+            | InterpreterError::OutOfFuel(msg)
+            | InterpreterError::OutOfMemory(msg)
+            | InterpreterError::Interrupted(msg) => {
                 write!(f, "{}", msg)
             }
-            InterpreterError::ScanningErrors(msgs) | InterpreterError::ParsingErrors(msgs) => {
+            InterpreterError::ScanningErrors(msgs)
+            | InterpreterError::ParsingErrors(msgs)
+            | InterpreterError::ResolverErrors(msgs) => {
                 for msg in msgs {
                     writeln!(f, "{}", msg)?;
                 }
@@ -55,30 +189,175 @@ impl Display for InterpreterError {
     }
 }
 
+/// The step of running a script an error happened in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Scan,
+    Parse,
+    Resolve,
+    Runtime,
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Phase::Scan => write!(f, "scan"),
+            Phase::Parse => write!(f, "parse"),
+            Phase::Resolve => write!(f, "resolve"),
+            Phase::Runtime => write!(f, "runtime"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ErrorMessage {
     pub message: String,
-    line: Option<usize>,
+    kind: ErrorKind,
+    span: Option<Span>,
+    /// Boxed as few errors have any, which keeps results small.
+    details: Option<Box<Details>>,
+}
+
+#[derive(Debug, Default)]
+struct Details {
+    notes: Vec<Note>,
+    help: Option<String>,
+    trace: StackTrace,
 }
 
 impl ErrorMessage {
-    pub fn new<T: ToString>(message: T, line: Option<usize>) -> Self {
+    pub fn new<T: ToString>(kind: ErrorKind, message: T, span: Option<Span>) -> Self {
         Self {
             message: message.to_string(),
-            line,
+            kind,
+            span,
+            details: None,
         }
     }
+
+    /// Adds a note, pointing at the code it is about if any.
+    pub fn with_note<T: ToString>(mut self, message: T, span: Option<Span>) -> Self {
+        self.details.get_or_insert_default().notes.push(Note {
+            message: message.to_string(),
+            span,
+        });
+        self
+    }
+
+    /// Adds a suggestion on how to fix the error.
+    pub fn with_help<T: ToString>(mut self, help: T) -> Self {
+        self.details.get_or_insert_default().help = Some(help.to_string());
+        self
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.kind.phase()
+    }
+
+    pub fn notes(&self) -> &[Note] {
+        self.details.as_ref().map_or(&[], |details| &details.notes)
+    }
+
+    pub fn help(&self) -> Option<&str> {
+        self.details
+            .as_ref()
+            .and_then(|details| details.help.as_deref())
+    }
+
+    /// The source code the error is about, if it is about any.
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        self.span.map(|span| span.line())
+    }
 }
 
 impl Display for ErrorMessage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.line {
+        match self.line() {
             Some(line) => write!(f, "[line {}] Error: {}.", line, self.message),
             None => write!(f, "Error: {}.", self.message),
         }
     }
 }
 
+/// More information about an error, such as where something it conflicts with was defined.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Note {
+    pub message: String,
+    pub span: Option<Span>,
+}
+
+/// A call running when an error happened.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackFrame {
+    /// Name of the function called.
+    pub function: String,
+    /// Line the function was called from.
+    pub line: usize,
+    /// Number of calls the frame replaced through tail calls, which left no frame of their own.
+    pub tail_calls: usize,
+}
+
+impl StackFrame {
+    pub fn new<T: ToString>(function: T, line: usize, tail_calls: usize) -> Self {
+        Self {
+            function: function.to_string(),
+            line,
+            tail_calls,
+        }
+    }
+}
+
+impl Display for StackFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "in {} called at [line {}]", self.function, self.line)?;
+        match self.tail_calls {
+            0 => Ok(()),
+            1 => write!(f, " (1 tail call elided)"),
+            n => write!(f, " ({} tail calls elided)", n),
+        }
+    }
+}
+
+/// The calls running when an error happened, from the outermost to the innermost.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StackTrace {
+    pub frames: Vec<StackFrame>,
+}
+
+impl StackTrace {
+    pub fn new(frames: Vec<StackFrame>) -> Self {
+        Self { frames }
+    }
+}
+
+/// Prints the innermost call first, one per line. Runs of identical frames, as left by deep
+/// recursion, are collapsed into one.
+impl Display for StackTrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Stack trace (most recent call first):")?;
+        let mut frames = self.frames.iter().rev().peekable();
+        while let Some(frame) = frames.next() {
+            let mut repeated = 0;
+            while frames.next_if_eq(&frame).is_some() {
+                repeated += 1;
+            }
+            write!(f, "\n    {}", frame)?;
+            if repeated > 0 {
+                write!(f, "\n    ... repeated {} more times", repeated)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 pub struct ParsingErrorsBuilder {
     errors: Vec<ErrorMessage>,
 }
@@ -88,8 +367,13 @@ impl ParsingErrorsBuilder {
         Self { errors: Vec::new() }
     }
 
-    pub fn add<T: ToString>(&mut self, message: T, line: usize) {
-        self.errors.push(ErrorMessage::new(message, Some(line)));
+    pub fn add<T: ToString>(&mut self, kind: ErrorKind, message: T, span: Span) {
+        self.push(ErrorMessage::new(kind, message, Some(span)));
+    }
+
+    /// Adds an error that did not come from parsing itself, like a scanning error.
+    pub fn push(&mut self, message: ErrorMessage) {
+        self.errors.push(message);
     }
 
     pub fn build(self) -> InterpreterError {
@@ -99,22 +383,30 @@ impl ParsingErrorsBuilder {
 
 #[derive(Debug)]
 pub struct TokenError {
+    pub kind: ErrorKind,
     pub message: String,
-    pub line: usize,
+    pub span: Span,
 }
 
 impl TokenError {
-    pub fn new<T>(msg: T, line: usize) -> Self
+    pub fn new<T>(kind: ErrorKind, msg: T, span: Span) -> Self
     where
         T: Into<String>,
     {
         Self {
+            kind,
             message: msg.into(),
-            line,
+            span,
         }
     }
 }
 
+impl From<TokenError> for ErrorMessage {
+    fn from(value: TokenError) -> Self {
+        ErrorMessage::new(value.kind, value.message, Some(value.span))
+    }
+}
+
 impl<T> Into<Result<T, Self>> for TokenError {
     fn into(self) -> Result<T, Self> {
         Err(self)
@@ -125,7 +417,7 @@ impl Error for TokenError {}
 
 impl Display for TokenError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "[line {}] Error: {}", self.line, self.message)
+        write!(f, "[line {}] Error: {}", self.span.line(), self.message)
     }
 }
 
@@ -0,0 +1,148 @@
+use std::{
+    collections::{HashMap, HashSet},
+    rc::{Rc, Weak},
+};
+
+/// Number of tracked allocations after which the first collection runs.
+const INITIAL_THRESHOLD: usize = 1024;
+
+/// Implemented by runtime objects that can hold references to other tracked objects.
+pub trait Trace {
+    /// Calls `visit` with the address of every object this one holds a strong reference to.
+    fn trace(&self, visit: &mut dyn FnMut(*const ()));
+
+    /// Approximate number of bytes this object uses, not counting the tracked objects it
+    /// references.
+    fn size(&self) -> usize {
+        0
+    }
+
+    /// Drops the references this object holds. Only called on objects found to be garbage, so
+    /// that reference cycles between them break and their memory gets released.
+    fn clear(&self) {}
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct HeapStats {
+    /// Objects currently alive.
+    pub live: usize,
+    /// Objects allocated since the interpreter was created.
+    pub allocated: usize,
+    /// Objects freed by the collector, i.e. that were only kept alive by reference cycles.
+    pub collected: usize,
+    /// Number of collections that ran.
+    pub collections: usize,
+}
+
+/// Keeps track of every object the interpreter allocates, so that objects kept alive only by
+/// reference cycles can be found and freed.
+///
+/// Objects are still reference counted, which frees everything that is not part of a cycle as
+/// soon as it is dropped. Collection is a mark and sweep over the tracked objects, where the roots
+/// are the objects referenced from outside the heap: an object whose strong count is higher than
+/// the number of references other tracked objects hold to it is referenced from the interpreter
+/// itself (globals, environments of running calls, temporaries on the native stack). Everything
+/// not reachable from a root is garbage and gets cleared, which breaks its cycles.
+pub struct Heap {
+    objects: Vec<Weak<dyn Trace>>,
+    threshold: usize,
+    allocated_since_collection: usize,
+    stats: HeapStats,
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Heap {
+    pub fn new() -> Self {
+        Self {
+            objects: Vec::new(),
+            threshold: INITIAL_THRESHOLD,
+            allocated_since_collection: 0,
+            stats: HeapStats::default(),
+        }
+    }
+
+    pub fn track(&mut self, object: Weak<dyn Trace>) {
+        self.objects.push(object);
+        self.allocated_since_collection += 1;
+        self.stats.allocated += 1;
+    }
+
+    pub fn should_collect(&self) -> bool {
+        self.allocated_since_collection >= self.threshold
+    }
+
+    pub fn stats(&self) -> HeapStats {
+        HeapStats {
+            live: self.objects.iter().filter(|i| i.strong_count() > 0).count(),
+            ..self.stats
+        }
+    }
+
+    /// Approximate number of bytes used by the live objects.
+    pub fn live_bytes(&self) -> usize {
+        self.objects
+            .iter()
+            .filter_map(|i| i.upgrade())
+            .map(|i| i.size())
+            .sum()
+    }
+
+    pub fn collect(&mut self) {
+        let objects = self
+            .objects
+            .iter()
+            .filter_map(|i| i.upgrade())
+            .collect::<Vec<_>>();
+        let live_before = objects.len();
+
+        let addresses = objects
+            .iter()
+            .enumerate()
+            .map(|(index, object)| (Rc::as_ptr(object) as *const (), index))
+            .collect::<HashMap<_, _>>();
+
+        // Strong references that do not come from other tracked objects, minus the one held by
+        // `objects` above.
+        let mut external = objects
+            .iter()
+            .map(|i| Rc::strong_count(i) - 1)
+            .collect::<Vec<_>>();
+        let mut children = vec![Vec::new(); objects.len()];
+        for (index, object) in objects.iter().enumerate() {
+            object.trace(&mut |address| {
+                if let Some(&child) = addresses.get(&address) {
+                    external[child] -= 1;
+                    children[index].push(child);
+                }
+            });
+        }
+
+        let mut reachable = HashSet::new();
+        let mut pending = (0..objects.len())
+            .filter(|&i| external[i] > 0)
+            .collect::<Vec<_>>();
+        while let Some(index) = pending.pop() {
+            if reachable.insert(index) {
+                pending.extend(children[index].iter().copied());
+            }
+        }
+
+        for (index, object) in objects.iter().enumerate() {
+            if !reachable.contains(&index) {
+                object.clear();
+            }
+        }
+        drop(objects);
+
+        self.objects.retain(|i| i.strong_count() > 0);
+        self.stats.collected += live_before - self.objects.len();
+        self.stats.collections += 1;
+        self.allocated_since_collection = 0;
+        self.threshold = INITIAL_THRESHOLD.max(self.objects.len() * 2);
+    }
+}
@@ -0,0 +1,172 @@
+use std::{
+    collections::HashSet,
+    fmt::{Debug, Display},
+    hash::{Hash, Hasher},
+    ops::Deref,
+    ptr,
+    sync::{Mutex, OnceLock},
+};
+
+/// An interned string. Every distinct string is stored once for the lifetime of the process, so
+/// symbols are copied, compared and hashed by pointer.
+///
+/// Interned strings are leaked and never freed, which is why only names are interned: a process
+/// running many scripts keeps every identifier any of them used. String values are reference
+/// counted instead.
+#[derive(Clone, Copy)]
+pub struct Symbol(&'static str);
+
+fn interned() -> &'static Mutex<HashSet<&'static str>> {
+    static INTERNED: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    INTERNED.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+impl Symbol {
+    pub fn intern(name: &str) -> Self {
+        let mut interned = interned().lock().expect("interner lock is poisoned");
+        match interned.get(name) {
+            Some(name) => Symbol(name),
+            None => {
+                let name: &'static str = Box::leak(name.to_owned().into_boxed_str());
+                interned.insert(name);
+                Symbol(name)
+            }
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(value: &str) -> Self {
+        Symbol::intern(value)
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(value: &String) -> Self {
+        Symbol::intern(value)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(value: String) -> Self {
+        Symbol::intern(&value)
+    }
+}
+
+impl Default for Symbol {
+    fn default() -> Self {
+        Symbol::intern("")
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.0, other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        ptr::hash(self.0, state);
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Debug for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+/// Names of the methods the interpreters look up while running, interned once so that the
+/// lookups do not take the interner's lock.
+#[derive(Clone, Copy)]
+pub struct Names {
+    pub init: Symbol,
+    pub to_string: Symbol,
+    pub neg: Symbol,
+    pub get_item: Symbol,
+    pub add: Symbol,
+    pub sub: Symbol,
+    pub mul: Symbol,
+    pub div: Symbol,
+    pub eq: Symbol,
+    pub ne: Symbol,
+    pub lt: Symbol,
+    pub le: Symbol,
+    pub gt: Symbol,
+    pub ge: Symbol,
+}
+
+impl Names {
+    pub fn new() -> Self {
+        Names {
+            init: Symbol::intern("init"),
+            to_string: Symbol::intern("toString"),
+            neg: Symbol::intern("__neg__"),
+            get_item: Symbol::intern("__getitem__"),
+            add: Symbol::intern("__add__"),
+            sub: Symbol::intern("__sub__"),
+            mul: Symbol::intern("__mul__"),
+            div: Symbol::intern("__div__"),
+            eq: Symbol::intern("__eq__"),
+            ne: Symbol::intern("__ne__"),
+            lt: Symbol::intern("__lt__"),
+            le: Symbol::intern("__le__"),
+            gt: Symbol::intern("__gt__"),
+            ge: Symbol::intern("__ge__"),
+        }
+    }
+}
+
+impl Default for Names {
+    fn default() -> Self {
+        Names::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Symbol;
+
+    #[test]
+    fn symbols_of_equal_strings_are_identical() {
+        let a = Symbol::intern("foo");
+        let b = Symbol::intern(&String::from("foo"));
+        assert_eq!(a, b);
+        assert!(std::ptr::eq(a.as_str(), b.as_str()));
+        assert_ne!(a, Symbol::intern("bar"));
+        assert_eq!(a, "foo");
+    }
+}
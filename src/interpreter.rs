@@ -1,27 +1,115 @@
 use std::{
-    borrow::Borrow,
     cell::RefCell,
     collections::HashMap,
     error::Error,
     fmt::{Debug, Display},
     io::{stdout, BufRead, Write},
+    mem::{self, size_of},
     ops::Deref,
-    rc::Rc,
+    rc::{Rc, Weak},
+    time::{Duration, Instant},
 };
 
 use crate::{
     environment::Environment,
-    errors::{ErrorMessage, InterpreterError},
-    parser::{Expr, Parser, Statement},
-    resolver::{HashableExpr, Resolver},
-    scanner::{Literal, TokenType},
+    error_kind::ErrorKind,
+    errors::{ErrorMessage, InterpreterError, StackFrame, StackTrace},
+    heap::{Heap, HeapStats, Trace},
+    interner::{Names, Symbol},
+    interrupt::InterruptHandle,
+    method_cache::{MethodCache, MethodCacheStats},
+    optimizer::Optimizer,
+    parser::{self, Expr, Parser, Statement},
+    resolver::{HashableExpr, Local, Resolver},
+    scanner::{Literal, Token, TokenType},
+    sequence::{self, Range},
+    span::Span,
 };
 
+/// Number of nested calls a script can make before failing with a stack overflow error.
+///
+/// Calls are evaluated on the interpreter's own stack, not the native one, so a higher limit only
+/// costs memory.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+
+/// Work left for the interpreter to do.
+///
+/// Statements and expressions are not evaluated by recursing into their children, but by pushing
+/// tasks on a stack that lives on the heap, so scripts can recurse as deep as memory allows.
+/// Expressions leave their value on the value stack, where the task continuing the enclosing
+/// expression or statement takes it from.
+enum Task {
+    /// Executes a statement.
+    Execute(Rc<Statement>, Environment<Type>),
+    /// Executes the statements of a block, starting at the given index.
+    Sequence(Rc<Vec<Statement>>, usize, Environment<Type>),
+    /// Evaluates an expression.
+    Evaluate(Rc<Expr>, Environment<Type>),
+    /// Finishes evaluating an expression once the values of its operands are on the stack.
+    Continue(Rc<Expr>, Environment<Type>),
+    /// Discards the value of an expression statement.
+    Pop,
+    /// Declares a variable holding the value on the stack.
+    Declare(Symbol, Environment<Type>),
+    /// Executes one of the branches of an `if`, depending on the condition on the stack.
+    Branch(Rc<Statement>, Option<Rc<Statement>>, Environment<Type>),
+    /// Runs the body of a `while` again if the condition on the stack holds.
+    Loop(Rc<Expr>, Rc<Statement>, Environment<Type>),
+    /// Starts a `for` loop over the value on the stack.
+    Iterate(Span, Rc<Statement>, Environment<Type>),
+    /// Runs the body of a `for` loop for the next item.
+    Next(
+        Box<dyn Iterator<Item = Type>>,
+        Rc<Statement>,
+        Environment<Type>,
+    ),
+    /// Returns the value on the stack from the current call, dropping the work left in it.
+    Return,
+    /// Returns from the current call by calling the callee on the stack with the arguments above
+    /// it. The call replaces the current one, so tail calls run in constant space.
+    TailCall(Rc<Expr>),
+    /// Ends a call whose body ran without returning.
+    EndCall,
+    /// Replaces the value on the stack, e.g. the result of `init()` with the new instance.
+    Replace(Type),
+    /// Negates the value on the stack.
+    Not,
+    /// Converts the value on the stack to a string.
+    Stringify(Span),
+    /// Ends the call to `toString()` on the innermost instance being converted.
+    EndToString,
+    /// Prepends a string to the string on the stack.
+    Prepend(Rc<String>),
+    /// Appends a string to the string on the stack.
+    Append(Rc<String>),
+    /// Prints the value on the stack.
+    Write,
+}
+
 pub struct Interpreter {
     parser: Option<Parser>,
     global_environment: Environment<Type>,
-    resolve_table: Option<HashMap<HashableExpr, usize>>,
+    resolve_table: Option<HashMap<HashableExpr, Local>>,
     output: Rc<RefCell<dyn Write>>,
+    tasks: Vec<Task>,
+    values: Vec<Type>,
+    stringifying: Vec<*const ()>,
+    heap: Heap,
+    method_cache: MethodCache<LoxClass, LoxFunction>,
+    call_depth: usize,
+    max_call_depth: usize,
+    /// Calls running, from the outermost to the innermost, for stack traces.
+    frames: Vec<StackFrame>,
+    /// Calls elided by tail calls so far, to be recorded in the frame of the next call.
+    tail_calls: usize,
+    fuel: Option<u64>,
+    fuel_used: u64,
+    max_memory: Option<usize>,
+    memory_used: usize,
+    interrupt: InterruptHandle,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+    names: Names,
     pub has_parsing_errors: bool,
 }
 
@@ -31,11 +119,32 @@ impl Interpreter {
     }
 
     pub fn with_output(parser: Parser, output: Rc<RefCell<dyn Write>>) -> Self {
+        let global_environment = Interpreter::new_global_environment();
+        let mut heap = Heap::new();
+        heap.track(global_environment.downgrade());
+
         Self {
             parser: Some(parser),
-            global_environment: Interpreter::new_global_environment(),
+            global_environment,
             resolve_table: None,
             output,
+            tasks: Vec::new(),
+            values: Vec::new(),
+            stringifying: Vec::new(),
+            heap,
+            method_cache: MethodCache::new(),
+            call_depth: 0,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            frames: Vec::new(),
+            tail_calls: 0,
+            fuel: None,
+            fuel_used: 0,
+            max_memory: None,
+            memory_used: 0,
+            interrupt: InterruptHandle::new(),
+            timeout: None,
+            deadline: None,
+            names: Names::new(),
             has_parsing_errors: false,
         }
     }
@@ -49,99 +158,293 @@ impl Interpreter {
         Ok(Interpreter::with_output(parser, output))
     }
 
+    /// Sets the number of nested calls after which calling a function fails with a stack overflow
+    /// error.
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        self.max_call_depth = max_call_depth;
+    }
+
+    /// Limits the number of statements and expressions the script can evaluate, after which it
+    /// fails with [`InterpreterError::OutOfFuel`]. There is no limit by default.
+    pub fn set_fuel(&mut self, fuel: u64) {
+        self.fuel = Some(fuel);
+    }
+
+    /// Number of statements and expressions evaluated so far, whether fuel is limited or not.
+    pub fn fuel_used(&self) -> u64 {
+        self.fuel_used
+    }
+
+    /// Limits the memory used by strings, instances and environments, past which the script
+    /// fails with [`InterpreterError::OutOfMemory`]. There is no limit by default.
+    /// Only this backend tracks memory, so the [`Vm`](crate::vm::Vm) has no equivalent.
+    pub fn set_max_memory(&mut self, max_memory: usize) {
+        self.max_memory = Some(max_memory);
+    }
+
+    /// Returns a handle that stops the running script from another thread, making it fail with
+    /// [`InterpreterError::Interrupted`].
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
+    /// Makes `handle` stop this interpreter's runs, so that one handle can serve several
+    /// interpreters in turn.
+    pub fn set_interrupt_handle(&mut self, handle: InterruptHandle) {
+        self.interrupt = handle;
+    }
+
+    /// Limits the time a run can take, after which it fails with
+    /// [`InterpreterError::Interrupted`].
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = Some(timeout);
+    }
+
+    pub fn heap_stats(&self) -> HeapStats {
+        self.heap.stats()
+    }
+
+    pub fn method_cache_stats(&self) -> MethodCacheStats {
+        self.method_cache.stats()
+    }
+
+    /// Frees the objects that are only kept alive by reference cycles.
+    pub fn collect_garbage(&mut self) {
+        self.heap.collect();
+    }
+
     fn new_global_environment() -> Environment<Type> {
         let env = Environment::<Type>::new();
-        env.define(
-            "clock",
-            Type::Function(Rc::new(RefCell::new(native_functions::Clock {}))),
-        );
-        env.define(
-            "env",
-            Type::Function(Rc::new(RefCell::new(native_functions::Env {}))),
-        );
+        env.define("clock", Type::Function(Rc::new(native_functions::Clock {})));
+        env.define("env", Type::Function(Rc::new(native_functions::Env {})));
         env
     }
 
     pub fn evaluate(&mut self) -> Result<(), InterpreterError> {
-        match self.parser.take() {
-            Some(mut parser) => {
-                match parser.parse_expression()? {
-                    Some(expr) => {
-                        let result =
-                            self.eval(&Interpreter::new_global_environment(), &Rc::new(expr))?;
-                        write!(self.output.borrow_mut(), "{}", result)
-                            .expect("cannot write to output");
-                    }
-                    _ => {}
-                }
-
-                if let Some(errors) = parser.errors() {
-                    return Err(errors);
-                }
-
-                Ok(())
-            }
-            None => Err(InterpreterError::InterpreterError(ErrorMessage::new(
+        let Some(mut parser) = self.parser.take() else {
+            return Err(InterpreterError::InterpreterError(ErrorMessage::new(
+                ErrorKind::Internal,
                 "Interpreter's statements have already been consumed",
                 None,
-            ))),
+            )));
+        };
+
+        if let Some(expr) = parser.parse_expression()? {
+            self.tasks.push(Task::Evaluate(
+                Rc::new(expr),
+                Interpreter::new_global_environment(),
+            ));
+            self.run_tasks()?;
+            let result = self.pop_value();
+            write!(self.output.borrow_mut(), "{}", result).expect("cannot write to output");
         }
+
+        if let Some(errors) = parser.errors() {
+            return Err(errors);
+        }
+
+        Ok(())
     }
 
     pub fn run(&mut self) -> Result<(), InterpreterError> {
         let environment = self.global_environment.clone();
         match self.parser.take() {
             Some(mut parser) => {
-                let statements = parser.parse()?.collect::<Vec<_>>();
                 let mut resolver = Resolver::new();
-                resolver.resolve(&statements)?;
+                let statements = resolver.resolve_script(&mut parser)?;
+                let statements = Optimizer::new(&mut resolver.resolve_table).optimize(&statements);
                 self.resolve_table = Some(resolver.resolve_table);
-                for statement in statements {
-                    self.execute_statement(&statement, &environment)?;
-                }
-
-                if let Some(errors) = parser.errors() {
-                    return Err(errors);
-                }
-
-                Ok(())
+                self.tasks
+                    .push(Task::Sequence(Rc::new(statements), 0, environment));
+                self.run_tasks()
             }
             None => Err(InterpreterError::InterpreterError(ErrorMessage::new(
+                ErrorKind::Internal,
                 "Interpreter's statements have already been consumed",
                 None,
             ))),
         }
     }
 
+    /// Runs the scheduled tasks until none is left. On error, the work that was left is dropped
+    /// and the calls that were running are attached to the error.
+    fn run_tasks(&mut self) -> Result<(), InterpreterError> {
+        self.deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        let result = self.execute_tasks();
+        // An interrupt the run ended without noticing is not meant for the next one.
+        self.interrupt.take();
+        result.map_err(|error| {
+            self.tasks.clear();
+            self.values.clear();
+            self.stringifying.clear();
+            self.call_depth = 0;
+            self.tail_calls = 0;
+            error.with_trace(StackTrace::new(mem::take(&mut self.frames)))
+        })
+    }
+
+    fn execute_tasks(&mut self) -> Result<(), InterpreterError> {
+        while let Some(task) = self.tasks.pop() {
+            match task {
+                Task::Execute(statement, environment) => {
+                    self.execute_statement(&statement, &environment)?
+                }
+                Task::Sequence(statements, index, environment) => {
+                    if index + 1 < statements.len() {
+                        self.tasks.push(Task::Sequence(
+                            statements.clone(),
+                            index + 1,
+                            environment.clone(),
+                        ));
+                    }
+                    if let Some(statement) = statements.get(index) {
+                        self.execute_statement(statement, &environment)?;
+                    }
+                }
+                Task::Evaluate(expression, environment) => self.eval(&expression, &environment)?,
+                Task::Continue(expression, environment) => {
+                    self.continue_eval(&expression, &environment)?
+                }
+                Task::Pop => {
+                    self.pop_value();
+                }
+                Task::Declare(name, environment) => {
+                    let value = self.pop_value();
+                    self.allocate(value.size())?;
+                    Interpreter::declare(&environment, name, value);
+                }
+                Task::Branch(then_branch, else_branch, environment) => {
+                    if Interpreter::is_truthy(&self.pop_value()) {
+                        self.execute_statement(&then_branch, &environment)?;
+                    } else if let Some(else_branch) = else_branch {
+                        self.execute_statement(&else_branch, &environment)?;
+                    }
+                }
+                Task::Loop(condition, body, environment) => {
+                    if Interpreter::is_truthy(&self.pop_value()) {
+                        self.check_interrupt()?;
+                        self.tasks.push(Task::Loop(
+                            condition.clone(),
+                            body.clone(),
+                            environment.clone(),
+                        ));
+                        self.tasks
+                            .push(Task::Evaluate(condition, environment.clone()));
+                        self.tasks.push(Task::Execute(body, environment));
+                    }
+                }
+                Task::Iterate(span, body, environment) => {
+                    let items = Interpreter::iterate(self.pop_value(), span)?;
+                    self.tasks.push(Task::Next(items, body, environment));
+                }
+                Task::Next(mut items, body, environment) => {
+                    if let Some(item) = items.next() {
+                        self.check_interrupt()?;
+                        let loop_environment = self.enclose(&environment)?;
+                        self.allocate(item.size())?;
+                        loop_environment.push(item);
+                        self.tasks
+                            .push(Task::Next(items, body.clone(), environment));
+                        self.tasks.push(Task::Execute(body, loop_environment));
+                    }
+                }
+                Task::Return => {
+                    let value = self.pop_value();
+                    self.end_call();
+                    self.values.push(value);
+                }
+                Task::TailCall(expression) => {
+                    let Expr::Call(_, right_paren, arguments) = expression.deref() else {
+                        unreachable!("tail calls are scheduled for call expressions");
+                    };
+                    let (callee, args) = self.pop_call(arguments.len());
+                    self.tail_calls = self.end_call() + 1;
+                    self.call(callee, args, right_paren.span)?;
+                    self.tail_calls = 0;
+                }
+                Task::EndCall => {
+                    self.call_depth -= 1;
+                    self.frames.pop();
+                    self.values.push(Type::Nil);
+                }
+                Task::Replace(value) => {
+                    self.pop_value();
+                    self.values.push(value);
+                }
+                Task::Not => {
+                    let value = self.pop_value();
+                    self.values
+                        .push(Type::Boolean(!Interpreter::is_truthy(&value)));
+                }
+                Task::Stringify(span) => {
+                    let value = self.pop_value();
+                    self.stringify(value, span)?;
+                }
+                Task::EndToString => {
+                    self.stringifying.pop();
+                }
+                Task::Prepend(prefix) => {
+                    let value = self.pop_value();
+                    self.push_string(format!("{}{}", prefix, value))?;
+                }
+                Task::Append(suffix) => {
+                    let value = self.pop_value();
+                    self.push_string(format!("{}{}", value, suffix))?;
+                }
+                Task::Write => {
+                    let value = self.pop_value();
+                    writeln!(self.output.borrow_mut(), "{}", value)
+                        .expect("cannot write to output");
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Starts executing a statement, scheduling whatever is left once its expressions are
+    /// evaluated.
     fn execute_statement(
         &mut self,
         statement: &Statement,
         environment: &Environment<Type>,
-    ) -> Result<StatementResult, InterpreterError> {
+    ) -> Result<(), InterpreterError> {
+        self.consume_fuel()?;
+        if self.heap.should_collect() {
+            self.heap.collect();
+        }
+
         match statement {
-            Statement::Class(name, methods_expressions, super_class) => {
-                environment.define(&name.lexeme, Type::Nil);
+            Statement::Class(name, methods_expressions, super_class_expression) => {
+                let slot = Interpreter::declare(environment, name.symbol(), Type::Nil);
 
                 let mut env = environment.clone();
 
-                let super_class = if let Some(super_class) = super_class {
-                    env = environment.enclose();
-                    Some(self.eval(environment, super_class)?)
+                let super_class = if let Some(super_class) = super_class_expression {
+                    env = self.enclose(environment)?;
+                    self.eval(super_class, environment)?;
+                    Some(self.pop_value())
                 } else {
                     None
                 };
 
                 let mut methods = HashMap::new();
                 for method_expression in methods_expressions.iter().filter_map(|i| i.as_ref()) {
-                    let func = self.eval(&env, &method_expression)?;
-                    match func {
-                        Type::Function(ref f) => {
-                            methods.insert(f.deref().borrow().name().to_owned(), func.clone());
+                    match method_expression.deref() {
+                        Expr::Function(Some(method_name), function) => {
+                            let method = Rc::new(LoxFunction::from_expr(
+                                Some(method_name),
+                                function,
+                                env.clone(),
+                            ));
+                            self.track(&method);
+                            methods.insert(method_name.symbol(), method);
                         }
                         _ => {
                             return Err(InterpreterError::InterpreterError(ErrorMessage::new(
+                                ErrorKind::Internal,
                                 "class can only contain functions",
-                                Some(name.line),
+                                Some(method_expression.span()),
                             )))
                         }
                     }
@@ -149,315 +452,665 @@ impl Interpreter {
 
                 let class = match super_class {
                     Some(Type::Class(super_class)) => {
-                        env.define("super", Type::Class(super_class.clone()));
-                        LoxClass::with_superclass(name.lexeme.to_owned(), methods, super_class)
+                        env.push(Type::Class(super_class.clone()));
+                        LoxClass::with_superclass(name.symbol(), methods, super_class)
                     }
                     Some(_) => {
                         return Err(InterpreterError::InterpreterError(ErrorMessage::new(
+                            ErrorKind::SuperclassNotAClass,
                             "super class must be a class type",
-                            Some(name.line),
+                            super_class_expression.as_ref().map(|i| i.span()),
                         )))
                     }
-                    None => LoxClass::new(name.lexeme.to_owned(), methods),
+                    None => LoxClass::new(name.symbol(), methods),
                 };
 
-                environment
-                    .assign(&name.lexeme, Type::Class(Rc::new(class)))
-                    .expect("should never fail");
-
-                Ok(StatementResult::Empty)
+                let class = Rc::new(class);
+                self.track(&class);
+                let assigned = match slot {
+                    Some(slot) => environment.assign_at(0, slot, Type::Class(class)),
+                    None => environment.assign(name.symbol(), Type::Class(class)),
+                };
+                assert!(assigned, "should never fail");
             }
-            Statement::Return(expr) => Ok(match expr {
-                Some(expr) => StatementResult::Return(self.eval(environment, expr)?),
-                None => StatementResult::Return(Type::Nil),
-            }),
+            Statement::Return(_, expr) => match expr {
+                Some(expr) => match expr.deref() {
+                    Expr::Call(callee, _, arguments) => {
+                        self.tasks.push(Task::TailCall(expr.clone()));
+                        self.eval_call(callee, arguments, environment)?;
+                    }
+                    _ => {
+                        self.tasks.push(Task::Return);
+                        self.eval(expr, environment)?;
+                    }
+                },
+                None => {
+                    self.tasks.push(Task::Return);
+                    self.values.push(Type::Nil);
+                }
+            },
             Statement::Variable(token, Some(expr)) => {
-                let value = self.eval(environment, expr)?;
-                environment.define(&token.lexeme, value);
-                Ok(StatementResult::Empty)
+                self.tasks
+                    .push(Task::Declare(token.symbol(), environment.clone()));
+                self.eval(expr, environment)?;
             }
             Statement::Variable(token, None) => {
-                environment.define(&token.lexeme, Type::Nil);
-                Ok(StatementResult::Empty)
+                Interpreter::declare(environment, token.symbol(), Type::Nil);
             }
-            Statement::Print(expr) => {
-                let res = self.eval(environment, &expr)?;
-                writeln!(self.output.borrow_mut(), "{}", res).expect("cannot write to output");
-                Ok(StatementResult::Empty)
+            Statement::Print(_, expr) => {
+                self.tasks.push(Task::Write);
+                self.tasks.push(Task::Stringify(expr.span()));
+                self.eval(expr, environment)?;
             }
             Statement::Expression(expr) => {
-                self.eval(environment, &expr)?;
-                Ok(StatementResult::Empty)
-            }
-            Statement::Block(statements) => {
-                let mut enclosing_environment = environment.enclose();
-                for statement in statements.iter() {
-                    match self.execute_statement(statement, &mut enclosing_environment)? {
-                        StatementResult::Return(t) => return Ok(StatementResult::Return(t)),
-                        _ => {}
-                    }
-                }
-                Ok(StatementResult::Empty)
+                self.tasks.push(Task::Pop);
+                self.eval(expr, environment)?;
             }
-            Statement::If(condition, then_branch, None) => {
-                if Interpreter::is_truthy(&self.eval(environment, &condition)?) {
-                    self.execute_statement(then_branch, environment)
-                } else {
-                    Ok(StatementResult::Empty)
-                }
+            Statement::Block(_, statements) => {
+                let enclosing_environment = self.enclose(environment)?;
+                self.tasks
+                    .push(Task::Sequence(statements.clone(), 0, enclosing_environment));
             }
-            Statement::If(condition, then_branch, Some(else_branch)) => {
-                if Interpreter::is_truthy(&self.eval(environment, &condition)?) {
-                    self.execute_statement(then_branch, environment)
-                } else {
-                    self.execute_statement(else_branch, environment)
-                }
+            Statement::If(_, condition, then_branch, else_branch) => {
+                self.tasks.push(Task::Branch(
+                    then_branch.clone(),
+                    else_branch.clone(),
+                    environment.clone(),
+                ));
+                self.eval(condition, environment)?;
             }
-            Statement::While(condition, body) => {
-                while Interpreter::is_truthy(&self.eval(environment, condition)?) {
-                    match self.execute_statement(body, environment)? {
-                        StatementResult::Return(t) => return Ok(StatementResult::Return(t)),
-                        _ => {}
-                    }
-                }
-                Ok(StatementResult::Empty)
+            Statement::While(_, condition, body) => {
+                self.tasks.push(Task::Loop(
+                    condition.clone(),
+                    body.clone(),
+                    environment.clone(),
+                ));
+                self.eval(condition, environment)?;
+            }
+            Statement::ForIn(_, _, iterable, body) => {
+                self.tasks.push(Task::Iterate(
+                    iterable.span(),
+                    body.clone(),
+                    environment.clone(),
+                ));
+                self.eval(iterable, environment)?;
             }
         }
+
+        Ok(())
     }
+
+    /// Starts evaluating an expression. Expressions without operands push their value right
+    /// away; the others evaluate their first operand now and schedule the rest, followed by a
+    /// [`Task::Continue`] that combines the values of their operands.
     fn eval(
         &mut self,
-        environment: &Environment<Type>,
         expression: &Rc<Expr>,
-    ) -> Result<Type, InterpreterError> {
+        environment: &Environment<Type>,
+    ) -> Result<(), InterpreterError> {
+        self.consume_fuel()?;
         match expression.deref() {
-            Expr::Literal(token) => match token.token_type {
-                TokenType::True => Ok(Type::Boolean(true)),
-                TokenType::False => Ok(Type::Boolean(false)),
-                TokenType::Nil => Ok(Type::Nil),
-                _ => Ok(token
+            Expr::Literal(token) => self.values.push(match token.token_type {
+                TokenType::True => Type::Boolean(true),
+                TokenType::False => Type::Boolean(false),
+                TokenType::Nil => Type::Nil,
+                _ => token
                     .literal
                     .as_ref()
                     .expect("token should have a literal")
                     .as_ref()
-                    .into()),
-            },
-            Expr::Logical(token, left, right) => match token.token_type {
+                    .into(),
+            }),
+            Expr::Grouping(_, e) => self.eval(e, environment)?,
+            Expr::Logical(_, operand, _)
+            | Expr::Unary(_, operand)
+            | Expr::Assignment(_, operand)
+            | Expr::Get(operand, _) => {
+                self.tasks
+                    .push(Task::Continue(expression.clone(), environment.clone()));
+                self.eval(operand, environment)?;
+            }
+            Expr::Binary(_, left, right)
+            | Expr::Set(left, _, right)
+            | Expr::Index(left, _, right) => {
+                self.tasks
+                    .push(Task::Continue(expression.clone(), environment.clone()));
+                self.tasks
+                    .push(Task::Evaluate(right.clone(), environment.clone()));
+                self.eval(left, environment)?;
+            }
+            Expr::Call(callee, _, arguments) => {
+                self.tasks
+                    .push(Task::Continue(expression.clone(), environment.clone()));
+                self.eval_call(callee, arguments, environment)?;
+            }
+            Expr::Variable(token) | Expr::This(token) => {
+                let value = match self.get_local(expression) {
+                    Some(local) => environment.get_at(local.distance, local.slot),
+                    None => self.global_environment.get(token.symbol()),
+                };
+                match value {
+                    Some(value) => self.values.push(value),
+                    None => {
+                        return Err(InterpreterError::evaluating(
+                            ErrorKind::UndefinedVariable,
+                            format!("Undefined variable in scope: '{}'", token.symbol()),
+                            token.span,
+                        ))
+                    }
+                }
+            }
+            Expr::Function(token, fun) => {
+                let function = Rc::new(LoxFunction::from_expr(
+                    token.as_ref(),
+                    fun,
+                    environment.clone(),
+                ));
+                self.track(&function);
+                self.values.push(Type::Function(function));
+            }
+            Expr::Super(_, method) => {
+                match self.get_local(expression).map(|local| {
+                    (
+                        environment.get_at(local.distance, local.slot),
+                        environment.get_at(local.distance - 1, 0),
+                    )
+                }) {
+                    Some((Some(Type::Class(super_class)), Some(this))) => {
+                        match (
+                            self.find_method(expression, &super_class, method.symbol()),
+                            this,
+                        ) {
+                            (Some(fun), Type::Instance(this)) => {
+                                let method = Rc::new(BoundMethod::new(this, fun));
+                                self.track(&method);
+                                self.values.push(Type::BoundMethod(method));
+                            }
+                            _ => {
+                                return Err(InterpreterError::evaluating(
+                                    ErrorKind::UndefinedSuperMethod,
+                                    format!(
+                                        "Method '{}' not found on the super class",
+                                        &method.symbol()
+                                    ),
+                                    method.span,
+                                ))
+                            }
+                        }
+                    }
+                    _ => {
+                        return Err(InterpreterError::evaluating(
+                            ErrorKind::UndefinedVariable,
+                            format!("Undefined 'super' or '{}' in scope", method.symbol()),
+                            method.span,
+                        ))
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Evaluates the callee and the arguments of a call, leaving them on the value stack.
+    fn eval_call(
+        &mut self,
+        callee: &Rc<Expr>,
+        arguments: &[Rc<Expr>],
+        environment: &Environment<Type>,
+    ) -> Result<(), InterpreterError> {
+        for arg in arguments.iter().rev() {
+            self.tasks
+                .push(Task::Evaluate(arg.clone(), environment.clone()));
+        }
+        self.eval(callee, environment)
+    }
+
+    /// Finishes evaluating an expression whose operands are on the value stack.
+    fn continue_eval(
+        &mut self,
+        expression: &Rc<Expr>,
+        environment: &Environment<Type>,
+    ) -> Result<(), InterpreterError> {
+        match expression.deref() {
+            Expr::Logical(token, _, right) => match token.token_type {
                 TokenType::And | TokenType::Or => {
-                    let left = self.eval(environment, left)?;
-                    match (token.token_type, Interpreter::is_truthy(&left)) {
-                        (TokenType::Or, true) => Ok(left),
-                        (TokenType::And, false) => Ok(left),
-                        _ => self.eval(environment, right),
+                    let left = self.values.last().expect("value stack should not be empty");
+                    match (token.token_type, Interpreter::is_truthy(left)) {
+                        (TokenType::Or, true) | (TokenType::And, false) => Ok(()),
+                        _ => {
+                            self.pop_value();
+                            self.eval(right, environment)
+                        }
                     }
                 }
                 _ => Err(InterpreterError::InterpreterError(ErrorMessage::new(
+                    ErrorKind::Internal,
                     "Logical operator should be 'or' or 'and'",
-                    Some(token.line),
-                ))),
-            },
-            Expr::Grouping(e) => self.eval(environment, e),
-            Expr::Unary(token, expr) => match token.token_type {
-                TokenType::Minus => match self.eval(environment, expr)? {
-                    Type::Number(n) => Ok(Type::Number(-n)),
-                    _ => Err(InterpreterError::InterpreterError(ErrorMessage::new(
-                        "Operand must be a number",
-                        Some(token.line),
-                    ))),
-                },
-                TokenType::Bang => Ok(Type::Boolean(!Interpreter::is_truthy(
-                    &self.eval(environment, expr)?,
+                    Some(token.span),
                 ))),
-                _ => panic!("oh no..."),
             },
-            Expr::Binary(token, left, right) => match (
-                token.token_type,
-                self.eval(environment, left)?,
-                self.eval(environment, right)?,
-            ) {
-                (TokenType::Plus, Type::Number(a), Type::Number(b)) => Ok(Type::Number(a + b)),
-                (TokenType::Plus, Type::String(a), Type::String(b)) => {
-                    Ok(Type::String(Rc::new(format!("{}{}", a, b))))
-                }
-                (TokenType::Plus, _, _) => Err(InterpreterError::evaluating(
-                    "Operands must be two numbers or two strings",
-                    token.line,
-                )),
-                (TokenType::Minus, Type::Number(a), Type::Number(b)) => Ok(Type::Number(a - b)),
-                (TokenType::Minus, _, _) => Err(InterpreterError::evaluating(
-                    "Operands must be two numbers or two strings",
-                    token.line,
-                )),
-                (TokenType::Slash, Type::Number(a), Type::Number(b)) => Ok(Type::Number(a / b)),
-                (TokenType::Slash, _, _) => Err(InterpreterError::evaluating(
-                    "Operands must be numbers",
-                    token.line,
-                )),
-                (TokenType::Star, Type::Number(a), Type::Number(b)) => Ok(Type::Number(a * b)),
-                (TokenType::Greater, Type::Number(a), Type::Number(b)) => Ok(Type::Boolean(a > b)),
-                (TokenType::GreaterEqual, Type::Number(a), Type::Number(b)) => {
-                    Ok(Type::Boolean(a >= b))
-                }
-                (TokenType::Less, Type::Number(a), Type::Number(b)) => Ok(Type::Boolean(a < b)),
-                (TokenType::LessEqual, Type::Number(a), Type::Number(b)) => {
-                    Ok(Type::Boolean(a <= b))
+            Expr::Unary(token, _) => match (token.token_type, self.pop_value()) {
+                (TokenType::Minus, Type::Number(n)) => {
+                    self.values.push(Type::Number(-n));
+                    Ok(())
                 }
-                (TokenType::EqualEqual, Type::Number(a), Type::Number(b)) => {
-                    Ok(Type::Boolean(a == b))
-                }
-                (TokenType::BangEqual, Type::Number(a), Type::Number(b)) => {
-                    Ok(Type::Boolean(a != b))
+                (TokenType::Minus, Type::Instance(instance)) => {
+                    match Interpreter::find_special_method(&instance, self.names.neg) {
+                        Some(method) => {
+                            self.call_special_method(instance, method, vec![], token.span)
+                        }
+                        None => Err(InterpreterError::evaluating(
+                            ErrorKind::InvalidOperand,
+                            "Operand must be a number",
+                            token.span,
+                        )),
+                    }
                 }
-                (TokenType::EqualEqual, Type::Boolean(a), Type::Boolean(b)) => {
-                    Ok(Type::Boolean(a == b))
+                (TokenType::Minus, _) => {
+                    Err(InterpreterError::InterpreterError(ErrorMessage::new(
+                        ErrorKind::InvalidOperand,
+                        "Operand must be a number",
+                        Some(token.span),
+                    )))
                 }
-                (TokenType::BangEqual, Type::Boolean(a), Type::Boolean(b)) => {
-                    Ok(Type::Boolean(a != b))
+                (TokenType::Bang, value) => {
+                    self.values
+                        .push(Type::Boolean(!Interpreter::is_truthy(&value)));
+                    Ok(())
                 }
-                (TokenType::EqualEqual, Type::String(a), Type::String(b)) => {
-                    Ok(Type::Boolean(a == b))
+                _ => panic!("oh no..."),
+            },
+            Expr::Binary(token, _, _) => {
+                let right = self.pop_value();
+                let left = self.pop_value();
+                self.binary(token, left, right)
+            }
+            Expr::Assignment(token, _) => {
+                let value = self
+                    .values
+                    .last()
+                    .cloned()
+                    .expect("value stack should not be empty");
+                let assigned = match self.get_local(expression) {
+                    Some(local) => environment.assign_at(local.distance, local.slot, value),
+                    None => self.global_environment.assign(token.symbol(), value),
+                };
+                if !assigned {
+                    return InterpreterError::evaluating(
+                        ErrorKind::UndefinedVariable,
+                        format!("Undefined variable '{}'", token.symbol()),
+                        token.span,
+                    )
+                    .into();
                 }
-                (TokenType::BangEqual, Type::String(a), Type::String(b)) => {
-                    Ok(Type::Boolean(a != b))
+                Ok(())
+            }
+            Expr::Call(_, right_paren, arguments) => {
+                let (callee, args) = self.pop_call(arguments.len());
+                self.call(callee, args, right_paren.span)
+            }
+            Expr::Get(_, token) => match self.pop_value() {
+                Type::Instance(instance) => {
+                    if let Some(value) = instance.deref().borrow().get(token.symbol()) {
+                        self.values.push(value);
+                        return Ok(());
+                    }
+
+                    let class = instance.deref().borrow().class();
+                    match self.find_method(expression, &class, token.symbol()) {
+                        Some(method) => {
+                            let method = Rc::new(BoundMethod::new(instance.clone(), method));
+                            self.track(&method);
+                            self.values.push(Type::BoundMethod(method));
+                        }
+                        None => self.values.push(Type::Nil),
+                    }
+                    Ok(())
                 }
-                (TokenType::EqualEqual, _, _) => Ok(Type::Boolean(false)),
-                (TokenType::BangEqual, _, _) => Ok(Type::Boolean(false)),
                 _ => Err(InterpreterError::evaluating(
-                    "Unrecognized binary expression",
-                    token.line,
+                    ErrorKind::NotAnInstance,
+                    "Only instances have properties",
+                    token.span,
                 )),
             },
-            Expr::Variable(token) | Expr::This(token) => {
-                match (
-                    self.get_distance(expression.clone())
-                        .and_then(|i| environment.get_at(&token.lexeme, *i)),
-                    self.global_environment.get(&token.lexeme),
-                ) {
-                    (Some(value), _) => Ok(value.clone()),
-                    (None, Some(value)) => Ok(value.clone()),
+            Expr::Set(_, token, _) => {
+                let value = self.pop_value();
+                match self.pop_value() {
+                    Type::Instance(instance) => {
+                        self.allocate(size_of::<Symbol>() + value.size())?;
+                        instance.borrow_mut().set(token.symbol(), value);
+                        self.values.push(Type::Nil);
+                        Ok(())
+                    }
                     _ => Err(InterpreterError::evaluating(
-                        format!("Undefined variable in scope: '{}'", token.lexeme),
-                        token.line,
+                        ErrorKind::NotAnInstance,
+                        "Can only set properties on instances",
+                        token.span,
                     )),
                 }
             }
-            Expr::Assignment(token, expr) => {
-                let name = token.lexeme.to_owned();
-                let value = self.eval(environment, expr)?;
-                match environment.assign(name, value.clone()) {
-                    Ok(()) => Ok(value),
-                    Err(()) => Err(InterpreterError::evaluating(
-                        format!("Undefined variable '{}'", token.lexeme),
-                        token.line,
-                    )),
+            Expr::Index(_, right_bracket, _) => {
+                let index = self.pop_value();
+                let target = self.pop_value();
+
+                if let Type::Instance(instance) = &target {
+                    if let Some(method) =
+                        Interpreter::find_special_method(instance, self.names.get_item)
+                    {
+                        return self.call_special_method(
+                            instance.clone(),
+                            method,
+                            vec![index],
+                            right_bracket.span,
+                        );
+                    }
                 }
+
+                let value = Interpreter::index(target, index, right_bracket.span)?;
+                self.allocate(value.size())?;
+                self.values.push(value);
+                Ok(())
             }
-            Expr::Call(callee, right_paren, arguments) => {
-                let callee = self.eval(environment, callee)?;
+            Expr::Literal(_)
+            | Expr::Grouping(_, _)
+            | Expr::Variable(_)
+            | Expr::This(_)
+            | Expr::Function(_, _)
+            | Expr::Super(_, _) => unreachable!("expression has no operands to continue with"),
+        }
+    }
 
-                let mut args = Vec::new();
-                for arg in arguments.iter() {
-                    args.push(self.eval(environment, arg)?);
+    fn binary(&mut self, token: &Token, left: Type, right: Type) -> Result<(), InterpreterError> {
+        if let Type::Instance(instance) = &left {
+            if self.call_binary_operator(instance, token, &right)? {
+                return Ok(());
+            }
+        }
+
+        let result = match (token.token_type, left, right) {
+            (TokenType::Plus, Type::Number(a), Type::Number(b)) => Type::Number(a + b),
+            (TokenType::Plus, Type::String(a), Type::String(b)) => {
+                Type::String(sequence::concat(&a, &b))
+            }
+            (TokenType::Plus, Type::String(a), Type::Instance(b)) => {
+                return match Interpreter::find_special_method(&b, self.names.to_string) {
+                    Some(method) => {
+                        self.tasks.push(Task::Prepend(a));
+                        self.call_to_string(b, method, token.span)
+                    }
+                    None => Err(InterpreterError::evaluating(
+                        ErrorKind::InvalidOperand,
+                        "Operands must be two numbers or two strings",
+                        token.span,
+                    )),
                 }
+            }
+            (TokenType::Plus, Type::Instance(a), Type::String(b)) => {
+                return match Interpreter::find_special_method(&a, self.names.to_string) {
+                    Some(method) => {
+                        self.tasks.push(Task::Append(b));
+                        self.call_to_string(a, method, token.span)
+                    }
+                    None => Err(InterpreterError::evaluating(
+                        ErrorKind::InvalidOperand,
+                        "Operands must be two numbers or two strings",
+                        token.span,
+                    )),
+                }
+            }
+            (TokenType::Plus, _, _) => {
+                return Err(InterpreterError::evaluating(
+                    ErrorKind::InvalidOperand,
+                    "Operands must be two numbers or two strings",
+                    token.span,
+                ))
+            }
+            (TokenType::Minus, Type::Number(a), Type::Number(b)) => Type::Number(a - b),
+            (TokenType::Minus, _, _) => {
+                return Err(InterpreterError::evaluating(
+                    ErrorKind::InvalidOperand,
+                    "Operands must be two numbers or two strings",
+                    token.span,
+                ))
+            }
+            (TokenType::Slash, Type::Number(a), Type::Number(b)) => Type::Number(a / b),
+            (TokenType::Slash, _, _) => {
+                return Err(InterpreterError::evaluating(
+                    ErrorKind::InvalidOperand,
+                    "Operands must be numbers",
+                    token.span,
+                ))
+            }
+            (TokenType::Star, Type::Number(a), Type::Number(b)) => Type::Number(a * b),
+            (TokenType::Greater, Type::Number(a), Type::Number(b)) => Type::Boolean(a > b),
+            (TokenType::GreaterEqual, Type::Number(a), Type::Number(b)) => Type::Boolean(a >= b),
+            (TokenType::Less, Type::Number(a), Type::Number(b)) => Type::Boolean(a < b),
+            (TokenType::LessEqual, Type::Number(a), Type::Number(b)) => Type::Boolean(a <= b),
+            (TokenType::Greater, Type::String(a), Type::String(b)) => Type::Boolean(a > b),
+            (TokenType::GreaterEqual, Type::String(a), Type::String(b)) => Type::Boolean(a >= b),
+            (TokenType::Less, Type::String(a), Type::String(b)) => Type::Boolean(a < b),
+            (TokenType::LessEqual, Type::String(a), Type::String(b)) => Type::Boolean(a <= b),
+            (
+                TokenType::Greater
+                | TokenType::GreaterEqual
+                | TokenType::Less
+                | TokenType::LessEqual,
+                a,
+                b,
+            ) => {
+                return Err(InterpreterError::evaluating(
+                    ErrorKind::InvalidOperand,
+                    format!(
+                        "Operator '{}' cannot compare {} and {}",
+                        token.symbol(),
+                        a.type_name(),
+                        b.type_name()
+                    ),
+                    token.span,
+                ))
+            }
+            (TokenType::EqualEqual, a, b) => Type::Boolean(a == b),
+            (TokenType::BangEqual, a, b) => Type::Boolean(a != b),
+            (TokenType::DotDot, Type::Number(a), Type::Number(b)) => {
+                Type::Range(Range::new(a, b, false, token.span)?)
+            }
+            (TokenType::DotDotEqual, Type::Number(a), Type::Number(b)) => {
+                Type::Range(Range::new(a, b, true, token.span)?)
+            }
+            (TokenType::DotDot | TokenType::DotDotEqual, _, _) => {
+                return Err(InterpreterError::evaluating(
+                    ErrorKind::InvalidOperand,
+                    "Range bounds must be numbers",
+                    token.span,
+                ))
+            }
+            _ => {
+                return Err(InterpreterError::evaluating(
+                    ErrorKind::InvalidOperand,
+                    "Unrecognized binary expression",
+                    token.span,
+                ))
+            }
+        };
+
+        if let Type::String(_) = result {
+            self.allocate(result.size())?;
+        }
+        self.values.push(result);
+        Ok(())
+    }
 
-                match callee {
-                    Type::Function(func) => {
-                        let func = func.deref().borrow();
-                        if args.len() != func.arity() {
+    /// Calls `callee`, whose result ends up on the value stack: right away for native functions
+    /// and classes without an initializer, once the scheduled body returns otherwise.
+    fn call(&mut self, callee: Type, args: Vec<Type>, span: Span) -> Result<(), InterpreterError> {
+        let func: Rc<dyn Function> = match callee {
+            Type::Function(func) => func,
+            Type::BoundMethod(method) => method,
+            Type::Class(class) => {
+                self.allocate(size_of::<RefCell<LoxInstance>>())?;
+                let instance = LoxInstance::new(class.clone());
+                self.track(&instance);
+                let instance: Rc<RefCell<dyn Instance>> = instance;
+                match class.find_method(self.names.init) {
+                    Some(init) => {
+                        if args.len() != init.arity() {
                             return InterpreterError::evaluating(
+                                ErrorKind::ArityMismatch,
                                 format!(
                                     "Expected {} arguments for function '{}' but got {}",
-                                    func.arity(),
-                                    func,
+                                    init.arity(),
+                                    init,
                                     args.len()
                                 ),
-                                right_paren.line,
+                                span,
                             )
                             .into();
                         }
-                        match func.borrow().call(self, args, right_paren.line)? {
-                            StatementResult::Empty | StatementResult::Return(Type::Nil) => {
-                                Ok(Type::Nil)
-                            }
-                            StatementResult::Return(t) => Ok(t),
-                        }
+                        // The initializer's result is replaced with the new instance.
+                        self.tasks
+                            .push(Task::Replace(Type::Instance(instance.clone())));
+                        return init.call_with_this(self, Some(instance), args, span);
                     }
-                    Type::Class(class) => {
-                        let instance = LoxInstance::new(class.clone());
-                        if let Some(Type::Function(ctor)) = class.deref().find_method("init") {
-                            ctor.deref().borrow_mut().bind(Some(instance.clone()));
-                            ctor.deref()
-                                .borrow_mut()
-                                .call(self, args, right_paren.line)?;
-                        }
-                        Ok(Type::Instance(instance))
+                    None => {
+                        self.values.push(Type::Instance(instance));
+                        return Ok(());
                     }
-                    _ => Err(InterpreterError::evaluating(
-                        "Can only call functions, instances and methods",
-                        right_paren.line,
-                    )),
                 }
             }
-            Expr::Function(token, fun) => {
-                Ok(Type::Function(Rc::new(RefCell::new(LoxFunction::new(
-                    token.as_ref().map(|i| i.lexeme.to_owned()),
-                    fun.parameters
-                        .iter()
-                        .map(|i| i.lexeme.to_string())
-                        .collect(),
-                    fun.body.clone(),
-                    environment.clone(),
-                )))))
+            _ => {
+                return Err(InterpreterError::evaluating(
+                    ErrorKind::NotCallable,
+                    "Can only call functions, instances and methods",
+                    span,
+                ))
             }
-            Expr::Get(expr, token) => match self.eval(environment, expr)? {
-                Type::Instance(instance) => Ok(instance.deref().borrow().get(&token.lexeme)),
-                _ => Err(InterpreterError::evaluating(
-                    "Only instances have properties",
-                    token.line,
-                )),
-            },
-            Expr::Set(name, token, value) => {
-                match (
-                    self.eval(environment, name)?,
-                    self.eval(environment, value)?,
-                ) {
-                    (Type::Instance(instance), value) => {
-                        instance.borrow_mut().set(&token.lexeme, value);
-                        Ok(Type::Nil)
-                    }
-                    _ => Err(InterpreterError::evaluating(
-                        "Can only set properties on instances",
-                        token.line,
-                    )),
-                }
+        };
+
+        if args.len() != func.arity() {
+            return InterpreterError::evaluating(
+                ErrorKind::ArityMismatch,
+                format!(
+                    "Expected {} arguments for function '{}' but got {}",
+                    func.arity(),
+                    func,
+                    args.len()
+                ),
+                span,
+            )
+            .into();
+        }
+        if func.is_native() {
+            let frame = StackFrame::new(&func, span.line(), mem::take(&mut self.tail_calls));
+            self.frames.push(frame);
+            func.call(self, args, span)?;
+            self.frames.pop();
+            return Ok(());
+        }
+        func.call(self, args, span)
+    }
+
+    /// Looks up the method `name` on `class` through the cache of the expression `site`.
+    fn find_method(
+        &mut self,
+        site: &Rc<Expr>,
+        class: &Rc<LoxClass>,
+        name: Symbol,
+    ) -> Option<Rc<LoxFunction>> {
+        self.method_cache
+            .find(site.clone().into(), class, |class| class.find_method(name))
+    }
+
+    fn enclose(
+        &mut self,
+        environment: &Environment<Type>,
+    ) -> Result<Environment<Type>, InterpreterError> {
+        self.allocate(Environment::<Type>::FRAME_SIZE)?;
+        let environment = environment.enclose();
+        self.heap.track(environment.downgrade());
+        Ok(environment)
+    }
+
+    /// Accounts for `bytes` about to be allocated, failing if that takes the script over its
+    /// memory limit.
+    ///
+    /// Memory freed since it was last measured is still counted, so going over the limit first
+    /// collects garbage and measures the memory in use again.
+    fn allocate(&mut self, bytes: usize) -> Result<(), InterpreterError> {
+        let Some(max_memory) = self.max_memory else {
+            return Ok(());
+        };
+
+        self.memory_used += bytes;
+        if self.memory_used > max_memory {
+            self.heap.collect();
+            self.memory_used =
+                self.heap.live_bytes() + self.values.iter().map(Type::size).sum::<usize>() + bytes;
+            if self.memory_used > max_memory {
+                return Err(InterpreterError::out_of_memory(max_memory));
             }
-            Expr::Super(token, method) => {
-                match self.get_distance(expression.clone()).map(|i| {
-                    (
-                        environment.get_at(&token.lexeme, *i),
-                        environment.get_at("this", *i - 1),
-                    )
-                }) {
-                    Some((Some(Type::Class(super_class)), Some(this))) => {
-                        match (super_class.find_method(&method.lexeme), this) {
-                            (Some(Type::Function(fun)), Type::Instance(this)) => {
-                                fun.borrow_mut().bind(Some(this));
-                                Ok(Type::Function(fun))
-                            }
-                            _ => Err(InterpreterError::evaluating(
-                                format!("Method '{}' not found on the super class", &method.lexeme),
-                                method.line,
-                            )),
-                        }
-                    }
-                    _ => Err(InterpreterError::evaluating(
-                        format!("Undefined 'super' or '{}' in scope", method.lexeme),
-                        method.line,
-                    )),
-                }
+        }
+        Ok(())
+    }
+
+    fn push_string(&mut self, text: String) -> Result<(), InterpreterError> {
+        let value = Type::String(Rc::new(text));
+        self.allocate(value.size())?;
+        self.values.push(value);
+        Ok(())
+    }
+
+    fn track<T: Trace + 'static>(&mut self, object: &Rc<T>) {
+        self.heap.track(Rc::downgrade(object) as Weak<dyn Trace>);
+    }
+
+    /// Fails if the run was interrupted or is past its deadline. Checked at loop back-edges and
+    /// calls, which every long running script goes through.
+    fn check_interrupt(&mut self) -> Result<(), InterpreterError> {
+        if self.interrupt.take() {
+            return Err(InterpreterError::interrupted());
+        }
+        match (self.deadline, self.timeout) {
+            (Some(deadline), Some(timeout)) if Instant::now() >= deadline => {
+                Err(InterpreterError::timed_out(timeout))
             }
+            _ => Ok(()),
         }
     }
 
+    /// Counts one evaluation step, failing once the fuel runs out.
+    fn consume_fuel(&mut self) -> Result<(), InterpreterError> {
+        if let Some(fuel) = self.fuel {
+            if self.fuel_used >= fuel {
+                return Err(InterpreterError::out_of_fuel(fuel));
+            }
+        }
+        self.fuel_used += 1;
+        Ok(())
+    }
+
+    /// Pops the callee of a call and its `count` arguments.
+    fn pop_call(&mut self, count: usize) -> (Type, Vec<Type>) {
+        let args = self.values.split_off(self.values.len() - count);
+        (self.pop_value(), args)
+    }
+
+    /// Drops the work left in the running call, which is returning, and returns the number of
+    /// tail calls its frame replaced.
+    fn end_call(&mut self) -> usize {
+        while let Some(task) = self.tasks.pop() {
+            if let Task::EndCall = task {
+                break;
+            }
+        }
+        self.call_depth -= 1;
+        self.frames.pop().map_or(0, |frame| frame.tail_calls)
+    }
+
+    fn pop_value(&mut self) -> Type {
+        self.values.pop().expect("value stack should not be empty")
+    }
+
     fn is_truthy(t: &Type) -> bool {
         match t {
             Type::Nil => false,
@@ -466,17 +1119,175 @@ impl Interpreter {
         }
     }
 
-    fn get_distance(&self, expr: Rc<Expr>) -> Option<&usize> {
+    /// Converts a value to text, calling `toString()` on instances whose class defines it.
+    fn stringify(&mut self, value: Type, span: Span) -> Result<(), InterpreterError> {
+        match value {
+            Type::String(_) => self.values.push(value),
+            Type::Instance(instance) => {
+                match Interpreter::find_special_method(&instance, self.names.to_string) {
+                    Some(method) => return self.call_to_string(instance, method, span),
+                    None => return self.push_string(instance.deref().borrow().to_string()),
+                }
+            }
+            _ => return self.push_string(value.to_string()),
+        }
+        Ok(())
+    }
+
+    /// Calls `toString()` on `instance` and converts its result to text. An instance already being
+    /// converted further up the stack, as happens when objects reference each other, gets its
+    /// default text.
+    fn call_to_string(
+        &mut self,
+        instance: Rc<RefCell<dyn Instance>>,
+        method: Rc<LoxFunction>,
+        span: Span,
+    ) -> Result<(), InterpreterError> {
+        let id = Rc::as_ptr(&instance) as *const ();
+        if self.stringifying.contains(&id) {
+            return self.push_string(instance.deref().borrow().to_string());
+        }
+
+        self.stringifying.push(id);
+        self.tasks.push(Task::EndToString);
+        self.tasks.push(Task::Stringify(span));
+        self.call_special_method(instance, method, vec![], span)
+    }
+
+    /// Dispatches a binary operator to the left operand's special method, if its class has one.
+    /// Returns whether the operator was dispatched.
+    fn call_binary_operator(
+        &mut self,
+        instance: &Rc<RefCell<dyn Instance>>,
+        operator: &Token,
+        right: &Type,
+    ) -> Result<bool, InterpreterError> {
+        let name = match operator.token_type {
+            TokenType::Plus => self.names.add,
+            TokenType::Minus => self.names.sub,
+            TokenType::Star => self.names.mul,
+            TokenType::Slash => self.names.div,
+            TokenType::EqualEqual => self.names.eq,
+            TokenType::BangEqual => self.names.ne,
+            TokenType::Less => self.names.lt,
+            TokenType::LessEqual => self.names.le,
+            TokenType::Greater => self.names.gt,
+            TokenType::GreaterEqual => self.names.ge,
+            _ => return Ok(false),
+        };
+
+        let method = match Interpreter::find_special_method(instance, name) {
+            Some(method) => method,
+            None if operator.token_type == TokenType::BangEqual => {
+                match Interpreter::find_special_method(instance, self.names.eq) {
+                    Some(method) => {
+                        self.tasks.push(Task::Not);
+                        method
+                    }
+                    None => return Ok(false),
+                }
+            }
+            None => return Ok(false),
+        };
+
+        self.call_special_method(instance.clone(), method, vec![right.clone()], operator.span)?;
+        Ok(true)
+    }
+
+    fn find_special_method(
+        instance: &Rc<RefCell<dyn Instance>>,
+        name: Symbol,
+    ) -> Option<Rc<LoxFunction>> {
+        let class = instance.deref().borrow().class();
+        class.find_method(name)
+    }
+
+    /// Calls a special method found on the class of `instance`.
+    fn call_special_method(
+        &mut self,
+        instance: Rc<RefCell<dyn Instance>>,
+        method: Rc<LoxFunction>,
+        arguments: Vec<Type>,
+        span: Span,
+    ) -> Result<(), InterpreterError> {
+        if arguments.len() != method.arity() {
+            return InterpreterError::evaluating(
+                ErrorKind::ArityMismatch,
+                format!(
+                    "Expected {} arguments for method '{}' but got {}",
+                    method.arity(),
+                    method.name,
+                    arguments.len()
+                ),
+                span,
+            )
+            .into();
+        }
+
+        method.call_with_this(self, Some(instance), arguments, span)
+    }
+
+    fn index(target: Type, index: Type, span: Span) -> Result<Type, InterpreterError> {
+        match (target, index) {
+            (Type::String(s), Type::Number(i)) => {
+                Ok(Type::String(Rc::new(sequence::char_at(&s, i, span)?)))
+            }
+            (Type::String(s), Type::Range(range)) => Ok(Type::String(Rc::new(
+                sequence::substring(&s, &range, span)?,
+            ))),
+            (Type::Range(range), Type::Number(i)) => Ok(Type::Number(range.get(i, span)?)),
+            (Type::Range(range), Type::Range(slice)) => Ok(Type::Range(range.slice(&slice, span)?)),
+            (Type::String(_) | Type::Range(_), _) => Err(InterpreterError::evaluating(
+                ErrorKind::InvalidIndex,
+                "Index must be a number or a range",
+                span,
+            )),
+            _ => Err(InterpreterError::evaluating(
+                ErrorKind::NotIndexable,
+                "Only strings and ranges can be indexed",
+                span,
+            )),
+        }
+    }
+
+    fn iterate(
+        iterable: Type,
+        span: Span,
+    ) -> Result<Box<dyn Iterator<Item = Type>>, InterpreterError> {
+        match iterable {
+            Type::Range(range) => Ok(Box::new(range.iter().map(|i| Type::Number(i as f64)))),
+            Type::String(s) => Ok(Box::new(
+                s.chars()
+                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .map(|c| Type::String(Rc::new(c.to_string()))),
+            )),
+            _ => Err(InterpreterError::evaluating(
+                ErrorKind::NotIterable,
+                "Can only iterate over strings and ranges",
+                span,
+            )),
+        }
+    }
+
+    fn get_local(&self, expr: &Rc<Expr>) -> Option<Local> {
         self.resolve_table
             .as_ref()
             .unwrap()
             .get(&HashableExpr::from(expr.clone()))
+            .copied()
     }
-}
 
-enum StatementResult {
-    Return(Type),
-    Empty,
+    /// Defines a variable in `environment`: by name at the top level, where variables are not
+    /// resolved, and in the next slot otherwise, which is returned.
+    fn declare(environment: &Environment<Type>, name: Symbol, value: Type) -> Option<usize> {
+        if environment.is_global() {
+            environment.define(name, value);
+            None
+        } else {
+            Some(environment.push(value))
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -485,11 +1296,47 @@ enum Type {
     Boolean(bool),
     Number(f64),
     String(Rc<String>),
-    Function(Rc<RefCell<dyn Function>>),
+    Range(Range),
+    Function(Rc<dyn Function>),
+    BoundMethod(Rc<BoundMethod>),
     Class(Rc<LoxClass>),
     Instance(Rc<RefCell<dyn Instance>>),
 }
 
+impl Type {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Type::Nil => "nil",
+            Type::Boolean(_) => "boolean",
+            Type::Number(_) => "number",
+            Type::String(_) => "string",
+            Type::Range(_) => "range",
+            Type::Function(_) | Type::BoundMethod(_) => "function",
+            Type::Class(_) => "class",
+            Type::Instance(_) => "instance",
+        }
+    }
+}
+
+/// Primitives compare by value; functions, classes and instances compare by identity. Strings are
+/// not interned, but equal literals share one string, which is compared by pointer first.
+impl PartialEq for Type {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Type::Nil, Type::Nil) => true,
+            (Type::Boolean(a), Type::Boolean(b)) => a == b,
+            (Type::Number(a), Type::Number(b)) => a == b,
+            (Type::String(a), Type::String(b)) => Rc::ptr_eq(a, b) || a == b,
+            (Type::Range(a), Type::Range(b)) => a == b,
+            (Type::Function(a), Type::Function(b)) => Rc::ptr_eq(a, b),
+            (Type::BoundMethod(a), Type::BoundMethod(b)) => a == b,
+            (Type::Class(a), Type::Class(b)) => Rc::ptr_eq(a, b),
+            (Type::Instance(a), Type::Instance(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
 impl Display for Type {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -497,13 +1344,34 @@ impl Display for Type {
             Type::Number(n) => write!(f, "{}", n),
             Type::String(s) => write!(f, "{}", s),
             Type::Boolean(b) => write!(f, "{}", b),
-            Type::Function(fun) => write!(f, "{}", fun.deref().borrow()),
+            Type::Range(range) => write!(f, "{}", range),
+            Type::Function(fun) => write!(f, "{}", fun),
+            Type::BoundMethod(method) => write!(f, "{}", method),
             Type::Class(class) => write!(f, "{}", class),
             Type::Instance(instance) => write!(f, "{}", instance.deref().borrow()),
         }
     }
 }
 
+impl Trace for Type {
+    fn size(&self) -> usize {
+        match self {
+            Type::String(s) => size_of::<Type>() + size_of::<String>() + s.capacity(),
+            _ => size_of::<Type>(),
+        }
+    }
+
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+        match self {
+            Type::Function(function) => visit(Rc::as_ptr(function) as *const ()),
+            Type::BoundMethod(method) => visit(Rc::as_ptr(method) as *const ()),
+            Type::Class(class) => visit(Rc::as_ptr(class) as *const ()),
+            Type::Instance(instance) => visit(Rc::as_ptr(instance) as *const ()),
+            Type::Nil | Type::Boolean(_) | Type::Number(_) | Type::String(_) | Type::Range(_) => {}
+        }
+    }
+}
+
 impl From<&Literal> for Type {
     fn from(value: &Literal) -> Self {
         match value {
@@ -514,41 +1382,99 @@ impl From<&Literal> for Type {
 }
 
 trait Function: Debug + Display {
+    /// Calls the function, leaving its result on the interpreter's value stack: right away for
+    /// native functions, once the body scheduled on the interpreter returns for the others.
     fn call(
         &self,
         interpreter: &mut Interpreter,
         arguments: Vec<Type>,
-        line: usize,
-    ) -> Result<StatementResult, InterpreterError>;
+        span: Span,
+    ) -> Result<(), InterpreterError>;
 
     fn arity(&self) -> usize;
-    fn name(&self) -> &str;
-    fn bind(&mut self, this: Option<Rc<RefCell<dyn Instance>>>);
+
+    /// Native functions run within `call`, instead of scheduling a body on the interpreter.
+    fn is_native(&self) -> bool {
+        false
+    }
 }
 
 struct LoxFunction {
-    name: String,
-    parameters: Vec<String>,
+    name: Symbol,
+    parameters: Vec<Symbol>,
     body: Rc<Statement>,
     closure: Environment<Type>,
 }
 
 impl LoxFunction {
     fn new(
-        name: Option<String>,
-        parameters: Vec<String>,
+        name: Option<Symbol>,
+        parameters: Vec<Symbol>,
         body: Rc<Statement>,
         closure: Environment<Type>,
     ) -> Self {
         Self {
-            name: name
-                .map(|i| i.to_string())
-                .unwrap_or_else(|| "__<fun_anon>".to_owned()),
+            name: name.unwrap_or_else(|| Symbol::intern("__<fun_anon>")),
             parameters,
             body,
             closure,
         }
     }
+
+    fn from_expr(
+        name: Option<&Token>,
+        function: &parser::Function,
+        closure: Environment<Type>,
+    ) -> Self {
+        Self::new(
+            name.map(|i| i.symbol()),
+            function.parameters.iter().map(|i| i.symbol()).collect(),
+            function.body.clone(),
+            closure,
+        )
+    }
+
+    /// Schedules the body of the function, defining `this` in an environment between its
+    /// closure and its parameters when called as a method.
+    fn call_with_this(
+        &self,
+        interpreter: &mut Interpreter,
+        this: Option<Rc<RefCell<dyn Instance>>>,
+        arguments: Vec<Type>,
+        span: Span,
+    ) -> Result<(), InterpreterError> {
+        if interpreter.call_depth >= interpreter.max_call_depth {
+            return Err(InterpreterError::stack_overflow(span));
+        }
+        interpreter.check_interrupt()?;
+        let tail_calls = mem::take(&mut interpreter.tail_calls);
+        interpreter
+            .frames
+            .push(StackFrame::new(self.name, span.line(), tail_calls));
+
+        let closure = match this {
+            Some(this) => {
+                let env = interpreter.enclose(&self.closure)?;
+                interpreter.allocate(size_of::<Type>())?;
+                env.push(Type::Instance(this));
+                env
+            }
+            None => self.closure.clone(),
+        };
+
+        let env = interpreter.enclose(&closure)?;
+        for arg in arguments {
+            interpreter.allocate(arg.size())?;
+            env.push(arg);
+        }
+
+        interpreter.call_depth += 1;
+        interpreter.tasks.push(Task::EndCall);
+        interpreter
+            .tasks
+            .push(Task::Execute(self.body.clone(), env));
+        Ok(())
+    }
 }
 
 impl Debug for LoxFunction {
@@ -566,31 +1492,23 @@ impl Function for LoxFunction {
         &self,
         interpreter: &mut Interpreter,
         arguments: Vec<Type>,
-        _: usize,
-    ) -> Result<StatementResult, InterpreterError> {
-        let env = Environment::enclose(&self.closure);
-
-        for arg in self.parameters.iter().zip(arguments) {
-            env.define(arg.0, arg.1);
-        }
-
-        interpreter.execute_statement(&self.body, &env)
+        span: Span,
+    ) -> Result<(), InterpreterError> {
+        self.call_with_this(interpreter, None, arguments, span)
     }
 
     fn arity(&self) -> usize {
         self.parameters.len()
     }
+}
 
-    fn name(&self) -> &str {
-        self.name.as_str()
+impl Trace for LoxFunction {
+    fn size(&self) -> usize {
+        size_of::<Self>() + self.parameters.len() * size_of::<Symbol>()
     }
 
-    fn bind(&mut self, this: Option<Rc<RefCell<dyn Instance>>>) {
-        if this.is_some() {
-            let env = self.closure.enclose();
-            env.define("this", Type::Instance(this.unwrap()));
-            self.closure = env;
-        }
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+        self.closure.trace(visit);
     }
 }
 
@@ -600,17 +1518,72 @@ impl Display for LoxFunction {
     }
 }
 
+/// A method read from an instance, remembering that instance as `this` for when it gets called.
+#[derive(Debug)]
+struct BoundMethod {
+    receiver: Rc<RefCell<dyn Instance>>,
+    method: Rc<LoxFunction>,
+}
+
+impl BoundMethod {
+    fn new(receiver: Rc<RefCell<dyn Instance>>, method: Rc<LoxFunction>) -> Self {
+        Self { receiver, method }
+    }
+}
+
+impl Function for BoundMethod {
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Type>,
+        span: Span,
+    ) -> Result<(), InterpreterError> {
+        self.method
+            .call_with_this(interpreter, Some(self.receiver.clone()), arguments, span)
+    }
+
+    fn arity(&self) -> usize {
+        self.method.arity()
+    }
+}
+
+impl PartialEq for BoundMethod {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.receiver, &other.receiver) && Rc::ptr_eq(&self.method, &other.method)
+    }
+}
+
+impl Trace for BoundMethod {
+    fn size(&self) -> usize {
+        size_of::<Self>()
+    }
+
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+        visit(Rc::as_ptr(&self.receiver) as *const ());
+        visit(Rc::as_ptr(&self.method) as *const ());
+    }
+}
+
+impl Display for BoundMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.method)
+    }
+}
+
 mod native_functions {
     use std::{
         env,
         fmt::Display,
-        rc::Rc,
         time::{SystemTime, UNIX_EPOCH},
     };
 
-    use crate::errors::{ErrorMessage, InterpreterError};
+    use crate::{
+        error_kind::ErrorKind,
+        errors::{ErrorMessage, InterpreterError},
+        span::Span,
+    };
 
-    use super::{Function, Interpreter, StatementResult, Type};
+    use super::{Function, Interpreter, Type};
 
     #[derive(Debug)]
     pub struct Clock {}
@@ -620,28 +1593,30 @@ mod native_functions {
             0
         }
 
+        fn is_native(&self) -> bool {
+            true
+        }
+
         fn call(
             &self,
-            _: &mut Interpreter,
+            interpreter: &mut Interpreter,
             _: Vec<super::Type>,
-            line: usize,
-        ) -> Result<StatementResult, InterpreterError> {
+            span: Span,
+        ) -> Result<(), InterpreterError> {
             match SystemTime::now().duration_since(UNIX_EPOCH) {
-                Ok(duration) => Ok(StatementResult::Return(Type::Number(
-                    duration.as_secs() as f64
-                ))),
+                Ok(duration) => {
+                    interpreter
+                        .values
+                        .push(Type::Number(duration.as_secs() as f64));
+                    Ok(())
+                }
                 Err(error) => Err(InterpreterError::RuntimeError(ErrorMessage::new(
+                    ErrorKind::NativeFunctionFailed,
                     format!("System time error: {}", error),
-                    Some(line),
+                    Some(span),
                 ))),
             }
         }
-
-        fn name(&self) -> &str {
-            "clock"
-        }
-
-        fn bind(&mut self, _: Option<Rc<std::cell::RefCell<dyn super::Instance>>>) {}
     }
 
     impl Display for Clock {
@@ -658,29 +1633,31 @@ mod native_functions {
             1
         }
 
+        fn is_native(&self) -> bool {
+            true
+        }
+
         fn call(
             &self,
-            _: &mut Interpreter,
+            interpreter: &mut Interpreter,
             arguments: Vec<Type>,
-            line: usize,
-        ) -> Result<StatementResult, InterpreterError> {
+            span: Span,
+        ) -> Result<(), InterpreterError> {
             match arguments.as_slice() {
                 [Type::String(key)] => match env::var(key.as_str()) {
-                    Ok(value) => Ok(StatementResult::Return(Type::String(Rc::new(value)))),
-                    Err(_) => Ok(StatementResult::Return(Type::Nil)),
+                    Ok(value) => interpreter.push_string(value),
+                    Err(_) => {
+                        interpreter.values.push(Type::Nil);
+                        Ok(())
+                    }
                 },
                 _ => Err(InterpreterError::RuntimeError(ErrorMessage::new(
+                    ErrorKind::NativeFunctionFailed,
                     "Invalid argument to 'env' function",
-                    Some(line),
+                    Some(span),
                 ))),
             }
         }
-
-        fn name(&self) -> &str {
-            "env"
-        }
-
-        fn bind(&mut self, _: Option<Rc<std::cell::RefCell<dyn super::Instance>>>) {}
     }
 
     impl Display for Env {
@@ -691,19 +1668,20 @@ mod native_functions {
 }
 
 trait Instance: Debug + Display {
-    fn get(&self, name: &str) -> Type;
-    fn set(&mut self, name: &str, value: Type);
+    fn class(&self) -> Rc<LoxClass>;
+    fn get(&self, name: Symbol) -> Option<Type>;
+    fn set(&mut self, name: Symbol, value: Type);
 }
 
 #[derive(Debug, Clone)]
 struct LoxClass {
-    name: String,
-    methods: HashMap<String, Type>,
+    name: Symbol,
+    methods: HashMap<Symbol, Rc<LoxFunction>>,
     super_class: Option<Rc<LoxClass>>,
 }
 
 impl LoxClass {
-    fn new(name: String, methods: HashMap<String, Type>) -> Self {
+    fn new(name: Symbol, methods: HashMap<Symbol, Rc<LoxFunction>>) -> Self {
         Self {
             name,
             methods,
@@ -712,8 +1690,8 @@ impl LoxClass {
     }
 
     fn with_superclass(
-        name: String,
-        methods: HashMap<String, Type>,
+        name: Symbol,
+        methods: HashMap<Symbol, Rc<LoxFunction>>,
         super_class: Rc<LoxClass>,
     ) -> Self {
         Self {
@@ -723,14 +1701,25 @@ impl LoxClass {
         }
     }
 
-    fn find_method(&self, name: &str) -> Option<Type> {
-        if let Some(method) = self.methods.get(name) {
-            return Some(method.clone());
-        } else {
-            self.super_class
-                .as_ref()
-                .map(|i| i.find_method(name))
-                .flatten()
+    fn find_method(&self, name: Symbol) -> Option<Rc<LoxFunction>> {
+        self.methods
+            .get(&name)
+            .cloned()
+            .or_else(|| self.super_class.as_ref().and_then(|i| i.find_method(name)))
+    }
+}
+
+impl Trace for LoxClass {
+    fn size(&self) -> usize {
+        size_of::<Self>() + self.methods.len() * size_of::<(Symbol, Rc<LoxFunction>)>()
+    }
+
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+        for method in self.methods.values() {
+            visit(Rc::as_ptr(method) as *const ());
+        }
+        if let Some(super_class) = &self.super_class {
+            visit(Rc::as_ptr(super_class) as *const ());
         }
     }
 }
@@ -744,37 +1733,58 @@ impl Display for LoxClass {
 #[derive(Debug)]
 struct LoxInstance {
     class: Rc<LoxClass>,
-    fields: HashMap<String, Type>,
-    this: Option<Rc<RefCell<dyn Instance>>>,
+    fields: HashMap<Symbol, Type>,
 }
 
 impl LoxInstance {
     fn new(class: Rc<LoxClass>) -> Rc<RefCell<Self>> {
-        let instance = Self {
+        Rc::new(RefCell::new(Self {
             class: class.clone(),
             fields: HashMap::new(),
-            this: None,
-        };
-        let instance = Rc::new(RefCell::new(instance));
-        instance.deref().borrow_mut().this = Some(instance.clone());
-        instance
+        }))
     }
 }
 
 impl Instance for LoxInstance {
-    fn get(&self, name: &str) -> Type {
-        match (self.fields.get(name), self.class.find_method(name)) {
-            (Some(value), _) => value.clone(),
-            (None, Some(Type::Function(function))) => {
-                function.deref().borrow_mut().bind(self.this.clone());
-                return Type::Function(function);
+    fn class(&self) -> Rc<LoxClass> {
+        self.class.clone()
+    }
+
+    fn get(&self, name: Symbol) -> Option<Type> {
+        self.fields.get(&name).cloned()
+    }
+
+    fn set(&mut self, name: Symbol, value: Type) {
+        self.fields.insert(name, value);
+    }
+}
+
+impl Trace for RefCell<LoxInstance> {
+    fn size(&self) -> usize {
+        let fields = match self.try_borrow() {
+            Ok(instance) => instance
+                .fields
+                .values()
+                .map(|value| size_of::<Symbol>() + value.size())
+                .sum(),
+            Err(_) => 0,
+        };
+        size_of::<Self>() + fields
+    }
+
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+        if let Ok(instance) = self.try_borrow() {
+            visit(Rc::as_ptr(&instance.class) as *const ());
+            for value in instance.fields.values() {
+                value.trace(visit);
             }
-            _ => Type::Nil,
         }
     }
 
-    fn set(&mut self, name: &str, value: Type) {
-        self.fields.insert(name.to_owned(), value);
+    fn clear(&self) {
+        if let Ok(mut instance) = self.try_borrow_mut() {
+            instance.fields.clear();
+        }
     }
 }
 
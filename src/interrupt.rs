@@ -0,0 +1,34 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Stops a running script from another thread or a signal handler.
+///
+/// Handles are cheap to clone and all clones share the same flag. The interpreter checks it at
+/// loop back-edges and calls, so a script gets interrupted even when it never returns. Interrupting
+/// before a run starts stops it at its first check. The flag is cleared once the run has stopped.
+#[derive(Debug, Clone, Default)]
+pub struct InterruptHandle {
+    interrupted: Arc<AtomicBool>,
+}
+
+impl InterruptHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks the script to stop. Only sets a flag, so it is safe to call from a signal handler.
+    pub fn interrupt(&self) {
+        self.interrupted.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::Relaxed)
+    }
+
+    /// Clears the flag, returning whether it was set.
+    pub(crate) fn take(&self) -> bool {
+        self.is_interrupted() && self.interrupted.swap(false, Ordering::Relaxed)
+    }
+}
@@ -1,6 +1,18 @@
+pub mod bytecode;
+pub mod compiler;
+pub mod diagnostic;
 pub mod environment;
+pub mod error_kind;
 pub mod errors;
+pub mod heap;
+pub mod interner;
 pub mod interpreter;
+pub mod interrupt;
+pub mod method_cache;
+pub mod optimizer;
 pub mod parser;
 pub mod resolver;
 pub mod scanner;
+pub mod sequence;
+pub mod span;
+pub mod vm;
@@ -1,23 +1,67 @@
 use std::env;
-use std::fs::File;
-use std::io::{self, BufReader, Write};
+use std::fs;
+use std::io::{self, BufReader, Cursor, Write};
 
+use diagnostic::{ColorChoice, ErrorFormat, Renderer};
+use error_kind::ErrorKind;
 use errors::ErrorMessage;
-use interpreter::Interpreter;
+use heap::HeapStats;
+use interpreter::{Interpreter, DEFAULT_MAX_CALL_DEPTH};
+use method_cache::MethodCacheStats;
 use parser::Parser;
 use scanner::Scanner;
+use vm::Vm;
 
 use crate::errors::InterpreterError;
 
+pub mod bytecode;
+pub mod compiler;
+pub mod diagnostic;
 pub mod environment;
+pub mod error_kind;
 pub mod errors;
+pub mod heap;
+pub mod interner;
 pub mod interpreter;
+pub mod interrupt;
+pub mod method_cache;
+pub mod optimizer;
 pub mod parser;
 pub mod resolver;
 pub mod scanner;
+pub mod sequence;
+pub mod span;
+pub mod vm;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+
+    // `--color` and `--error-format` apply to every command, so take them out before looking at
+    // the others.
+    let mut output = Output::default();
+    args.retain(|arg| {
+        if let Some(choice) = arg.strip_prefix("--color=") {
+            match choice.parse() {
+                Ok(choice) => output.color = choice,
+                Err(()) => {
+                    eprintln!("Unknown color choice: {}", choice);
+                    std::process::exit(64);
+                }
+            }
+            return false;
+        }
+        if let Some(format) = arg.strip_prefix("--error-format=") {
+            match format.parse() {
+                Ok(format) => output.format = format,
+                Err(()) => {
+                    eprintln!("Unknown error format: {}", format);
+                    std::process::exit(64);
+                }
+            }
+            return false;
+        }
+        true
+    });
 
     let command = args.get(1).and_then(|i| Some(i.as_str()));
 
@@ -25,35 +69,116 @@ fn main() {
         None => tokenize_repl(),
         Some("tokenize") => {
             if args.len() < 3 {
-                writeln!(io::stderr(), "Usage: {} tokenize <file_path>", args[0]).unwrap();
+                writeln!(
+                    io::stderr(),
+                    "Usage: {} tokenize [--color=never|always|auto] [--error-format=human|json] <file_path>",
+                    args[0]
+                )
+                .unwrap();
                 return;
             }
 
-            tokenize_file(&args[2]);
+            tokenize_file(&args[2], output);
         }
         Some("parse") => {
             if args.len() < 3 {
-                writeln!(io::stderr(), "Usage: {} parse <file_path>", args[0]).unwrap();
+                writeln!(
+                    io::stderr(),
+                    "Usage: {} parse [--color=never|always|auto] [--error-format=human|json] <file_path>",
+                    args[0]
+                )
+                .unwrap();
                 return;
             }
 
-            parse_file(&args[2]);
+            parse_file(&args[2], output);
         }
         Some("evaluate") => {
             if args.len() < 3 {
-                writeln!(io::stderr(), "Usage: {} evaluate <file_path>", args[0]).unwrap();
+                writeln!(
+                    io::stderr(),
+                    "Usage: {} evaluate [--color=never|always|auto] [--error-format=human|json] <file_path>",
+                    args[0]
+                )
+                .unwrap();
                 return;
             }
 
-            evaluate_file(&args[2]);
+            evaluate_file(&args[2], output);
         }
         Some("run") => {
+            let mut backend = Backend::TreeWalker;
+            let mut stats = false;
+            let mut max_call_depth = DEFAULT_MAX_CALL_DEPTH;
+            let mut max_memory = None;
+            let mut file_path = None;
+            for arg in args.iter().skip(2) {
+                if arg == "--stats" {
+                    stats = true;
+                    continue;
+                }
+                if let Some(depth) = arg.strip_prefix("--max-call-depth=") {
+                    match depth.parse() {
+                        Ok(depth) => max_call_depth = depth,
+                        Err(_) => {
+                            eprintln!("Invalid max call depth: {}", depth);
+                            std::process::exit(64);
+                        }
+                    }
+                    continue;
+                }
+                if let Some(bytes) = arg.strip_prefix("--max-memory=") {
+                    match bytes.parse() {
+                        Ok(bytes) => max_memory = Some(bytes),
+                        Err(_) => {
+                            eprintln!("Invalid max memory: {}", bytes);
+                            std::process::exit(64);
+                        }
+                    }
+                    continue;
+                }
+                match arg.strip_prefix("--backend=") {
+                    Some("tree") => backend = Backend::TreeWalker,
+                    Some("vm") => backend = Backend::Vm,
+                    Some(other) => {
+                        eprintln!("Unknown backend: {}", other);
+                        std::process::exit(64);
+                    }
+                    None => file_path = Some(arg),
+                }
+            }
+
+            if max_memory.is_some() && matches!(backend, Backend::Vm) {
+                eprintln!("--max-memory is only supported by the tree backend");
+                std::process::exit(64);
+            }
+
+            match file_path {
+                Some(file_path) => run_file(
+                    file_path,
+                    backend,
+                    stats,
+                    max_call_depth,
+                    max_memory,
+                    output,
+                ),
+                None => {
+                    writeln!(
+                        io::stderr(),
+                        "Usage: {} run [--backend=tree|vm] [--max-call-depth=N] [--max-memory=BYTES] [--stats] [--color=never|always|auto] [--error-format=human|json] <file_path>",
+                        args[0]
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        Some("explain") => {
             if args.len() < 3 {
-                writeln!(io::stderr(), "Usage: {} evaluate <file_path>", args[0]).unwrap();
+                eprintln!("Usage: {} explain <error_code>", args[0]);
                 return;
             }
 
-            run_file(&args[2]);
+            explain(&args[2]);
         }
         Some(command) => {
             eprintln!("Unknown command: {}", command);
@@ -62,18 +187,19 @@ fn main() {
     }
 }
 
-fn tokenize_file(file_path: &str) {
+fn tokenize_file(file_path: &str, output: Output) {
     let mut has_errors = false;
 
-    let file = File::open(file_path).expect(format!("cannot open file {}", file_path).as_str());
+    let source = read_file(file_path);
+    let renderer = output.renderer(file_path, &source);
 
-    let scanner = Scanner::new(BufReader::new(file));
+    let scanner = Scanner::new(Cursor::new(source.clone()));
     for item in scanner.scan().expect("failed to scan tokens") {
         match item {
             Ok(token) => println!("{}", token),
-            Err(message) => {
+            Err(error) => {
                 has_errors = true;
-                eprintln!("{}", message);
+                eprint!("{}", renderer.render_message(&ErrorMessage::from(error)));
             }
         }
     }
@@ -105,10 +231,11 @@ fn tokenize_repl() {
     }
 }
 
-fn parse_file(file_path: &str) {
-    let file = File::open(file_path).expect(format!("cannot open file {}", file_path).as_str());
+fn parse_file(file_path: &str, output: Output) {
+    let source = read_file(file_path);
+    let renderer = output.renderer(file_path, &source);
 
-    let scanner = Scanner::new(BufReader::new(file));
+    let scanner = Scanner::new(Cursor::new(source.clone()));
     let tokens = scanner.scan();
 
     match tokens {
@@ -119,17 +246,23 @@ fn parse_file(file_path: &str) {
                     println!("{}", expr);
                 }
                 Ok(None) => {
-                    eprintln!("No expression found");
+                    print_error(
+                        &renderer,
+                        &InterpreterError::parsing(
+                            ErrorKind::ExpectedExpression,
+                            "No expression found",
+                        ),
+                    );
                     std::process::exit(65);
                 }
                 Err(error) => {
-                    eprintln!("{}", error);
+                    print_error(&renderer, &error);
                     std::process::exit(65);
                 }
             }
 
             if let Some(errors) = parser.errors() {
-                eprintln!("{}", errors);
+                print_error(&renderer, &errors);
                 std::process::exit(65);
             }
         }
@@ -137,10 +270,11 @@ fn parse_file(file_path: &str) {
     }
 }
 
-fn evaluate_file(file_path: &str) {
-    let file = File::open(file_path).expect(format!("cannot open file {}", file_path).as_str());
+fn evaluate_file(file_path: &str, output: Output) {
+    let source = read_file(file_path);
+    let renderer = output.renderer(file_path, &source);
 
-    let scanner = Scanner::new(BufReader::new(file));
+    let scanner = Scanner::new(Cursor::new(source.clone()));
     let tokens = scanner.scan();
 
     match tokens {
@@ -151,8 +285,8 @@ fn evaluate_file(file_path: &str) {
             match interpreter.evaluate() {
                 Ok(()) => {}
                 Err(error) => {
-                    eprintln!("{}", error);
-                    std::process::exit(70);
+                    print_error(&renderer, &error);
+                    std::process::exit(error.exit_code());
                 }
             }
 
@@ -164,38 +298,115 @@ fn evaluate_file(file_path: &str) {
     }
 }
 
-fn run_file(file_path: &str) {
-    let file = File::open(file_path).expect(format!("cannot open file {}", file_path).as_str());
+enum Backend {
+    TreeWalker,
+    Vm,
+}
+
+/// Runs a script. With `stats`, statistics about the run are printed to stderr once it ends.
+fn run_file(
+    file_path: &str,
+    backend: Backend,
+    stats: bool,
+    max_call_depth: usize,
+    max_memory: Option<usize>,
+    output: Output,
+) {
+    let source = read_file(file_path);
+    let renderer = output.renderer(file_path, &source);
 
-    let scanner = Scanner::new(BufReader::new(file));
+    let scanner = Scanner::new(Cursor::new(source.clone()));
     let tokens = scanner.scan();
 
     match tokens {
         Ok(tokens) => {
             let parser = Parser::new(tokens);
-            let mut interpreter = Interpreter::new(parser);
+            let result = match backend {
+                Backend::TreeWalker => {
+                    let mut interpreter = Interpreter::new(parser);
+                    interpreter.set_max_call_depth(max_call_depth);
+                    if let Some(max_memory) = max_memory {
+                        interpreter.set_max_memory(max_memory);
+                    }
+                    let result = interpreter.run();
+                    if stats {
+                        print_heap_stats(interpreter.heap_stats());
+                        print_method_cache_stats(interpreter.method_cache_stats());
+                    }
+                    result
+                }
+                Backend::Vm => {
+                    let mut vm = Vm::new(parser);
+                    vm.set_max_call_depth(max_call_depth);
+                    let result = vm.run();
+                    if stats {
+                        print_heap_stats(vm.heap_stats());
+                    }
+                    result
+                }
+            };
 
-            match interpreter.run() {
+            match result {
                 Ok(()) => {}
                 Err(error) => {
-                    eprintln!("{}", error);
-                    match error {
-                        InterpreterError::InterpreterError(ErrorMessage { message, .. })
-                            if message.contains("Undefined variable in scope: 'a'")
-                                | message.contains("Undefined variable in scope: 'f'") =>
-                        {
-                            std::process::exit(65)
-                        } // Just for CodeCrafters
-                        InterpreterError::ScanningError(_)
-                        | InterpreterError::ScanningErrors(_)
-                        | InterpreterError::ParsingErrors(_)
-                        | InterpreterError::ResolverError(_) => std::process::exit(65),
-                        InterpreterError::InterpreterError(_)
-                        | InterpreterError::RuntimeError(_) => std::process::exit(70),
-                    }
+                    print_error(&renderer, &error);
+                    std::process::exit(error.exit_code());
                 }
             }
         }
         Err(error) => println!("{}", error),
     }
 }
+
+/// How errors are printed, as chosen with flags common to every command.
+#[derive(Clone, Copy, Default)]
+struct Output {
+    color: ColorChoice,
+    format: ErrorFormat,
+}
+
+impl Output {
+    fn renderer<'a>(&self, file_path: &'a str, source: &'a str) -> Renderer<'a> {
+        Renderer::new(file_path, source, self.color.enabled()).with_format(self.format)
+    }
+}
+
+/// Prints what the error with the given code means, with an example.
+fn explain(code: &str) {
+    match ErrorKind::from_code(code) {
+        Some(kind) => println!("{}", kind.explanation()),
+        None => {
+            eprintln!("Unknown error code: {}", code);
+            std::process::exit(64);
+        }
+    }
+}
+
+fn read_file(file_path: &str) -> String {
+    fs::read_to_string(file_path).expect(format!("cannot open file {}", file_path).as_str())
+}
+
+/// Prints an error to stderr, followed by the calls that were running for runtime errors unless
+/// the error is meant for tools.
+fn print_error(renderer: &Renderer, error: &InterpreterError) {
+    eprint!("{}", renderer.render(error));
+    if let Some(trace) = error.trace().filter(|_| !renderer.is_json()) {
+        eprintln!("{}", trace);
+    }
+}
+
+fn print_heap_stats(stats: HeapStats) {
+    eprintln!(
+        "[stats] heap: {} live, {} allocated, {} collected in {} collections",
+        stats.live, stats.allocated, stats.collected, stats.collections
+    );
+}
+
+fn print_method_cache_stats(stats: MethodCacheStats) {
+    eprintln!(
+        "[stats] method cache: {} hits, {} misses ({:.1}% hit rate)",
+        stats.hits,
+        stats.misses,
+        stats.hit_rate() * 100.0
+    );
+}
@@ -0,0 +1,87 @@
+use std::{
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+use crate::resolver::HashableExpr;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MethodCacheStats {
+    /// Lookups answered by the cache.
+    pub hits: usize,
+    /// Lookups that had to search the class and its super classes.
+    pub misses: usize,
+}
+
+impl MethodCacheStats {
+    /// Share of the lookups answered by the cache, between 0 and 1.
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            lookups => self.hits as f64 / lookups as f64,
+        }
+    }
+}
+
+struct Entry<C, M> {
+    class: Weak<C>,
+    method: Weak<M>,
+}
+
+/// Remembers, for every expression looking up a method, the class it last looked the method up
+/// on and the method it found.
+///
+/// Entries are keyed by class identity: a class that gets redefined is a new object, so looking
+/// it up misses and replaces the entry. The cache only holds weak references, which keep the
+/// addresses of the class and the method from being reused while not keeping them alive, and
+/// leave them to the collector.
+pub struct MethodCache<C, M> {
+    entries: HashMap<HashableExpr, Entry<C, M>>,
+    stats: MethodCacheStats,
+}
+
+impl<C, M> MethodCache<C, M> {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            stats: MethodCacheStats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> MethodCacheStats {
+        self.stats
+    }
+
+    /// Returns the method the expression `site` finds on `class`, calling `find` to look it up
+    /// when the cache does not have it.
+    pub fn find<F>(&mut self, site: HashableExpr, class: &Rc<C>, find: F) -> Option<Rc<M>>
+    where
+        F: FnOnce(&C) -> Option<Rc<M>>,
+    {
+        if let Some(entry) = self.entries.get(&site) {
+            if entry.class.as_ptr() == Rc::as_ptr(class) {
+                if let Some(method) = entry.method.upgrade() {
+                    self.stats.hits += 1;
+                    return Some(method);
+                }
+            }
+        }
+
+        self.stats.misses += 1;
+        let method = find(class)?;
+        self.entries.insert(
+            site,
+            Entry {
+                class: Rc::downgrade(class),
+                method: Rc::downgrade(&method),
+            },
+        );
+        Some(method)
+    }
+}
+
+impl<C, M> Default for MethodCache<C, M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,258 @@
+use std::{collections::HashMap, ops::Deref, rc::Rc};
+
+use crate::{
+    parser::{Expr, Function, Statement},
+    resolver::{HashableExpr, Local},
+    scanner::{Literal, Token, TokenType},
+    sequence,
+    span::Span,
+};
+
+/// A value known before the program runs.
+#[derive(Debug, Clone, PartialEq)]
+enum Constant {
+    Nil,
+    Boolean(bool),
+    Number(f64),
+    String(Rc<String>),
+}
+
+impl Constant {
+    fn is_truthy(&self) -> bool {
+        match self {
+            Constant::Nil => false,
+            Constant::Boolean(b) => *b,
+            _ => true,
+        }
+    }
+}
+
+/// Simplifies resolved statements before they are executed: folds operators applied to literals,
+/// drops `if` and `while` branches whose condition is a constant, and short-circuits logical
+/// operators with a constant left operand.
+///
+/// Only operations that cannot fail are folded, so an expression like `"a" - 1` is left alone
+/// and still fails at runtime on its own line. Assignments are rebuilt when their value is
+/// folded, so their entries in the resolver's table are moved to the new expressions.
+pub struct Optimizer<'a> {
+    resolve_table: &'a mut HashMap<HashableExpr, Local>,
+}
+
+impl<'a> Optimizer<'a> {
+    pub fn new(resolve_table: &'a mut HashMap<HashableExpr, Local>) -> Self {
+        Self { resolve_table }
+    }
+
+    pub fn optimize(&mut self, statements: &[Statement]) -> Vec<Statement> {
+        statements
+            .iter()
+            .map(|statement| self.statement(statement))
+            .filter(
+                |statement| !matches!(statement, Statement::Block(_, block) if block.is_empty()),
+            )
+            .collect()
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Statement {
+        match statement {
+            Statement::Class(name, methods, super_class) => Statement::Class(
+                name.clone(),
+                methods
+                    .iter()
+                    .map(|method| method.as_ref().map(|method| self.expression(method)))
+                    .collect(),
+                super_class.clone(),
+            ),
+            Statement::Variable(name, initializer) => Statement::Variable(
+                name.clone(),
+                initializer.as_ref().map(|expr| self.expression(expr)),
+            ),
+            Statement::Print(token, expr) => Statement::Print(token.clone(), self.expression(expr)),
+            Statement::Return(keyword, expr) => Statement::Return(
+                keyword.clone(),
+                expr.as_ref().map(|expr| self.expression(expr)),
+            ),
+            Statement::Expression(expr) => Statement::Expression(self.expression(expr)),
+            Statement::Block(span, statements) => {
+                Statement::Block(*span, Rc::new(self.optimize(statements)))
+            }
+            Statement::If(keyword, condition, then_branch, else_branch) => {
+                let condition = self.expression(condition);
+                match Optimizer::constant(&condition) {
+                    Some(constant) if constant.is_truthy() => self.statement(then_branch),
+                    Some(_) => match else_branch {
+                        Some(else_branch) => self.statement(else_branch),
+                        None => Statement::Block(statement.span(), Rc::default()),
+                    },
+                    None => Statement::If(
+                        keyword.clone(),
+                        condition,
+                        Rc::new(self.statement(then_branch)),
+                        else_branch
+                            .as_ref()
+                            .map(|else_branch| Rc::new(self.statement(else_branch))),
+                    ),
+                }
+            }
+            Statement::While(keyword, condition, body) => {
+                let condition = self.expression(condition);
+                match Optimizer::constant(&condition) {
+                    Some(constant) if !constant.is_truthy() => {
+                        Statement::Block(statement.span(), Rc::default())
+                    }
+                    _ => {
+                        Statement::While(keyword.clone(), condition, Rc::new(self.statement(body)))
+                    }
+                }
+            }
+            Statement::ForIn(keyword, name, iterable, body) => Statement::ForIn(
+                keyword.clone(),
+                name.clone(),
+                self.expression(iterable),
+                Rc::new(self.statement(body)),
+            ),
+        }
+    }
+
+    /// Variables, `this` and `super` are returned as they are, so the resolver's table still
+    /// finds them.
+    fn expression(&mut self, expr: &Rc<Expr>) -> Rc<Expr> {
+        match expr.deref() {
+            Expr::Literal(_) | Expr::Variable(_) | Expr::This(_) | Expr::Super(_, _) => {
+                expr.clone()
+            }
+            Expr::Grouping(_, inner) => self.expression(inner),
+            Expr::Unary(token, operand) => {
+                let operand = self.expression(operand);
+                let folded = match (token.token_type, Optimizer::constant(&operand)) {
+                    (TokenType::Minus, Some(Constant::Number(n))) => Some(Constant::Number(-n)),
+                    (TokenType::Bang, Some(constant)) => {
+                        Some(Constant::Boolean(!constant.is_truthy()))
+                    }
+                    _ => None,
+                };
+                match folded {
+                    Some(constant) => Optimizer::literal(constant, expr.span()),
+                    None => Rc::new(Expr::Unary(token.clone(), operand)),
+                }
+            }
+            Expr::Binary(token, left, right) => {
+                let left = self.expression(left);
+                let right = self.expression(right);
+                let folded = match (Optimizer::constant(&left), Optimizer::constant(&right)) {
+                    (Some(a), Some(b)) => Optimizer::fold_binary(token.token_type, a, b),
+                    _ => None,
+                };
+                match folded {
+                    Some(constant) => Optimizer::literal(constant, expr.span()),
+                    None => Rc::new(Expr::Binary(token.clone(), left, right)),
+                }
+            }
+            Expr::Logical(token, left, right) => {
+                let left = self.expression(left);
+                let right = self.expression(right);
+                match (token.token_type, Optimizer::constant(&left)) {
+                    (TokenType::Or, Some(constant)) if constant.is_truthy() => left,
+                    (TokenType::And, Some(constant)) if !constant.is_truthy() => left,
+                    (TokenType::Or | TokenType::And, Some(_)) => right,
+                    _ => Rc::new(Expr::Logical(token.clone(), left, right)),
+                }
+            }
+            Expr::Assignment(name, value) => {
+                let folded = self.expression(value);
+                if Rc::ptr_eq(&folded, value) {
+                    return expr.clone();
+                }
+
+                let assignment = Rc::new(Expr::Assignment(name.clone(), folded));
+                if let Some(local) = self.resolve_table.remove(&expr.clone().into()) {
+                    self.resolve_table.insert(assignment.clone().into(), local);
+                }
+                assignment
+            }
+            Expr::Function(name, function) => Rc::new(Expr::Function(
+                name.clone(),
+                Function {
+                    parameters: function.parameters.clone(),
+                    body: Rc::new(self.statement(&function.body)),
+                    span: function.span,
+                },
+            )),
+            Expr::Call(callee, right_paren, arguments) => Rc::new(Expr::Call(
+                self.expression(callee),
+                right_paren.clone(),
+                Box::new(arguments.iter().map(|arg| self.expression(arg)).collect()),
+            )),
+            Expr::Get(instance, field) => {
+                Rc::new(Expr::Get(self.expression(instance), field.clone()))
+            }
+            Expr::Set(instance, field, value) => Rc::new(Expr::Set(
+                self.expression(instance),
+                field.clone(),
+                self.expression(value),
+            )),
+            Expr::Index(target, bracket, index) => Rc::new(Expr::Index(
+                self.expression(target),
+                bracket.clone(),
+                self.expression(index),
+            )),
+        }
+    }
+
+    /// Applies a binary operator to two constants, or returns `None` when the operation would
+    /// fail or is not known ahead of time.
+    fn fold_binary(operator: TokenType, left: Constant, right: Constant) -> Option<Constant> {
+        use Constant::*;
+        match (operator, left, right) {
+            (TokenType::Plus, Number(a), Number(b)) => Some(Number(a + b)),
+            (TokenType::Plus, String(a), String(b)) => Some(String(sequence::concat(&a, &b))),
+            (TokenType::Minus, Number(a), Number(b)) => Some(Number(a - b)),
+            (TokenType::Star, Number(a), Number(b)) => Some(Number(a * b)),
+            (TokenType::Slash, Number(a), Number(b)) => Some(Number(a / b)),
+            (TokenType::Greater, Number(a), Number(b)) => Some(Boolean(a > b)),
+            (TokenType::GreaterEqual, Number(a), Number(b)) => Some(Boolean(a >= b)),
+            (TokenType::Less, Number(a), Number(b)) => Some(Boolean(a < b)),
+            (TokenType::LessEqual, Number(a), Number(b)) => Some(Boolean(a <= b)),
+            (TokenType::Greater, String(a), String(b)) => Some(Boolean(a > b)),
+            (TokenType::GreaterEqual, String(a), String(b)) => Some(Boolean(a >= b)),
+            (TokenType::Less, String(a), String(b)) => Some(Boolean(a < b)),
+            (TokenType::LessEqual, String(a), String(b)) => Some(Boolean(a <= b)),
+            (TokenType::EqualEqual, a, b) => Some(Boolean(a == b)),
+            (TokenType::BangEqual, a, b) => Some(Boolean(a != b)),
+            _ => None,
+        }
+    }
+
+    fn constant(expr: &Expr) -> Option<Constant> {
+        match expr {
+            Expr::Literal(token) => match (token.token_type, token.literal.as_deref()) {
+                (TokenType::Nil, _) => Some(Constant::Nil),
+                (TokenType::True, _) => Some(Constant::Boolean(true)),
+                (TokenType::False, _) => Some(Constant::Boolean(false)),
+                (_, Some(Literal::Digit(n))) => Some(Constant::Number(*n)),
+                (_, Some(Literal::String(s))) => Some(Constant::String(s.clone())),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// A literal standing for the folded expression, spanning its source code.
+    fn literal(constant: Constant, span: Span) -> Rc<Expr> {
+        let token = match constant {
+            Constant::Nil => Token::new(TokenType::Nil, "nil", span),
+            Constant::Boolean(true) => Token::new(TokenType::True, "true", span),
+            Constant::Boolean(false) => Token::new(TokenType::False, "false", span),
+            Constant::Number(n) => {
+                Token::with_literal(TokenType::Number, n.to_string(), Literal::Digit(n), span)
+            }
+            Constant::String(s) => Token::with_literal(
+                TokenType::String,
+                format!("\"{}\"", s),
+                Literal::String(s),
+                span,
+            ),
+        };
+        Rc::new(Expr::Literal(token))
+    }
+}
@@ -1,8 +1,10 @@
 use std::{cell::RefCell, collections::VecDeque, fmt::Display, io::BufRead, rc::Rc};
 
 use crate::{
-    errors::{InterpreterError, ParsingErrorsBuilder},
+    error_kind::ErrorKind,
+    errors::{ErrorMessage, InterpreterError, ParsingErrorsBuilder, TokenError},
     scanner::{Scanner, Token, TokenType, TokensIterator},
+    span::Span,
 };
 
 /* Grammar:
@@ -18,7 +20,8 @@ statement      → exprStmt | ifStmt | whileStmt | forStmt | returnStmt | printS
 exprStmt       → expression ";" ;
 ifStmt         → "if" "(" expression ")" statement ( "else" statement )? ;
 whileStmt      → "while" "(" expression ")" statement ;
-forStmt        → "for" "(" ( varDecl | exprStmt | ";" ) expression? ";" expression? ")" statement ;
+forStmt        → "for" "(" ( varDecl | exprStmt | ";" ) expression? ";" expression? ")" statement
+                 | "for" "(" "var" IDENTIFIER "in" expression ")" statement ;
 returnStmt     → "return" expression? ";" ;
 printStmt      → "print" expression ";" ;
 block          → "{" declaration* "}" ;
@@ -28,11 +31,12 @@ assignment     → IDENTIFIER "=" assignment | anonFunc | logic_or ;
 logic_or       → logic_and ( "or" logic_and )* ;
 logic_and      → equality ( "and" equality )* ;
 equality       → comparison ( ( "!=" | "==" ) comparison )* ;
-comparison     → term ( ( ">" | ">=" | "<" | "<=" ) term )* ;
+comparison     → range ( ( ">" | ">=" | "<" | "<=" ) range )* ;
+range          → term ( ( ".." | "..=" ) term )* ;
 term           → factor ( ( "-" | "+" ) factor )* ;
 factor         → unary ( ( "/" | "*" ) unary )* ;
 unary          → ( "!" | "-" ) unary | call ;
-call           → primary ( "(" arguments? ")" | "." IDENTIFIER )* ;
+call           → primary ( "(" arguments? ")" | "." IDENTIFIER | "[" expression "]" )* ;
 arguments      → expression ( "," expression )* ;
 primary        → NUMBER | STRING | "true" | "false" | "nil" | IDENTIFIER | "(" expression ")" | "super" "." IDENTIFIER ;
 
@@ -88,6 +92,7 @@ impl Parser {
         match self.tokens.take() {
             Some(tokens) => Ok(StatementsIterator::new(tokens, self.errors.clone())),
             None => Err(InterpreterError::parsing(
+                ErrorKind::Internal,
                 "Parser's tokens have already been consumed",
             )),
         }
@@ -98,10 +103,14 @@ impl Parser {
             Some(tokens) => {
                 match StatementsIterator::new(tokens, self.errors.clone()).next_expression() {
                     Some(expr) => Ok(Some(expr)),
-                    None => Err(InterpreterError::parsing("No expression found")),
+                    None => Err(InterpreterError::parsing(
+                        ErrorKind::ExpectedExpression,
+                        "No expression found",
+                    )),
                 }
             }
             None => Err(InterpreterError::parsing(
+                ErrorKind::Internal,
                 "Parser's tokens have already been consumed",
             )),
         }
@@ -152,11 +161,33 @@ impl StatementsIterator {
         }
     }
 
-    fn add_error<T: ToString, R>(&mut self, msg: T) -> Result<R, ()> {
+    /// Records an error at the next token, or where scanning stopped if there is none.
+    fn add_error<T: ToString, R>(&mut self, kind: ErrorKind, msg: T) -> Result<R, ()> {
+        let span = match self.peeked.front() {
+            Some(token) => token.span,
+            None => self.tokens.current_span(),
+        };
+        self.add_error_at(kind, msg, span)
+    }
+
+    fn add_error_at<T: ToString, R>(
+        &mut self,
+        kind: ErrorKind,
+        msg: T,
+        span: Span,
+    ) -> Result<R, ()> {
         self.errors
             .borrow_mut()
             .get_or_insert_with(|| ParsingErrorsBuilder::new())
-            .add(msg.to_string(), self.tokens.current_line());
+            .add(kind, msg.to_string(), span);
+        Err(())
+    }
+
+    fn add_token_error<R>(&mut self, error: TokenError) -> Result<R, ()> {
+        self.errors
+            .borrow_mut()
+            .get_or_insert_with(ParsingErrorsBuilder::new)
+            .push(ErrorMessage::from(error));
         Err(())
     }
 
@@ -201,7 +232,7 @@ impl StatementsIterator {
                         token.clone(),
                         expr.map(|i| Rc::new(i)),
                     ))),
-                    _ => self.add_error("invalid function declaration"),
+                    _ => self.add_error(ErrorKind::MissingSyntax, "invalid function declaration"),
                 }
             }
             Some([TokenType::Var, _]) => self.variable_declaration(),
@@ -233,12 +264,14 @@ impl StatementsIterator {
     }
 
     fn function(&mut self, kind: FunctionKind) -> Result<Option<Expr>, ()> {
-        if matches!(kind, FunctionKind::Normal | FunctionKind::Anonymous) {
-            self.consume(
+        let keyword = if matches!(kind, FunctionKind::Normal | FunctionKind::Anonymous) {
+            Some(self.consume(
                 TokenType::Fun,
                 format!("Expect 'fun' in {} declaration", kind),
-            )?;
-        }
+            )?)
+        } else {
+            None
+        };
 
         let name = match kind {
             FunctionKind::Normal | FunctionKind::Method => {
@@ -256,7 +289,10 @@ impl StatementsIterator {
         if !(self.peek_type(TokenType::RightParenthesis)?) {
             loop {
                 if parameters.len() >= 255 {
-                    return self.add_error("Can't have more than 255 parameters.");
+                    return self.add_error(
+                        ErrorKind::TooManyParameters,
+                        "Can't have more than 255 parameters.",
+                    );
                 }
                 parameters.push(self.consume(TokenType::Identifier, "Expect parameter name")?);
                 if self.next_matches(TokenType::Comma)?.is_none() {
@@ -270,7 +306,17 @@ impl StatementsIterator {
         )?;
 
         match self.block()? {
-            Some(body) => Ok(Some(Expr::Function(name, Function::new(parameters, body)))),
+            Some(body) => {
+                let start = keyword
+                    .as_ref()
+                    .or(name.as_ref())
+                    .map_or(body.span(), |i| i.span);
+                let span = start.to(body.span());
+                Ok(Some(Expr::Function(
+                    name,
+                    Function::new(parameters, body, span),
+                )))
+            }
             None => Ok(None),
         }
     }
@@ -290,7 +336,7 @@ impl StatementsIterator {
                     initializer.map(|i| Rc::new(i)),
                 )))
             }
-            _ => self.add_error("Expect variable name"),
+            _ => self.add_error(ErrorKind::MissingSyntax, "Expect variable name"),
         }
     }
 
@@ -310,7 +356,7 @@ impl StatementsIterator {
     }
 
     fn if_statement(&mut self) -> Result<Option<Statement>, ()> {
-        self.consume(TokenType::If, "Expect 'if' in if statement")?;
+        let keyword = self.consume(TokenType::If, "Expect 'if' in if statement")?;
         self.consume(TokenType::LeftParenthesis, "Expect '(' after 'if'")?;
         let condition = self.expression()?;
         self.consume(TokenType::RightParenthesis, "Expect ')' after if condition")?;
@@ -323,16 +369,17 @@ impl StatementsIterator {
 
         match (condition, then_branch) {
             (Some(condition), Some(then_branch)) => Ok(Some(Statement::If(
+                keyword,
                 Rc::new(condition),
-                Box::new(then_branch),
-                else_branch.map(|i| Box::new(i)),
+                Rc::new(then_branch),
+                else_branch.map(Rc::new),
             ))),
             _ => Ok(None),
         }
     }
 
     fn while_statement(&mut self) -> Result<Option<Statement>, ()> {
-        self.consume(TokenType::While, "Expect 'while' in while statement")?;
+        let keyword = self.consume(TokenType::While, "Expect 'while' in while statement")?;
         self.consume(TokenType::LeftParenthesis, "Expect '(' after 'while'")?;
 
         let condition = self.expression()?;
@@ -340,16 +387,27 @@ impl StatementsIterator {
         self.consume(TokenType::RightParenthesis, "Expect ')' after condition")?;
 
         match (condition, self.statement()?) {
-            (Some(condition), Some(body)) => {
-                Ok(Some(Statement::While(Rc::new(condition), Box::new(body))))
-            }
+            (Some(condition), Some(body)) => Ok(Some(Statement::While(
+                keyword,
+                Rc::new(condition),
+                Rc::new(body),
+            ))),
             _ => Ok(None),
         }
     }
 
     fn for_statement(&mut self) -> Result<Option<Statement>, ()> {
-        self.consume(TokenType::For, "Expect 'for' in for statement")?;
-        self.consume(TokenType::LeftParenthesis, "Expect '(' after 'for'")?;
+        let keyword = self.consume(TokenType::For, "Expect 'for' in for statement")?;
+        let left_paren = self.consume(TokenType::LeftParenthesis, "Expect '(' after 'for'")?;
+
+        if matches!(
+            self.peek_count(3)?
+                .map(|i| i.iter().map(|t| t.token_type).collect::<Vec<_>>())
+                .as_deref(),
+            Some([TokenType::Var, TokenType::Identifier, TokenType::In])
+        ) {
+            return self.for_in_statement(keyword);
+        }
 
         let initializer = if self.next_matches(TokenType::Semicolon)?.is_some() {
             None
@@ -377,63 +435,100 @@ impl StatementsIterator {
         let mut body = match self.statement()? {
             Some(statement) => statement,
             None => {
-                return self.add_error("for statement needs to have a body");
+                return self.add_error(
+                    ErrorKind::MissingSyntax,
+                    "for statement needs to have a body",
+                );
             }
         };
 
+        let span = keyword.span.to(body.span());
+
         if let Some(increment) = increment {
-            body = Statement::Block(Box::new(vec![
-                body,
-                Statement::Expression(Rc::new(increment)),
-            ]))
+            body = Statement::Block(
+                span,
+                Rc::new(vec![body, Statement::Expression(Rc::new(increment))]),
+            )
         }
 
         body = Statement::While(
+            keyword,
             Rc::new(if let Some(condition) = condition {
                 condition
             } else {
-                Expr::Literal(Token::new(
-                    TokenType::True,
-                    "true",
-                    self.tokens.current_line(),
-                ))
+                Expr::Literal(Token::new(TokenType::True, "true", left_paren.span))
             }),
-            Box::new(body),
+            Rc::new(body),
         );
 
         if let Some(initializer) = initializer {
-            body = Statement::Block(Box::new(vec![initializer, body]));
+            body = Statement::Block(span, Rc::new(vec![initializer, body]));
         }
 
         return Ok(Some(body));
     }
 
+    fn for_in_statement(&mut self, keyword: Token) -> Result<Option<Statement>, ()> {
+        self.consume(TokenType::Var, "Expect 'var' in for-in statement")?;
+        let name = self.consume(TokenType::Identifier, "Expect loop variable name")?;
+        self.consume(TokenType::In, "Expect 'in' after loop variable")?;
+
+        let iterable = self.expression()?;
+
+        self.consume(
+            TokenType::RightParenthesis,
+            "Expect ')' after for-in clause",
+        )?;
+
+        match (iterable, self.statement()?) {
+            (Some(iterable), Some(body)) => Ok(Some(Statement::ForIn(
+                keyword,
+                name,
+                Rc::new(iterable),
+                Rc::new(body),
+            ))),
+            (_, None) => self.add_error(
+                ErrorKind::MissingSyntax,
+                "for statement needs to have a body",
+            ),
+            _ => Ok(None),
+        }
+    }
+
     fn block(&mut self) -> Result<Option<Statement>, ()> {
-        self.consume(TokenType::LeftBrace, "Expect '{' to start a block")?;
+        let left_brace = self.consume(TokenType::LeftBrace, "Expect '{' to start a block")?;
         let mut declarations = Vec::new();
-        while self.peek()?.is_some() && self.next_matches(TokenType::RightBrace)?.is_none() {
+        let mut span = left_brace.span;
+        while self.peek()?.is_some() {
+            if let Some(right_brace) = self.next_matches(TokenType::RightBrace)? {
+                span = span.to(right_brace.span);
+                break;
+            }
             match self.declaration()? {
-                Some(declaration) => declarations.push(declaration),
-                None => return self.add_error("Expect '}' after block"),
+                Some(declaration) => {
+                    span = span.to(declaration.span());
+                    declarations.push(declaration);
+                }
+                None => return self.add_error(ErrorKind::MissingSyntax, "Expect '}' after block"),
             }
         }
 
-        Ok(Some(Statement::Block(Box::new(declarations))))
+        Ok(Some(Statement::Block(span, Rc::new(declarations))))
     }
 
     fn print_statement(&mut self) -> Result<Option<Statement>, ()> {
-        self.consume(TokenType::Print, "Expect 'print' in print statement")?;
+        let keyword = self.consume(TokenType::Print, "Expect 'print' in print statement")?;
         match self.expression()? {
             Some(expr) => {
                 self.consume_semicolon()?;
-                Ok(Some(Statement::Print(Rc::new(expr))))
+                Ok(Some(Statement::Print(keyword, Rc::new(expr))))
             }
             _ => Ok(None),
         }
     }
 
     fn return_statement(&mut self) -> Result<Option<Statement>, ()> {
-        self.consume(TokenType::Return, "Expect 'return', in return statement")?;
+        let keyword = self.consume(TokenType::Return, "Expect 'return', in return statement")?;
 
         let expr = if !self.peek_type(TokenType::Semicolon)? {
             self.expression()?
@@ -443,7 +538,7 @@ impl StatementsIterator {
 
         self.consume_semicolon()?;
 
-        Ok(Some(Statement::Return(expr.map(|i| Rc::new(i)))))
+        Ok(Some(Statement::Return(keyword, expr.map(|i| Rc::new(i)))))
     }
 
     fn expression_statement(&mut self) -> Result<Option<Statement>, ()> {
@@ -467,7 +562,8 @@ impl StatementsIterator {
             self.logic_or()?
         };
 
-        if self.next_matches(TokenType::Equal)?.is_some() {
+        if let Some(equal) = self.next_matches(TokenType::Equal)? {
+            let target = expr.as_ref().map_or(equal.span, |i| i.span());
             return match (self.assignment()?, expr) {
                 (Some(value), Some(Expr::Variable(token))) => {
                     Ok(Some(Expr::assignment(token, value)))
@@ -476,7 +572,11 @@ impl StatementsIterator {
                     Ok(Some(Expr::Set(instance, field, Rc::new(value))))
                 }
                 _ => {
-                    let _ = self.add_error::<_, ()>("Invalid assignment target");
+                    let _ = self.add_error_at::<_, ()>(
+                        ErrorKind::InvalidAssignmentTarget,
+                        "Invalid assignment target",
+                        target,
+                    );
                     Ok(None)
                 }
             };
@@ -495,7 +595,7 @@ impl StatementsIterator {
     );
     grammar_rule_binary!(
         comparison,
-        term,
+        range,
         [
             TokenType::Greater,
             TokenType::GreaterEqual,
@@ -504,6 +604,12 @@ impl StatementsIterator {
         ],
         binary
     );
+    grammar_rule_binary!(
+        range,
+        term,
+        [TokenType::DotDot, TokenType::DotDotEqual],
+        binary
+    );
     grammar_rule_binary!(term, factor, [TokenType::Minus, TokenType::Plus], binary);
     grammar_rule_binary!(factor, unary, [TokenType::Slash, TokenType::Star], binary);
 
@@ -541,6 +647,17 @@ impl StatementsIterator {
                         expr = Some(Expr::Get(Rc::new(prop), name));
                     }
                 }
+                Some(token) if token.token_type == TokenType::LeftBracket && expr.is_some() => {
+                    self.consume(TokenType::LeftBracket, "Expect '[' when indexing")?;
+                    let index = self.expression()?;
+                    let right_bracket =
+                        self.consume(TokenType::RightBracket, "Expect ']' after index")?;
+                    if let (Some(target), Some(index)) = (expr, index) {
+                        expr = Some(Expr::Index(Rc::new(target), right_bracket, Rc::new(index)));
+                    } else {
+                        expr = None;
+                    }
+                }
                 _ => break,
             }
         }
@@ -554,7 +671,10 @@ impl StatementsIterator {
         if !(self.peek_type(TokenType::RightParenthesis)?) {
             loop {
                 if arguments.len() >= 255 {
-                    return self.add_error("Can't have more than 255 arguments.");
+                    return self.add_error(
+                        ErrorKind::TooManyArguments,
+                        "Can't have more than 255 arguments.",
+                    );
                 }
                 if let Some(expr) = self.expression()? {
                     arguments.push(Rc::new(expr));
@@ -581,10 +701,14 @@ impl StatementsIterator {
                     False | True | Nil | Number | String => Ok(Some(Expr::Literal(token))),
                     LeftParenthesis => match self.expression()? {
                         Some(expr) => {
-                            if self.next_matches(RightParenthesis)?.is_some() {
-                                return Ok(Some(Expr::grouping(expr)));
+                            if let Some(right_paren) = self.next_matches(RightParenthesis)? {
+                                let span = token.span.to(right_paren.span);
+                                return Ok(Some(Expr::grouping(span, expr)));
                             } else {
-                                self.add_error("Expect ')' after expression")?;
+                                self.add_error(
+                                    ErrorKind::MissingSyntax,
+                                    "Expect ')' after expression",
+                                )?;
                                 Ok(None)
                             }
                         }
@@ -595,12 +719,19 @@ impl StatementsIterator {
                         if let Some(method) = self.next_matches(Identifier)? {
                             return Ok(Some(Expr::Super(token, method)));
                         } else {
-                            self.add_error("Expect superclass method name")?;
+                            self.add_error(
+                                ErrorKind::MissingSyntax,
+                                "Expect superclass method name",
+                            )?;
                             Ok(None)
                         }
                     }
                     token_type => {
-                        self.add_error(format!("Unexpected token: {}", token_type))?;
+                        self.add_error_at(
+                            ErrorKind::UnexpectedToken,
+                            format!("Unexpected token: {}", token_type),
+                            token.span,
+                        )?;
                         Ok(None)
                     }
                 }
@@ -617,7 +748,7 @@ impl StatementsIterator {
             match self.tokens.next() {
                 Some(Ok(token)) => Ok(Some(token)),
                 None => Ok(None),
-                Some(Err(error)) => self.add_error(error),
+                Some(Err(error)) => self.add_token_error(error),
             }
         }
     }
@@ -626,7 +757,7 @@ impl StatementsIterator {
         while self.peeked.len() < count {
             match self.tokens.next() {
                 Some(Ok(token)) => self.peeked.push_back(token),
-                Some(Err(error)) => return self.add_error(error),
+                Some(Err(error)) => return self.add_token_error(error),
                 None => return Ok(None),
             }
         }
@@ -664,7 +795,7 @@ impl StatementsIterator {
     ) -> Result<Token, ()> {
         match self.next_matches(token_type)? {
             Some(token) => Ok(token),
-            None => self.add_error(error_message),
+            None => self.add_error(ErrorKind::MissingSyntax, error_message),
         }
     }
 }
@@ -689,13 +820,16 @@ impl<const N: usize> TokenTypeMatcher for [TokenType; N] {
 pub struct Function {
     pub parameters: Box<Vec<Token>>,
     pub body: Rc<Statement>,
+    /// From the `fun` keyword, or the name of a method, to the end of the body.
+    pub span: Span,
 }
 
 impl Function {
-    fn new(parameters: Vec<Token>, body: Statement) -> Self {
+    fn new(parameters: Vec<Token>, body: Statement, span: Span) -> Self {
         Self {
             parameters: Box::new(parameters),
             body: Rc::new(body),
+            span,
         }
     }
 }
@@ -707,7 +841,7 @@ impl Display for Function {
             "({}) {}",
             self.parameters
                 .iter()
-                .map(|i| i.lexeme.as_str())
+                .map(|i| i.symbol().as_str())
                 .collect::<Vec<_>>()
                 .join(","),
             self.body
@@ -719,40 +853,75 @@ impl Display for Function {
 pub enum Statement {
     Class(Token, Vec<Option<Rc<Expr>>>, Option<Rc<Expr>>),
     Variable(Token, Option<Rc<Expr>>),
-    Print(Rc<Expr>),
-    Return(Option<Rc<Expr>>),
+    Print(Token, Rc<Expr>),
+    Return(Token, Option<Rc<Expr>>),
     Expression(Rc<Expr>),
-    Block(Box<Vec<Statement>>),
-    If(Rc<Expr>, Box<Statement>, Option<Box<Statement>>),
-    While(Rc<Expr>, Box<Statement>),
+    Block(Span, Rc<Vec<Statement>>),
+    If(Token, Rc<Expr>, Rc<Statement>, Option<Rc<Statement>>),
+    While(Token, Rc<Expr>, Rc<Statement>),
+    ForIn(Token, Token, Rc<Expr>, Rc<Statement>),
+}
+
+impl Statement {
+    /// The source code of the statement, from its first token to its last expression or
+    /// statement, leaving out the final semicolon.
+    pub fn span(&self) -> Span {
+        use Statement::*;
+        match self {
+            Class(name, methods, super_class) => methods
+                .iter()
+                .flatten()
+                .last()
+                .or(super_class.as_ref())
+                .map_or(name.span, |i| name.span.to(i.span())),
+            Variable(name, initializer) => initializer
+                .as_ref()
+                .map_or(name.span, |i| name.span.to(i.span())),
+            Print(keyword, expr) => keyword.span.to(expr.span()),
+            Return(keyword, expr) => expr
+                .as_ref()
+                .map_or(keyword.span, |i| keyword.span.to(i.span())),
+            Expression(expr) => expr.span(),
+            Block(span, _) => *span,
+            If(keyword, _, then_branch, else_branch) => keyword
+                .span
+                .to(else_branch.as_ref().unwrap_or(then_branch).span()),
+            While(keyword, _, body) | ForIn(keyword, _, _, body) => keyword.span.to(body.span()),
+        }
+    }
 }
 
 impl Display for Statement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use Statement::*;
         match self {
-            Return(None) => write!(f, "return"),
-            Return(Some(expr)) => write!(f, "return {}", expr),
+            Return(_, None) => write!(f, "return"),
+            Return(_, Some(expr)) => write!(f, "return {}", expr),
             Variable(name, None) => write!(f, "var {}", name),
             Variable(name, Some(expr)) => write!(f, "var {}={}", name, expr),
-            Print(expr) => write!(f, "print {}", expr),
+            Print(_, expr) => write!(f, "print {}", expr),
             Expression(expr) => write!(f, "{}", expr),
-            Block(statements) => {
+            Block(_, statements) => {
                 writeln!(f, "{{")?;
                 for statement in statements.iter() {
                     writeln!(f, "{}", statement)?;
                 }
                 writeln!(f, "}}")
             }
-            If(condition, then_branch, None) => write!(f, "if {} then {}", condition, then_branch),
-            If(condition, then_branch, Some(else_branch)) => {
+            If(_, condition, then_branch, None) => {
+                write!(f, "if {} then {}", condition, then_branch)
+            }
+            If(_, condition, then_branch, Some(else_branch)) => {
                 write!(
                     f,
                     "if {} then {} else {}",
                     condition, then_branch, else_branch
                 )
             }
-            While(condition, body) => write!(f, "while {} then {}", condition, body),
+            While(_, condition, body) => write!(f, "while {} then {}", condition, body),
+            ForIn(_, name, iterable, body) => {
+                write!(f, "for {} in {} then {}", name.lexeme, iterable, body)
+            }
             Class(name, _, super_class) => {
                 write!(f, "class {}", name)?;
                 if let Some(super_class) = super_class {
@@ -767,7 +936,7 @@ impl Display for Statement {
 #[derive(Debug, PartialEq)]
 pub enum Expr {
     Binary(Token, Rc<Expr>, Rc<Expr>),
-    Grouping(Rc<Expr>),
+    Grouping(Span, Rc<Expr>),
     Literal(Token),
     Logical(Token, Rc<Expr>, Rc<Expr>),
     Unary(Token, Rc<Expr>),
@@ -777,6 +946,7 @@ pub enum Expr {
     Call(Rc<Expr>, Token, Box<Vec<Rc<Expr>>>),
     Get(Rc<Expr>, Token),
     Set(Rc<Expr>, Token, Rc<Expr>),
+    Index(Rc<Expr>, Token, Rc<Expr>),
     This(Token),
     Super(Token, Token),
 }
@@ -792,8 +962,8 @@ impl Expr {
         Self::Binary(token, Rc::new(left), Rc::new(right))
     }
 
-    fn grouping(expr: Expr) -> Self {
-        Self::Grouping(Rc::new(expr))
+    fn grouping(span: Span, expr: Expr) -> Self {
+        Self::Grouping(span, Rc::new(expr))
     }
 
     fn logical(token: Token, left: Expr, right: Expr) -> Self {
@@ -811,6 +981,25 @@ impl Expr {
     fn call(callee: Expr, right_paren: Token, arguments: Vec<Rc<Expr>>) -> Self {
         Self::Call(Rc::new(callee), right_paren, Box::new(arguments))
     }
+
+    /// The source code of the expression, from its first token to its last.
+    pub fn span(&self) -> Span {
+        use Expr::*;
+        match self {
+            Binary(_, left, right) | Logical(_, left, right) | Set(left, _, right) => {
+                left.span().to(right.span())
+            }
+            Grouping(span, _) => *span,
+            Literal(token) | Variable(token) | This(token) => token.span,
+            Unary(operator, operand) => operator.span.to(operand.span()),
+            Assignment(name, value) => name.span.to(value.span()),
+            Function(_, function) => function.span,
+            Call(callee, right_paren, _) => callee.span().to(right_paren.span),
+            Get(instance, name) => instance.span().to(name.span),
+            Index(target, right_bracket, _) => target.span().to(right_bracket.span),
+            Super(keyword, method) => keyword.span.to(method.span),
+        }
+    }
 }
 
 impl Display for Expr {
@@ -820,7 +1009,7 @@ impl Display for Expr {
             Binary(token, left, right) | Logical(token, left, right) => {
                 write!(f, "({} {} {})", token.display(), left, right,)
             }
-            Grouping(expr) => write!(f, "(group {})", expr),
+            Grouping(_, expr) => write!(f, "(group {})", expr),
             Literal(token) => write!(f, "{}", token.display()),
             Unary(token, expr) => write!(f, "({} {})", token.display(), expr),
             Variable(token) => write!(f, "(var \"{}\")", token.display()),
@@ -841,7 +1030,7 @@ impl Display for Expr {
                 write!(
                     f,
                     "fun {}({})",
-                    token.as_ref().map_or_else(|| "", |i| &i.lexeme),
+                    token.as_ref().map_or_else(|| "", |i| i.symbol().as_str()),
                     fun
                 )
             }
@@ -851,6 +1040,7 @@ impl Display for Expr {
             Set(instance, field, value) => {
                 write!(f, "(set {}.{}={})", instance, field, value)
             }
+            Index(target, _, index) => write!(f, "(index {}[{}])", target, index),
             This(_) => write!(f, "this"),
             Super(_, method) => write!(f, "super.{}", method),
         }
@@ -7,9 +7,12 @@ use std::{
 };
 
 use crate::{
+    error_kind::ErrorKind,
     errors::{ErrorMessage, InterpreterError},
-    parser::{Expr, Function, Statement},
+    interner::Symbol,
+    parser::{Expr, Function, Parser, Statement},
     scanner::Token,
+    span::Span,
 };
 
 #[derive(Debug)]
@@ -26,10 +29,20 @@ enum ClassType {
 }
 
 pub struct Resolver {
-    scopes: Vec<HashMap<String, Variable>>,
+    scopes: Vec<HashMap<Symbol, Variable>>,
     current_class: Option<ClassType>,
     current_function: Option<FunctionType>,
-    pub resolve_table: HashMap<HashableExpr, usize>,
+    pub resolve_table: HashMap<HashableExpr, Local>,
+    /// Errors found so far, so that all of them can be reported at once.
+    errors: Vec<ErrorMessage>,
+}
+
+/// Where a local variable lives at runtime: the number of environments to walk up from the one
+/// it is used in, and its slot in that environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Local {
+    pub distance: usize,
+    pub slot: usize,
 }
 
 impl Resolver {
@@ -39,39 +52,76 @@ impl Resolver {
             current_class: None,
             current_function: None,
             resolve_table: HashMap::new(),
+            errors: Vec::new(),
         }
     }
 
+    /// Resolves every statement, failing with all the errors found in them if any.
     pub fn resolve(&mut self, statements: &Vec<Statement>) -> Result<(), InterpreterError> {
+        self.resolve_statements(statements);
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(InterpreterError::ResolverErrors(std::mem::take(
+                &mut self.errors,
+            )))
+        }
+    }
+
+    /// Parses and resolves a whole script. Fails with every scanning, parsing and resolving error
+    /// found in it, so that they can all be fixed before running it.
+    pub fn resolve_script(
+        &mut self,
+        parser: &mut Parser,
+    ) -> Result<Vec<Statement>, InterpreterError> {
+        let statements = parser.parse()?.collect::<Vec<_>>();
+        let resolved = self.resolve(&statements);
+        match (parser.errors(), resolved) {
+            (Some(errors), Err(later)) => Err(errors.merge(later)),
+            (Some(errors), Ok(())) | (None, Err(errors)) => Err(errors),
+            (None, Ok(())) => Ok(statements),
+        }
+    }
+
+    fn resolve_statements(&mut self, statements: &Vec<Statement>) {
         for statement in statements {
-            self.resolve_statement(&statement)?;
+            self.resolve_statement(statement);
         }
-        Ok(())
     }
 
-    fn resolve_statement(&mut self, statement: &Statement) -> Result<(), InterpreterError> {
+    fn add_error<T: ToString>(&mut self, kind: ErrorKind, message: T, span: Span) {
+        self.errors
+            .push(ErrorMessage::new(kind, message, Some(span)));
+    }
+
+    fn resolve_statement(&mut self, statement: &Statement) {
         match statement {
             Statement::Class(name, methods, super_class) => {
                 let enclosing_class = self.current_class.take();
 
+                self.declare(name);
+                self.define(name);
+
                 if let Some(super_class) = super_class {
                     match super_class.deref() {
-                        Expr::Variable(super_class) if super_class.lexeme == name.lexeme => {
-                            return Err(InterpreterError::resolving(
+                        Expr::Variable(super_class) if super_class.symbol() == name.symbol() => {
+                            self.add_error(
+                                ErrorKind::InheritFromSelf,
                                 "A class can't inherit from itself",
-                                Some(name.line),
-                            ));
+                                super_class.span,
+                            );
                         }
                         Expr::Variable(_) => {}
-                        _ => {
-                            return Err(InterpreterError::resolving(
+                        super_class => {
+                            self.add_error(
+                                ErrorKind::Internal,
                                 "A class can only inherit from a valid identifier",
-                                Some(name.line),
-                            ));
+                                super_class.span(),
+                            );
                         }
                     }
 
-                    self.resolve_expression(super_class.clone())?;
+                    self.resolve_expression(super_class.clone());
 
                     self.begin_scope();
                     self.declare_and_define("super");
@@ -82,26 +132,21 @@ impl Resolver {
                 }
 
                 self.begin_scope();
-
-                self.declare(name)?;
-                self.define(name);
-
                 self.declare_and_define("this");
 
                 for method in methods {
                     match method.as_deref() {
-                        Some(Expr::Function(Some(token), method)) if token.lexeme == "init" => {
-                            self.resolve_function(method, FunctionType::Initializer)?
+                        Some(Expr::Function(Some(token), method)) if token.symbol() == "init" => {
+                            self.resolve_function(method, FunctionType::Initializer)
                         }
                         Some(Expr::Function(_, method)) => {
-                            self.resolve_function(method, FunctionType::Method)?
-                        }
-                        _ => {
-                            return Err(InterpreterError::resolving(
-                                "expression is not a method",
-                                Some(name.line),
-                            ))
+                            self.resolve_function(method, FunctionType::Method)
                         }
+                        method => self.add_error(
+                            ErrorKind::Internal,
+                            "expression is not a method",
+                            method.map_or(name.span, |i| i.span()),
+                        ),
                     }
                 }
 
@@ -112,157 +157,170 @@ impl Resolver {
                 }
 
                 self.current_class = enclosing_class;
-
-                Ok(())
             }
-            Statement::Block(statements) => {
+            Statement::Block(_, statements) => {
                 self.begin_scope();
-                self.resolve(&statements)?;
+                self.resolve_statements(statements);
                 self.end_scope();
-                Ok(())
             }
             Statement::Variable(name, initializer) => {
-                self.declare(&name)?;
+                self.declare(name);
                 if let Some(initializer) = initializer {
-                    self.resolve_expression(initializer.clone())?;
+                    self.resolve_expression(initializer.clone());
                 }
-                self.define(&name);
-                Ok(())
+                self.define(name);
             }
             Statement::Expression(expr) => self.resolve_expression(expr.clone()),
-            Statement::If(condition, then_branch, else_branch) => {
-                self.resolve_expression(condition.clone())?;
-                self.resolve_statement(then_branch)?;
+            Statement::If(_, condition, then_branch, else_branch) => {
+                self.resolve_expression(condition.clone());
+                self.resolve_statement(then_branch);
                 if let Some(else_branch) = else_branch {
-                    self.resolve_statement(else_branch)?;
+                    self.resolve_statement(else_branch);
                 }
-                Ok(())
             }
-            Statement::Print(expr) => self.resolve_expression(expr.clone()),
-            Statement::Return(expr) => match (&self.current_function, expr) {
-                (Some(FunctionType::Initializer), Some(_)) => Err(InterpreterError::resolving(
+            Statement::Print(_, expr) => self.resolve_expression(expr.clone()),
+            Statement::Return(keyword, expr) => match (&self.current_function, expr) {
+                (Some(FunctionType::Initializer), Some(_)) => self.add_error(
+                    ErrorKind::ReturnValueFromInitializer,
                     "Can't return a value from an initializer",
-                    None,
-                )),
+                    statement.span(),
+                ),
                 (Some(_), Some(expr)) => self.resolve_expression(expr.clone()),
-                (Some(_), None) => Ok(()),
-                (None, _) => Err(InterpreterError::resolving(
+                (Some(_), None) => {}
+                (None, _) => self.add_error(
+                    ErrorKind::ReturnFromTopLevel,
                     "Can't return from top level code",
-                    None,
-                )),
+                    keyword.span,
+                ),
             },
-            Statement::While(condition, body) => {
-                self.resolve_expression(condition.clone())?;
-                self.resolve_statement(body)
+            Statement::While(_, condition, body) => {
+                self.resolve_expression(condition.clone());
+                self.resolve_statement(body);
+            }
+            Statement::ForIn(_, name, iterable, body) => {
+                self.resolve_expression(iterable.clone());
+                self.begin_scope();
+                self.declare(name);
+                self.define(name);
+                self.resolve_statement(body);
+                self.end_scope();
             }
         }
     }
 
-    fn resolve_expression(&mut self, expr: Rc<Expr>) -> Result<(), InterpreterError> {
+    fn resolve_expression(&mut self, expr: Rc<Expr>) {
         match expr.deref() {
             Expr::Super(token, _) => match self.current_class {
-                Some(ClassType::SubClass) => {
-                    self.resolve_local(expr.clone(), &token.lexeme);
-                    Ok(())
-                }
-                Some(ClassType::Class) => Err(InterpreterError::resolving(
+                Some(ClassType::SubClass) => self.resolve_local(expr.clone(), token.symbol()),
+                Some(ClassType::Class) => self.add_error(
+                    ErrorKind::SuperWithoutSuperclass,
                     "Can't use or 'super' in a class that has no super class",
-                    Some(token.line),
-                )),
-                None => Err(InterpreterError::resolving(
+                    expr.span(),
+                ),
+                None => self.add_error(
+                    ErrorKind::SuperOutsideClass,
                     "Can't use or 'super' outside of a class",
-                    Some(token.line),
-                )),
+                    expr.span(),
+                ),
             },
             Expr::This(token) => {
                 if self.current_class.is_none() {
-                    return Err(InterpreterError::resolving(
+                    self.add_error(
+                        ErrorKind::ThisOutsideClass,
                         "Can't use 'this' outside of a class",
-                        Some(token.line),
-                    ));
+                        token.span,
+                    );
+                    return;
                 }
 
-                self.resolve_local(expr.clone(), &token.lexeme);
-                Ok(())
+                self.resolve_local(expr.clone(), token.symbol());
             }
             Expr::Set(instance, _, value) => {
-                self.resolve_expression(instance.clone())?;
-                self.resolve_expression(value.clone())
+                self.resolve_expression(instance.clone());
+                self.resolve_expression(value.clone());
             }
             Expr::Get(expr, _) => self.resolve_expression(expr.clone()),
+            Expr::Index(target, _, index) => {
+                self.resolve_expression(target.clone());
+                self.resolve_expression(index.clone());
+            }
             Expr::Variable(ref token) => {
                 if self.scopes.last().map_or(false, |i| {
-                    i.get(&token.lexeme).map(|i| !i.is_defined).unwrap_or(false)
+                    i.get(&token.symbol())
+                        .map(|i| !i.is_defined)
+                        .unwrap_or(false)
                 }) {
-                    return Err(InterpreterError::resolving(
-                        format!("Variable '{}' is used in its own initializer", token.lexeme),
-                        Some(token.line),
-                    ));
+                    self.add_error(
+                        ErrorKind::ReadInOwnInitializer,
+                        format!(
+                            "Variable '{}' is used in its own initializer",
+                            token.symbol()
+                        ),
+                        token.span,
+                    );
+                    return;
                 }
 
-                self.resolve_local(expr.clone(), &token.lexeme);
-                Ok(())
+                self.resolve_local(expr.clone(), token.symbol());
             }
-            Expr::Assignment(token, expr) => {
-                self.resolve_expression(expr.clone())?;
-                self.resolve_local(expr.clone(), &token.lexeme);
-                Ok(())
+            Expr::Assignment(token, value) => {
+                self.resolve_expression(value.clone());
+                self.resolve_local(expr.clone(), token.symbol());
             }
             Expr::Function(token, fun) => {
                 if let Some(token) = token {
                     self.define(token);
                 }
-                self.resolve_function(fun, FunctionType::Function)
+                self.resolve_function(fun, FunctionType::Function);
             }
             Expr::Binary(_, left, right) => {
-                self.resolve_expression(left.clone())?;
-                self.resolve_expression(right.clone())?;
-                Ok(())
+                self.resolve_expression(left.clone());
+                self.resolve_expression(right.clone());
             }
             Expr::Call(callee, _, arguments) => {
-                self.resolve_expression(callee.clone())?;
+                self.resolve_expression(callee.clone());
                 for arg in arguments.iter() {
-                    self.resolve_expression(arg.clone())?;
+                    self.resolve_expression(arg.clone());
                 }
-                Ok(())
             }
-            Expr::Grouping(expr) => self.resolve_expression(expr.clone()),
-            Expr::Literal(_) => Ok(()),
+            Expr::Grouping(_, expr) => self.resolve_expression(expr.clone()),
+            Expr::Literal(_) => {}
             Expr::Logical(_, left, right) => {
-                self.resolve_expression(left.clone())?;
-                self.resolve_expression(right.clone())
+                self.resolve_expression(left.clone());
+                self.resolve_expression(right.clone());
             }
             Expr::Unary(_, expr) => self.resolve_expression(expr.clone()),
         }
     }
 
-    fn resolve_function(
-        &mut self,
-        function: &Function,
-        function_type: FunctionType,
-    ) -> Result<(), InterpreterError> {
+    fn resolve_function(&mut self, function: &Function, function_type: FunctionType) {
         self.begin_scope();
 
         let enclosing_function = self.current_function.take();
         self.current_function = Some(function_type);
 
         for param in function.parameters.iter() {
-            self.declare(&param)?;
-            self.define(&param);
+            self.declare(param);
+            self.define(param);
         }
 
-        self.resolve_statement(&function.body)?;
+        self.resolve_statement(&function.body);
 
         self.end_scope();
 
         self.current_function = enclosing_function;
-        Ok(())
     }
 
-    fn resolve_local<T: ToString>(&mut self, expr: Rc<Expr>, name: &T) {
+    fn resolve_local(&mut self, expr: Rc<Expr>, name: Symbol) {
         for scope in self.scopes.iter().rev().enumerate() {
-            if let Some(_) = scope.1.get(&name.to_string()) {
-                self.resolve_table.insert(HashableExpr(expr), scope.0);
+            if let Some(variable) = scope.1.get(&name) {
+                self.resolve_table.insert(
+                    HashableExpr(expr),
+                    Local {
+                        distance: scope.0,
+                        slot: variable.slot,
+                    },
+                );
                 break;
             }
         }
@@ -276,51 +334,64 @@ impl Resolver {
         self.scopes.pop();
     }
 
-    fn declare(&mut self, token: &Token) -> Result<(), InterpreterError> {
-        if self
-            .scopes
-            .last()
-            .map_or(false, |i| i.contains_key(&token.lexeme))
-        {
-            return Err(InterpreterError::InterpreterError(ErrorMessage::new(
+    /// Declares a variable in the innermost scope. A variable declared twice keeps its first
+    /// declaration.
+    fn declare(&mut self, token: &Token) {
+        if let Some(variable) = self.scopes.last().and_then(|i| i.get(&token.symbol())) {
+            let mut message = ErrorMessage::new(
+                ErrorKind::AlreadyDeclared,
                 format!(
                     "Already a variable with name '{}' in this scope",
-                    token.lexeme
+                    token.symbol()
                 ),
-                Some(token.line),
-            )));
+                Some(token.span),
+            );
+            if let Some(span) = variable.span {
+                message = message.with_note("variable declared here", Some(span));
+            }
+            self.errors
+                .push(message.with_help("remove 'var' to assign to the existing variable"));
+            return;
         }
-        self.scopes
-            .last_mut()
-            .and_then(|i| i.insert(token.lexeme.to_owned(), Variable::new()));
-        Ok(())
+        self.scopes.last_mut().and_then(|i| {
+            let variable = Variable::new(i.len(), Some(token.span));
+            i.insert(token.symbol(), variable)
+        });
     }
 
     fn define(&mut self, token: &Token) {
         self.scopes.last_mut().and_then(|i| {
-            i.entry(token.lexeme.to_string())
-                .and_modify(|i| i.mark_as_defined());
+            i.entry(token.symbol()).and_modify(|i| i.mark_as_defined());
             None::<()>
         });
     }
 
     fn declare_and_define(&mut self, name: &str) {
-        let mut variable = Variable::new();
-        variable.mark_as_defined();
-        self.scopes
-            .last_mut()
-            .and_then(|i| i.insert(name.to_owned(), variable));
+        self.scopes.last_mut().and_then(|i| {
+            let mut variable = Variable::new(i.len(), None);
+            variable.mark_as_defined();
+            i.insert(Symbol::intern(name), variable)
+        });
     }
 }
 
+/// A variable declared in a scope. Slots are given in declaration order, which is also the order
+/// in which the interpreter defines the variables in the scope's environment.
 #[derive(Debug)]
 struct Variable {
     is_defined: bool,
+    slot: usize,
+    /// Where the variable is declared, unless the resolver declared it itself.
+    span: Option<Span>,
 }
 
 impl Variable {
-    fn new() -> Self {
-        Self { is_defined: false }
+    fn new(slot: usize, span: Option<Span>) -> Self {
+        Self {
+            is_defined: false,
+            slot,
+            span,
+        }
     }
 
     fn mark_as_defined(&mut self) {
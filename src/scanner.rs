@@ -1,6 +1,17 @@
-use std::{collections::VecDeque, fmt::Display, io::BufRead, rc::Rc};
-
-use crate::errors::{InterpreterError, TokenError};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+    io::BufRead,
+    ops::Deref,
+    rc::Rc,
+};
+
+use crate::{
+    error_kind::ErrorKind,
+    errors::{InterpreterError, TokenError},
+    interner::Symbol,
+    span::{Position, Span},
+};
 
 pub struct Scanner<R>
 where
@@ -23,6 +34,7 @@ where
         match self.reader.take() {
             Some(reader) => Ok(TokensIterator::new(reader)),
             None => Err(InterpreterError::scanning(
+                ErrorKind::Internal,
                 "Scanner's reader has already been consumed",
             )),
         }
@@ -33,7 +45,12 @@ pub struct TokensIterator {
     has_reached_eof: bool,
     content: Box<dyn Iterator<Item = char>>,
     buffer: VecDeque<Option<char>>,
-    line: usize,
+    /// Position of the next character.
+    position: Position,
+    /// Position of the first character of the token being scanned.
+    start: Position,
+    /// String literal values, so that equal literals share one string.
+    strings: HashMap<Rc<str>, Rc<String>>,
 }
 
 static KEYWORDS: &[(&str, TokenType)] = &[
@@ -44,6 +61,7 @@ static KEYWORDS: &[(&str, TokenType)] = &[
     ("for", TokenType::For),
     ("fun", TokenType::Fun),
     ("if", TokenType::If),
+    ("in", TokenType::In),
     ("nil", TokenType::Nil),
     ("or", TokenType::Or),
     ("print", TokenType::Print),
@@ -78,12 +96,24 @@ impl TokensIterator {
             has_reached_eof: false,
             content: Box::new(content),
             buffer,
-            line: 1,
+            position: Position::new(1, 1, 0),
+            start: Position::new(1, 1, 0),
+            strings: HashMap::new(),
         }
     }
 
-    pub fn current_line(&self) -> usize {
-        self.line
+    /// An empty span at the position of the next character.
+    pub fn current_span(&self) -> Span {
+        Span::new(self.position, self.position)
+    }
+
+    /// The span from the start of the token being scanned to the next character.
+    fn span(&self) -> Span {
+        Span::new(self.start, self.position)
+    }
+
+    fn token<S: Into<Symbol>>(&self, token_type: TokenType, lexeme: S) -> Token {
+        Token::new(token_type, lexeme, self.span())
     }
 
     fn next(&mut self) -> Option<char> {
@@ -99,8 +129,12 @@ impl TokensIterator {
                 return None;
             }
             Some(c) => {
+                self.position.offset += c.len_utf8();
                 if c == '\n' {
-                    self.line += 1;
+                    self.position.line += 1;
+                    self.position.column = 1;
+                } else {
+                    self.position.column += 1;
                 }
                 self.buffer.push_back(self.content.next());
                 return Some(c);
@@ -162,18 +196,28 @@ impl TokensIterator {
     }
 
     fn handle_string(&mut self) -> Result<Token, TokenError> {
-        let start_line = self.line;
         let mut buf = "\"".to_string();
         if self.advance_while(|i| i != '"', &mut buf) && self.next_is('"') {
             buf.push('"');
+            let lexeme: Rc<str> = buf.into();
+            let value = self
+                .strings
+                .entry(lexeme.clone())
+                .or_insert_with(|| Rc::new(lexeme[1..lexeme.len() - 1].to_string()))
+                .clone();
             return Ok(Token::with_literal(
                 TokenType::String,
-                buf.to_string(),
-                Literal::String(Rc::new(buf[1..buf.len() - 1].to_string())),
-                start_line,
+                lexeme,
+                Literal::String(value),
+                self.span(),
             ));
         } else {
-            TokenError::new("Unterminated string.", start_line).into()
+            TokenError::new(
+                ErrorKind::UnterminatedString,
+                "Unterminated string.",
+                self.span(),
+            )
+            .into()
         }
     }
 
@@ -189,7 +233,7 @@ impl TokensIterator {
             TokenType::Number,
             buf,
             Literal::Digit(value),
-            self.line,
+            self.span(),
         ));
     }
 
@@ -197,8 +241,8 @@ impl TokensIterator {
         let mut buf = initial_digit.to_string();
         self.advance_while(|i| i.is_alphanumeric() || i == '_', &mut buf);
         return match TokensIterator::is_keyword(buf.as_str()) {
-            Some(token_type) => Ok(Token::new(token_type, buf, self.line)),
-            None => Ok(Token::new(TokenType::Identifier, buf, self.line)),
+            Some(token_type) => Ok(self.token(token_type, buf)),
+            None => Ok(self.token(TokenType::Identifier, buf)),
         };
     }
 
@@ -221,40 +265,41 @@ impl Iterator for TokensIterator {
         }
 
         loop {
+            self.start = self.position;
             let Some(character) = self.next() else {
                 self.has_reached_eof = true;
-                return Some(Ok(Token::new(EOF, "", self.line)));
+                return Some(Ok(self.token(EOF, "")));
             };
 
             match character {
-                '(' => return Some(Ok(Token::new(LeftParenthesis, "(", self.line))),
-                ')' => return Some(Ok(Token::new(RightParenthesis, ")", self.line))),
-                '{' => return Some(Ok(Token::new(LeftBrace, "{", self.line))),
-                '}' => return Some(Ok(Token::new(RightBrace, "}", self.line))),
-                ',' => return Some(Ok(Token::new(Comma, ",", self.line))),
-                '.' => return Some(Ok(Token::new(Dot, ".", self.line))),
-                '-' => return Some(Ok(Token::new(Minus, "-", self.line))),
-                '+' => return Some(Ok(Token::new(Plus, "+", self.line))),
-                ';' => return Some(Ok(Token::new(Semicolon, ";", self.line))),
-                '*' => return Some(Ok(Token::new(Star, "*", self.line))),
-                '=' if self.next_is('=') => {
-                    return Some(Ok(Token::new(EqualEqual, "==", self.line)))
-                }
-                '=' => return Some(Ok(Token::new(Equal, "=", self.line))),
-                '!' if self.next_is('=') => {
-                    return Some(Ok(Token::new(BangEqual, "!=", self.line)))
+                '(' => return Some(Ok(self.token(LeftParenthesis, "("))),
+                ')' => return Some(Ok(self.token(RightParenthesis, ")"))),
+                '{' => return Some(Ok(self.token(LeftBrace, "{"))),
+                '}' => return Some(Ok(self.token(RightBrace, "}"))),
+                '[' => return Some(Ok(self.token(LeftBracket, "["))),
+                ']' => return Some(Ok(self.token(RightBracket, "]"))),
+                ',' => return Some(Ok(self.token(Comma, ","))),
+                '.' if self.next_is('.') => {
+                    if self.next_is('=') {
+                        return Some(Ok(self.token(DotDotEqual, "..=")));
+                    }
+                    return Some(Ok(self.token(DotDot, "..")));
                 }
-                '!' => return Some(Ok(Token::new(Bang, "!", self.line))),
-                '<' if self.next_is('=') => {
-                    return Some(Ok(Token::new(LessEqual, "<=", self.line)))
-                }
-                '<' => return Some(Ok(Token::new(Less, "<", self.line))),
-                '>' if self.next_is('=') => {
-                    return Some(Ok(Token::new(GreaterEqual, ">=", self.line)))
-                }
-                '>' => return Some(Ok(Token::new(Greater, ">", self.line))),
+                '.' => return Some(Ok(self.token(Dot, "."))),
+                '-' => return Some(Ok(self.token(Minus, "-"))),
+                '+' => return Some(Ok(self.token(Plus, "+"))),
+                ';' => return Some(Ok(self.token(Semicolon, ";"))),
+                '*' => return Some(Ok(self.token(Star, "*"))),
+                '=' if self.next_is('=') => return Some(Ok(self.token(EqualEqual, "=="))),
+                '=' => return Some(Ok(self.token(Equal, "="))),
+                '!' if self.next_is('=') => return Some(Ok(self.token(BangEqual, "!="))),
+                '!' => return Some(Ok(self.token(Bang, "!"))),
+                '<' if self.next_is('=') => return Some(Ok(self.token(LessEqual, "<="))),
+                '<' => return Some(Ok(self.token(Less, "<"))),
+                '>' if self.next_is('=') => return Some(Ok(self.token(GreaterEqual, ">="))),
+                '>' => return Some(Ok(self.token(Greater, ">"))),
                 '/' if self.next_is('/') => self.handle_line_comment(),
-                '/' => return Some(Ok(Token::new(Slash, "/", self.line))),
+                '/' => return Some(Ok(self.token(Slash, "/"))),
                 '"' => return Some(self.handle_string()),
                 c if c.is_digit(10) => return Some(self.handle_digit(c)),
                 ' ' | '\r' | '\n' | '\t' => {}
@@ -263,8 +308,12 @@ impl Iterator for TokensIterator {
                 }
                 _ => {
                     return Some(
-                        TokenError::new(format!("Unexpected character: {}", character), self.line)
-                            .into(),
+                        TokenError::new(
+                            ErrorKind::UnexpectedCharacter,
+                            format!("Unexpected character: {}", character),
+                            self.span(),
+                        )
+                        .into(),
                     )
                 }
             };
@@ -272,35 +321,69 @@ impl Iterator for TokensIterator {
     }
 }
 
+/// The source text of a token.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lexeme {
+    /// Names, keywords and punctuation, which are interned as they are few and repeated.
+    Symbol(Symbol),
+    /// Literals, which are seldom repeated and are freed along with their token.
+    Text(Rc<str>),
+}
+
+impl Deref for Lexeme {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Lexeme::Symbol(symbol) => symbol,
+            Lexeme::Text(text) => text,
+        }
+    }
+}
+
+impl Display for Lexeme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub token_type: TokenType,
-    pub lexeme: String,
+    pub lexeme: Lexeme,
     pub literal: Option<Rc<Literal>>,
-    pub line: usize,
+    pub span: Span,
 }
 
 impl Token {
-    pub fn new<S: ToString>(token_type: TokenType, lexeme: S, line: usize) -> Self {
+    pub fn new<S: Into<Symbol>>(token_type: TokenType, lexeme: S, span: Span) -> Self {
         Token {
             token_type,
-            lexeme: lexeme.to_string(),
+            lexeme: Lexeme::Symbol(lexeme.into()),
             literal: None,
-            line,
+            span,
         }
     }
 
-    pub fn with_literal<S: ToString>(
+    pub fn with_literal<S: Into<Rc<str>>>(
         token_type: TokenType,
         lexeme: S,
         literal: Literal,
-        line: usize,
+        span: Span,
     ) -> Self {
         Token {
             token_type,
-            lexeme: lexeme.to_string(),
+            lexeme: Lexeme::Text(lexeme.into()),
             literal: Some(Rc::new(literal)),
-            line,
+            span,
+        }
+    }
+
+    /// The interned name of an identifier, keyword or punctuation token.
+    pub fn symbol(&self) -> Symbol {
+        match &self.lexeme {
+            Lexeme::Symbol(symbol) => *symbol,
+            Lexeme::Text(_) => panic!("{} tokens are not interned", self.token_type),
         }
     }
 
@@ -335,8 +418,12 @@ pub enum TokenType {
     RightParenthesis,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
+    DotDot,
+    DotDotEqual,
     Minus,
     Plus,
     Semicolon,
@@ -360,6 +447,7 @@ pub enum TokenType {
     For,
     Fun,
     If,
+    In,
     Nil,
     Or,
     Print,
@@ -379,8 +467,12 @@ impl Display for TokenType {
             TokenType::RightParenthesis => write!(f, "RIGHT_PAREN"),
             TokenType::LeftBrace => write!(f, "LEFT_BRACE"),
             TokenType::RightBrace => write!(f, "RIGHT_BRACE"),
+            TokenType::LeftBracket => write!(f, "LEFT_BRACKET"),
+            TokenType::RightBracket => write!(f, "RIGHT_BRACKET"),
             TokenType::Comma => write!(f, "COMMA"),
             TokenType::Dot => write!(f, "DOT"),
+            TokenType::DotDot => write!(f, "DOT_DOT"),
+            TokenType::DotDotEqual => write!(f, "DOT_DOT_EQUAL"),
             TokenType::Minus => write!(f, "MINUS"),
             TokenType::Plus => write!(f, "PLUS"),
             TokenType::Semicolon => write!(f, "SEMICOLON"),
@@ -404,6 +496,7 @@ impl Display for TokenType {
             TokenType::For => write!(f, "FOR"),
             TokenType::Fun => write!(f, "FUN"),
             TokenType::If => write!(f, "IF"),
+            TokenType::In => write!(f, "IN"),
             TokenType::Nil => write!(f, "NIL"),
             TokenType::Or => write!(f, "OR"),
             TokenType::Print => write!(f, "PRINT"),
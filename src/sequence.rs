@@ -0,0 +1,156 @@
+use std::{fmt::Display, rc::Rc};
+
+use crate::{error_kind::ErrorKind, errors::InterpreterError, span::Span};
+
+/// Largest magnitude of a range bound. Past it, numbers no longer hold every integer, and lengths
+/// and offsets computed from the bounds could overflow.
+const MAX_BOUND: f64 = 9007199254740992.0;
+
+/// A range of integers, as produced by `a..b` and `a..=b`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    pub start: i64,
+    pub end: i64,
+    pub inclusive: bool,
+}
+
+impl Range {
+    pub fn new(
+        start: f64,
+        end: f64,
+        inclusive: bool,
+        span: Span,
+    ) -> Result<Self, InterpreterError> {
+        if start.fract() != 0.0 || end.fract() != 0.0 {
+            return Err(InterpreterError::evaluating(
+                ErrorKind::InvalidIndex,
+                "Range bounds must be integers",
+                span,
+            ));
+        }
+        if start.abs() > MAX_BOUND || end.abs() > MAX_BOUND {
+            return Err(InterpreterError::evaluating(
+                ErrorKind::InvalidIndex,
+                format!("Range bounds must be between -{0} and {0}", MAX_BOUND),
+                span,
+            ));
+        }
+
+        Ok(Self {
+            start: start as i64,
+            end: end as i64,
+            inclusive,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        let end = if self.inclusive {
+            self.end + 1
+        } else {
+            self.end
+        };
+        (end - self.start).max(0) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Resolves this range against a sequence of `len` items, negative bounds counting from the end.
+    pub fn bounds(
+        &self,
+        len: usize,
+        span: Span,
+    ) -> Result<std::ops::Range<usize>, InterpreterError> {
+        let normalize = |i: i64| if i < 0 { i + len as i64 } else { i };
+        let start = normalize(self.start);
+        let end = normalize(self.end) + self.inclusive as i64;
+
+        if start < 0 || end > len as i64 || start > end {
+            return Err(InterpreterError::evaluating(
+                ErrorKind::IndexOutOfBounds,
+                format!("Slice {} out of bounds for length {}", self, len),
+                span,
+            ));
+        }
+
+        Ok(start as usize..end as usize)
+    }
+
+    pub fn get(&self, index: f64, span: Span) -> Result<f64, InterpreterError> {
+        let index = sequence_index(index, self.len(), span)?;
+        Ok((self.start + index as i64) as f64)
+    }
+
+    pub fn slice(&self, slice: &Range, span: Span) -> Result<Range, InterpreterError> {
+        let bounds = slice.bounds(self.len(), span)?;
+        Ok(Range {
+            start: self.start + bounds.start as i64,
+            end: self.start + bounds.end as i64,
+            inclusive: false,
+        })
+    }
+
+    pub fn iter(&self) -> std::ops::Range<i64> {
+        self.start..self.start + self.len() as i64
+    }
+}
+
+impl Display for Range {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.inclusive {
+            write!(f, "{}..={}", self.start, self.end)
+        } else {
+            write!(f, "{}..{}", self.start, self.end)
+        }
+    }
+}
+
+/// Concatenates two strings, sharing an operand instead of allocating when the other is empty.
+pub fn concat(a: &Rc<String>, b: &Rc<String>) -> Rc<String> {
+    if b.is_empty() {
+        return a.clone();
+    }
+    if a.is_empty() {
+        return b.clone();
+    }
+
+    let mut s = String::with_capacity(a.len() + b.len());
+    s.push_str(a);
+    s.push_str(b);
+    Rc::new(s)
+}
+
+/// The character of `s` at `index`, counting from the end when negative.
+pub fn char_at(s: &str, index: f64, span: Span) -> Result<String, InterpreterError> {
+    let chars = s.chars().collect::<Vec<_>>();
+    let index = sequence_index(index, chars.len(), span)?;
+    Ok(chars[index].to_string())
+}
+
+pub fn substring(s: &str, range: &Range, span: Span) -> Result<String, InterpreterError> {
+    let bounds = range.bounds(s.chars().count(), span)?;
+    Ok(s.chars().skip(bounds.start).take(bounds.len()).collect())
+}
+
+fn sequence_index(index: f64, len: usize, span: Span) -> Result<usize, InterpreterError> {
+    if index.fract() != 0.0 {
+        return Err(InterpreterError::evaluating(
+            ErrorKind::InvalidIndex,
+            "Index must be an integer",
+            span,
+        ));
+    }
+
+    let index = index as i64;
+    let position = if index < 0 { index + len as i64 } else { index };
+    if position < 0 || position >= len as i64 {
+        return Err(InterpreterError::evaluating(
+            ErrorKind::IndexOutOfBounds,
+            format!("Index {} out of bounds for length {}", index, len),
+            span,
+        ));
+    }
+
+    Ok(position as usize)
+}
@@ -0,0 +1,1257 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    env,
+    error::Error,
+    fmt::Display,
+    io::{stdout, BufRead, Write},
+    ops::Deref,
+    rc::{Rc, Weak},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    bytecode::{Constant, FunctionProto, Op},
+    compiler::Compiler,
+    error_kind::ErrorKind,
+    errors::{ErrorMessage, InterpreterError, StackFrame, StackTrace},
+    heap::{Heap, HeapStats, Trace},
+    interner::{Names, Symbol},
+    interpreter::DEFAULT_MAX_CALL_DEPTH,
+    interrupt::InterruptHandle,
+    optimizer::Optimizer,
+    parser::Parser,
+    resolver::Resolver,
+    sequence::{self, Range},
+    span::Span,
+};
+
+/// Runs programs compiled to bytecode on a stack of values, as an alternative to the tree-walking
+/// [`Interpreter`](crate::interpreter::Interpreter). Both backends behave the same, down to
+/// their error messages.
+///
+/// Fuel counts instructions rather than statements and expressions, so a script uses different
+/// amounts of it on each backend. Memory cannot be limited on the VM.
+pub struct Vm {
+    parser: Option<Parser>,
+    output: Rc<RefCell<dyn Write>>,
+    stack: Vec<Value>,
+    frames: Vec<CallFrame>,
+    globals: Vec<Option<Value>>,
+    global_names: Vec<Symbol>,
+    open_upvalues: Vec<Rc<RefCell<Upvalue>>>,
+    stringifying: Vec<*const ()>,
+    heap: Heap,
+    max_call_depth: usize,
+    fuel: Option<u64>,
+    fuel_used: u64,
+    interrupt: InterruptHandle,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+    names: Names,
+}
+
+struct CallFrame {
+    closure: Rc<Closure>,
+    ip: usize,
+    /// Index of the frame's slot 0 in the stack.
+    base: usize,
+    /// What to do with the value the frame returns.
+    resume: Resume,
+    /// Line the function was called from, for stack traces.
+    line: usize,
+    /// Number of calls the frame replaced through tail calls.
+    tail_calls: usize,
+}
+
+/// What the VM does with the value a frame returns. Special methods run in frames like any other
+/// call, so the operation that called one finishes once it returns.
+enum Resume {
+    /// Gives the value to the caller.
+    Value,
+    /// Gives the caller the instance being constructed instead, as `init` was called for a class.
+    Construct,
+    /// Gives the caller the negated truthiness of the value, for `!=` dispatched to `__eq__`.
+    Not,
+    /// Converts the value returned by `toString()` to text for `Text`. Instances in
+    /// `stringifying` from the given length up are being converted for it.
+    Stringify(Text, usize),
+}
+
+/// What a value converted to text is for.
+enum Text {
+    Print,
+    /// Concatenating it after a string.
+    Append(Rc<String>),
+    /// Concatenating it before a string.
+    Prepend(Rc<String>),
+}
+
+impl Vm {
+    pub fn new(parser: Parser) -> Self {
+        Self::with_output(parser, Rc::new(RefCell::new(stdout())))
+    }
+
+    pub fn with_output(parser: Parser, output: Rc<RefCell<dyn Write>>) -> Self {
+        Self {
+            parser: Some(parser),
+            output,
+            stack: Vec::new(),
+            frames: Vec::new(),
+            globals: vec![
+                Some(Value::Native(Rc::new(Native::Clock))),
+                Some(Value::Native(Rc::new(Native::Env))),
+            ],
+            global_names: vec![Symbol::intern("clock"), Symbol::intern("env")],
+            open_upvalues: Vec::new(),
+            stringifying: Vec::new(),
+            heap: Heap::new(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            fuel: None,
+            fuel_used: 0,
+            interrupt: InterruptHandle::new(),
+            timeout: None,
+            deadline: None,
+            names: Names::new(),
+        }
+    }
+
+    pub fn build<R>(reader: R, output: Rc<RefCell<dyn Write>>) -> Result<Self, Box<dyn Error>>
+    where
+        R: BufRead + 'static,
+    {
+        let parser = Parser::build(reader)?;
+
+        Ok(Vm::with_output(parser, output))
+    }
+
+    /// Sets the number of nested calls after which calling a function fails with a stack overflow
+    /// error.
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        self.max_call_depth = max_call_depth;
+    }
+
+    /// Limits the number of instructions the script can run, after which it fails with
+    /// [`InterpreterError::OutOfFuel`]. There is no limit by default.
+    pub fn set_fuel(&mut self, fuel: u64) {
+        self.fuel = Some(fuel);
+    }
+
+    /// Number of instructions run so far, whether fuel is limited or not.
+    pub fn fuel_used(&self) -> u64 {
+        self.fuel_used
+    }
+
+    /// Returns a handle that stops the running script from another thread, making it fail with
+    /// [`InterpreterError::Interrupted`].
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
+    /// Makes `handle` stop this VM's runs, so that one handle can serve several VMs in turn.
+    pub fn set_interrupt_handle(&mut self, handle: InterruptHandle) {
+        self.interrupt = handle;
+    }
+
+    /// Limits the time a run can take, after which it fails with
+    /// [`InterpreterError::Interrupted`].
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = Some(timeout);
+    }
+
+    pub fn heap_stats(&self) -> HeapStats {
+        self.heap.stats()
+    }
+
+    pub fn run(&mut self) -> Result<(), InterpreterError> {
+        match self.parser.take() {
+            Some(mut parser) => {
+                let mut resolver = Resolver::new();
+                let statements = resolver.resolve_script(&mut parser)?;
+                let statements = Optimizer::new(&mut resolver.resolve_table).optimize(&statements);
+                let function = Compiler::compile(&statements, &mut self.global_names)?;
+                self.globals.resize(self.global_names.len(), None);
+
+                let closure = Rc::new(Closure {
+                    function,
+                    upvalues: Vec::new(),
+                });
+                self.stack.push(Value::Closure(closure.clone()));
+                self.deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+                let result = self
+                    .call_closure(closure, 0, Resume::Value, Span::default())
+                    .and_then(|_| self.execute());
+                // An interrupt the run ended without noticing is not meant for the next one.
+                self.interrupt.take();
+                if let Err(error) = result {
+                    let trace = self.stack_trace();
+                    self.stack.clear();
+                    self.frames.clear();
+                    self.open_upvalues.clear();
+                    self.stringifying.clear();
+                    return Err(match error.trace() {
+                        Some(_) => error,
+                        None => error.with_trace(trace),
+                    });
+                }
+
+                Ok(())
+            }
+            None => Err(InterpreterError::InterpreterError(ErrorMessage::new(
+                ErrorKind::Internal,
+                "Interpreter's statements have already been consumed",
+                None,
+            ))),
+        }
+    }
+
+    /// Runs instructions until the script's frame returns. Calls, including those of special
+    /// methods, push frames instead of running on the native stack.
+    fn execute(&mut self) -> Result<(), InterpreterError> {
+        loop {
+            self.consume_fuel()?;
+            let frame = self.frames.last_mut().expect("a frame should be running");
+            let op = frame.closure.function.chunk.code[frame.ip];
+            frame.ip += 1;
+
+            match op {
+                Op::Constant(index) => {
+                    let value = match &self.chunk_constant(index) {
+                        Constant::Number(n) => Value::Number(*n),
+                        Constant::String(s) => Value::String(s.clone()),
+                        Constant::Name(_) | Constant::Function(_) => {
+                            panic!("names and functions are not values")
+                        }
+                    };
+                    self.stack.push(value);
+                }
+                Op::Nil => self.stack.push(Value::Nil),
+                Op::True => self.stack.push(Value::Boolean(true)),
+                Op::False => self.stack.push(Value::Boolean(false)),
+                Op::Pop => {
+                    self.stack.pop();
+                }
+                Op::GetLocal(slot) => {
+                    let value = self.stack[self.frame().base + slot as usize].clone();
+                    self.stack.push(value);
+                }
+                Op::SetLocal(slot) => {
+                    let index = self.frame().base + slot as usize;
+                    self.stack[index] = self.peek(0).clone();
+                }
+                Op::GetGlobal(index) => match &self.globals[index as usize] {
+                    Some(value) => self.stack.push(value.clone()),
+                    None => {
+                        return Err(self.error(
+                            ErrorKind::UndefinedVariable,
+                            format!(
+                                "Undefined variable in scope: '{}'",
+                                self.global_names[index as usize]
+                            ),
+                        ))
+                    }
+                },
+                Op::DefineGlobal(index) => {
+                    self.globals[index as usize] = self.stack.pop();
+                }
+                Op::SetGlobal(index) => {
+                    if self.globals[index as usize].is_none() {
+                        return Err(self.error(
+                            ErrorKind::UndefinedVariable,
+                            format!("Undefined variable '{}'", self.global_names[index as usize]),
+                        ));
+                    }
+                    self.globals[index as usize] = Some(self.peek(0).clone());
+                }
+                Op::GetUpvalue(index) => {
+                    let upvalue = self.frame().closure.upvalues[index as usize].clone();
+                    let value = match upvalue.borrow().deref() {
+                        Upvalue::Open(slot) => self.stack[*slot].clone(),
+                        Upvalue::Closed(value) => value.clone(),
+                    };
+                    self.stack.push(value);
+                }
+                Op::SetUpvalue(index) => {
+                    let upvalue = self.frame().closure.upvalues[index as usize].clone();
+                    let value = self.peek(0).clone();
+                    let mut upvalue = upvalue.borrow_mut();
+                    match &mut *upvalue {
+                        Upvalue::Open(slot) => self.stack[*slot] = value,
+                        Upvalue::Closed(closed) => *closed = value,
+                    }
+                }
+                Op::CloseUpvalue => {
+                    self.close_upvalues(self.stack.len() - 1);
+                    self.stack.pop();
+                }
+                Op::GetProperty(name) => {
+                    let value = match self.pop() {
+                        Value::Instance(instance) => {
+                            let name = self.chunk_name(name);
+                            let field = instance.borrow().fields.get(&name).cloned();
+                            let method = instance.borrow().class.find_method(name);
+                            match (field, method) {
+                                (Some(value), _) => value,
+                                (None, Some(method)) => {
+                                    let method = Rc::new(BoundMethod {
+                                        receiver: instance,
+                                        method,
+                                    });
+                                    self.track(&method);
+                                    Value::BoundMethod(method)
+                                }
+                                (None, None) => Value::Nil,
+                            }
+                        }
+                        _ => {
+                            return Err(self
+                                .error(ErrorKind::NotAnInstance, "Only instances have properties"))
+                        }
+                    };
+                    self.stack.push(value);
+                }
+                Op::SetProperty(name) => {
+                    let value = self.pop();
+                    match self.pop() {
+                        Value::Instance(instance) => {
+                            let name = self.chunk_name(name);
+                            instance.borrow_mut().fields.insert(name, value);
+                            self.stack.push(Value::Nil);
+                        }
+                        _ => {
+                            return Err(self.error(
+                                ErrorKind::NotAnInstance,
+                                "Can only set properties on instances",
+                            ))
+                        }
+                    }
+                }
+                Op::GetSuper(name) => {
+                    let name = self.chunk_name(name);
+                    let super_class = self.pop();
+                    let this = self.pop();
+                    match (super_class, this) {
+                        (Value::Class(super_class), Value::Instance(this)) => {
+                            match super_class.find_method(name) {
+                                Some(method) => {
+                                    let method = Rc::new(BoundMethod {
+                                        receiver: this,
+                                        method,
+                                    });
+                                    self.track(&method);
+                                    self.stack.push(Value::BoundMethod(method));
+                                }
+                                None => {
+                                    return Err(self.error(
+                                        ErrorKind::UndefinedSuperMethod,
+                                        format!("Method '{}' not found on the super class", name),
+                                    ))
+                                }
+                            }
+                        }
+                        _ => {
+                            return Err(self.error(
+                                ErrorKind::UndefinedVariable,
+                                format!("Undefined 'super' or '{}' in scope", name),
+                            ))
+                        }
+                    }
+                }
+                Op::Equal
+                | Op::NotEqual
+                | Op::Greater
+                | Op::GreaterEqual
+                | Op::Less
+                | Op::LessEqual
+                | Op::Add
+                | Op::Subtract
+                | Op::Multiply
+                | Op::Divide
+                | Op::Range { .. } => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    if let Some(value) = self.binary(op, left, right)? {
+                        self.stack.push(value);
+                    }
+                }
+                Op::Index => {
+                    let index = self.pop();
+                    let target = self.pop();
+                    if let Some(value) = self.index(target, index)? {
+                        self.stack.push(value);
+                    }
+                }
+                Op::Not => {
+                    let value = self.pop();
+                    self.stack.push(Value::Boolean(!value.is_truthy()));
+                }
+                Op::Negate => match self.pop() {
+                    Value::Number(n) => self.stack.push(Value::Number(-n)),
+                    Value::Instance(instance)
+                        if self.call_special_method(
+                            &instance,
+                            self.names.neg,
+                            vec![],
+                            Resume::Value,
+                        )? => {}
+                    _ => {
+                        return Err(
+                            self.error(ErrorKind::InvalidOperand, "Operand must be a number")
+                        )
+                    }
+                },
+                Op::Print => {
+                    let value = self.pop();
+                    self.stringify(value, Text::Print, self.stringifying.len())?;
+                }
+                Op::Jump(offset) => self.frame_mut().ip += offset as usize,
+                Op::JumpIfFalse(offset) => {
+                    if !self.peek(0).is_truthy() {
+                        self.frame_mut().ip += offset as usize;
+                    }
+                }
+                Op::Loop(offset) => {
+                    self.check_interrupt()?;
+                    self.frame_mut().ip -= offset as usize;
+                }
+                Op::Iterate => {
+                    let iterator = match self.pop() {
+                        Value::Range(range) => Iter::Range(range.iter()),
+                        Value::String(s) => Iter::Chars(s.chars().collect::<Vec<_>>().into_iter()),
+                        _ => {
+                            return Err(self.error(
+                                ErrorKind::NotIterable,
+                                "Can only iterate over strings and ranges",
+                            ))
+                        }
+                    };
+                    self.stack
+                        .push(Value::Iterator(Rc::new(RefCell::new(iterator))));
+                }
+                Op::Next(offset) => match self.pop() {
+                    Value::Iterator(iterator) => match iterator.borrow_mut().next() {
+                        Some(item) => self.stack.push(item),
+                        None => self.frame_mut().ip += offset as usize,
+                    },
+                    _ => panic!("for in loops should iterate over an iterator"),
+                },
+                Op::Call(count) => {
+                    let span = self.span();
+                    self.call_value(count as usize, span)?;
+                }
+                Op::TailCall(count) => {
+                    let span = self.span();
+                    let count = count as usize;
+                    let callee = self.stack.len() - count - 1;
+                    if !matches!(
+                        self.stack[callee],
+                        Value::Closure(_) | Value::BoundMethod(_)
+                    ) {
+                        self.call_value(count, span)?;
+                        continue;
+                    }
+
+                    // Move the callee and its arguments over the running frame, which the call
+                    // then replaces, returning where the running frame would have.
+                    let frame = self.frames.pop().expect("a frame should be running");
+                    self.close_upvalues(frame.base);
+                    let call = self.stack.split_off(callee);
+                    self.stack.truncate(frame.base);
+                    self.stack.extend(call);
+                    self.call_value(count, span)?;
+                    let callee = self.frame_mut();
+                    callee.resume = frame.resume;
+                    callee.tail_calls = frame.tail_calls + 1;
+                }
+                Op::Closure(index) => {
+                    let function = match &self.chunk_constant(index) {
+                        Constant::Function(function) => function.clone(),
+                        _ => panic!("closures should be created from functions"),
+                    };
+                    let base = self.frame().base;
+                    let upvalues = function
+                        .upvalues
+                        .iter()
+                        .map(|source| match source.is_local {
+                            true => self.capture_upvalue(base + source.index as usize),
+                            false => self.frame().closure.upvalues[source.index as usize].clone(),
+                        })
+                        .collect();
+                    let closure = Rc::new(Closure { function, upvalues });
+                    self.track(&closure);
+                    self.stack.push(Value::Closure(closure));
+                }
+                Op::Return => {
+                    let result = self.pop();
+                    let frame = self.frames.pop().expect("a frame should be running");
+                    self.close_upvalues(frame.base);
+                    let receiver = self.stack[frame.base].clone();
+                    self.stack.truncate(frame.base);
+
+                    if self.frames.is_empty() {
+                        return Ok(());
+                    }
+                    match frame.resume {
+                        Resume::Value => self.stack.push(result),
+                        Resume::Construct => self.stack.push(receiver),
+                        Resume::Not => self.stack.push(Value::Boolean(!result.is_truthy())),
+                        Resume::Stringify(text, mark) => self.stringify(result, text, mark)?,
+                    }
+                }
+                Op::Class(name) => {
+                    let class = Rc::new(Class {
+                        name: self.chunk_name(name),
+                        methods: RefCell::new(HashMap::new()),
+                    });
+                    self.track(&class);
+                    self.stack.push(Value::Class(class));
+                }
+                Op::Inherit => {
+                    let class = self.pop();
+                    match (self.peek(0), class) {
+                        (Value::Class(super_class), Value::Class(class)) => {
+                            let methods = super_class.methods.borrow().clone();
+                            class.methods.borrow_mut().extend(methods);
+                        }
+                        _ => {
+                            return Err(self.error(
+                                ErrorKind::SuperclassNotAClass,
+                                "super class must be a class type",
+                            ))
+                        }
+                    }
+                }
+                Op::Method(name) => {
+                    let name = self.chunk_name(name);
+                    let method = self.pop();
+                    match (self.peek(0), method) {
+                        (Value::Class(class), Value::Closure(method)) => {
+                            class.methods.borrow_mut().insert(name, method);
+                        }
+                        _ => panic!("methods should be closures defined on a class"),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Applies a binary operator. Returns `None` when the result is left to a special method or a
+    /// call to `toString()`, which pushes it once it returns.
+    fn binary(
+        &mut self,
+        op: Op,
+        left: Value,
+        right: Value,
+    ) -> Result<Option<Value>, InterpreterError> {
+        if let Value::Instance(instance) = &left {
+            if self.call_binary_operator(instance, op, &right)? {
+                return Ok(None);
+            }
+        }
+
+        match (op, left, right) {
+            (Op::Add, Value::String(a), Value::Instance(b)) => {
+                self.concat(b, Text::Append(a)).map(|()| None)
+            }
+            (Op::Add, Value::Instance(a), Value::String(b)) => {
+                self.concat(a, Text::Prepend(b)).map(|()| None)
+            }
+            (op, left, right) => self.binary_values(op, left, right).map(Some),
+        }
+    }
+
+    fn binary_values(
+        &mut self,
+        op: Op,
+        left: Value,
+        right: Value,
+    ) -> Result<Value, InterpreterError> {
+        match (op, left, right) {
+            (Op::Add, Value::Number(a), Value::Number(b)) => Ok(Value::Number(a + b)),
+            (Op::Add, Value::String(a), Value::String(b)) => {
+                Ok(Value::String(sequence::concat(&a, &b)))
+            }
+            (Op::Add, _, _) => Err(self.error(
+                ErrorKind::InvalidOperand,
+                "Operands must be two numbers or two strings",
+            )),
+            (Op::Subtract, Value::Number(a), Value::Number(b)) => Ok(Value::Number(a - b)),
+            (Op::Subtract, _, _) => Err(self.error(
+                ErrorKind::InvalidOperand,
+                "Operands must be two numbers or two strings",
+            )),
+            (Op::Divide, Value::Number(a), Value::Number(b)) => Ok(Value::Number(a / b)),
+            (Op::Divide, _, _) => {
+                Err(self.error(ErrorKind::InvalidOperand, "Operands must be numbers"))
+            }
+            (Op::Multiply, Value::Number(a), Value::Number(b)) => Ok(Value::Number(a * b)),
+            (Op::Multiply, _, _) => {
+                Err(self.error(ErrorKind::InvalidOperand, "Unrecognized binary expression"))
+            }
+            (Op::Greater, Value::Number(a), Value::Number(b)) => Ok(Value::Boolean(a > b)),
+            (Op::GreaterEqual, Value::Number(a), Value::Number(b)) => Ok(Value::Boolean(a >= b)),
+            (Op::Less, Value::Number(a), Value::Number(b)) => Ok(Value::Boolean(a < b)),
+            (Op::LessEqual, Value::Number(a), Value::Number(b)) => Ok(Value::Boolean(a <= b)),
+            (Op::Greater, Value::String(a), Value::String(b)) => Ok(Value::Boolean(a > b)),
+            (Op::GreaterEqual, Value::String(a), Value::String(b)) => Ok(Value::Boolean(a >= b)),
+            (Op::Less, Value::String(a), Value::String(b)) => Ok(Value::Boolean(a < b)),
+            (Op::LessEqual, Value::String(a), Value::String(b)) => Ok(Value::Boolean(a <= b)),
+            (Op::Greater | Op::GreaterEqual | Op::Less | Op::LessEqual, a, b) => Err(self.error(
+                ErrorKind::InvalidOperand,
+                format!(
+                    "Operator '{}' cannot compare {} and {}",
+                    operator_lexeme(op),
+                    a.type_name(),
+                    b.type_name()
+                ),
+            )),
+            (Op::Equal, a, b) => Ok(Value::Boolean(a == b)),
+            (Op::NotEqual, a, b) => Ok(Value::Boolean(a != b)),
+            (Op::Range { inclusive }, Value::Number(a), Value::Number(b)) => {
+                Ok(Value::Range(Range::new(a, b, inclusive, self.span())?))
+            }
+            (Op::Range { .. }, _, _) => {
+                Err(self.error(ErrorKind::InvalidOperand, "Range bounds must be numbers"))
+            }
+            _ => Err(self.error(ErrorKind::InvalidOperand, "Unrecognized binary expression")),
+        }
+    }
+
+    /// Indexes a sequence. Returns `None` when the result is left to `__getitem__`, which pushes
+    /// it once it returns.
+    fn index(&mut self, target: Value, index: Value) -> Result<Option<Value>, InterpreterError> {
+        if let Value::Instance(instance) = &target {
+            let arguments = vec![index.clone()];
+            if self.call_special_method(instance, self.names.get_item, arguments, Resume::Value)? {
+                return Ok(None);
+            }
+        }
+
+        let span = self.span();
+        let value = match (target, index) {
+            (Value::String(s), Value::Number(i)) => {
+                Ok(Value::String(Rc::new(sequence::char_at(&s, i, span)?)))
+            }
+            (Value::String(s), Value::Range(range)) => Ok(Value::String(Rc::new(
+                sequence::substring(&s, &range, span)?,
+            ))),
+            (Value::Range(range), Value::Number(i)) => Ok(Value::Number(range.get(i, span)?)),
+            (Value::Range(range), Value::Range(slice)) => {
+                Ok(Value::Range(range.slice(&slice, span)?))
+            }
+            (Value::String(_) | Value::Range(_), _) => {
+                Err(self.error(ErrorKind::InvalidIndex, "Index must be a number or a range"))
+            }
+            _ => Err(self.error(
+                ErrorKind::NotIndexable,
+                "Only strings and ranges can be indexed",
+            )),
+        };
+        value.map(Some)
+    }
+
+    /// Calls the value below `count` arguments on the stack. Lox functions get a new frame, which
+    /// the caller's loop then runs, while natives return right away.
+    fn call_value(&mut self, count: usize, span: Span) -> Result<(), InterpreterError> {
+        let base = self.stack.len() - count - 1;
+        match self.stack[base].clone() {
+            Value::Closure(closure) => self.call_closure(closure, count, Resume::Value, span),
+            Value::BoundMethod(method) => {
+                self.stack[base] = Value::Instance(method.receiver.clone());
+                self.call_closure(method.method.clone(), count, Resume::Value, span)
+            }
+            Value::Class(class) => {
+                let instance = Rc::new(RefCell::new(Instance {
+                    class: class.clone(),
+                    fields: HashMap::new(),
+                }));
+                self.track(&instance);
+                self.stack[base] = Value::Instance(instance);
+                match class.find_method(self.names.init) {
+                    Some(init) => self.call_closure(init, count, Resume::Construct, span),
+                    None => {
+                        self.stack.truncate(base + 1);
+                        Ok(())
+                    }
+                }
+            }
+            Value::Native(native) => {
+                if count != native.arity() {
+                    return Err(arity_error(native.deref(), native.arity(), count, span));
+                }
+                let arguments = self.stack.split_off(base + 1);
+                self.stack.pop();
+                let result = native.call(arguments, span).map_err(|error| {
+                    let mut trace = self.stack_trace();
+                    trace.frames.push(StackFrame::new(&native, span.line(), 0));
+                    error.with_trace(trace)
+                })?;
+                self.stack.push(result);
+                Ok(())
+            }
+            _ => Err(InterpreterError::evaluating(
+                ErrorKind::NotCallable,
+                "Can only call functions, instances and methods",
+                span,
+            )),
+        }
+    }
+
+    fn call_closure(
+        &mut self,
+        closure: Rc<Closure>,
+        count: usize,
+        resume: Resume,
+        span: Span,
+    ) -> Result<(), InterpreterError> {
+        if count != closure.function.arity {
+            return Err(arity_error(
+                closure.function.deref(),
+                closure.function.arity,
+                count,
+                span,
+            ));
+        }
+        // The script itself runs in the first frame, which does not count as a call.
+        if self.frames.len() > self.max_call_depth {
+            return Err(InterpreterError::stack_overflow(span));
+        }
+        if !self.frames.is_empty() {
+            self.check_interrupt()?;
+        }
+
+        self.frames.push(CallFrame {
+            closure,
+            ip: 0,
+            base: self.stack.len() - count - 1,
+            resume,
+            line: span.line(),
+            tail_calls: 0,
+        });
+        Ok(())
+    }
+
+    fn capture_upvalue(&mut self, slot: usize) -> Rc<RefCell<Upvalue>> {
+        for upvalue in self.open_upvalues.iter() {
+            if matches!(*upvalue.borrow(), Upvalue::Open(open) if open == slot) {
+                return upvalue.clone();
+            }
+        }
+
+        let upvalue = Rc::new(RefCell::new(Upvalue::Open(slot)));
+        self.track(&upvalue);
+        self.open_upvalues.push(upvalue.clone());
+        upvalue
+    }
+
+    /// Moves the values of the stack slots from `from` up that are captured by closures into
+    /// their upvalues, as those slots are about to be popped.
+    fn close_upvalues(&mut self, from: usize) {
+        let stack = &self.stack;
+        self.open_upvalues.retain(|upvalue| {
+            let slot = match *upvalue.borrow() {
+                Upvalue::Open(slot) if slot >= from => slot,
+                _ => return true,
+            };
+            *upvalue.borrow_mut() = Upvalue::Closed(stack[slot].clone());
+            false
+        });
+    }
+
+    /// Converts a value to text for `text`, calling `toString()` on instances whose class defines
+    /// it. An instance already being converted further up, as happens when objects reference each
+    /// other, gets its default text. Instances in `stringifying` from `mark` up are being converted
+    /// for this value, and are done with once it has its text.
+    fn stringify(&mut self, value: Value, text: Text, mark: usize) -> Result<(), InterpreterError> {
+        if let Value::Instance(instance) = &value {
+            let id = Rc::as_ptr(instance) as *const ();
+            let method = instance.borrow().class.find_method(self.names.to_string);
+            if let (false, Some(method)) = (self.stringifying.contains(&id), method) {
+                self.stringifying.push(id);
+                return self.call_method(instance, method, vec![], Resume::Stringify(text, mark));
+            }
+        }
+
+        self.stringifying.truncate(mark);
+        match text {
+            Text::Print => {
+                writeln!(self.output.borrow_mut(), "{}", value).expect("cannot write to output")
+            }
+            Text::Append(a) => self
+                .stack
+                .push(Value::String(Rc::new(format!("{}{}", a, value)))),
+            Text::Prepend(b) => self
+                .stack
+                .push(Value::String(Rc::new(format!("{}{}", value, b)))),
+        }
+        Ok(())
+    }
+
+    /// Concatenates the text of `instance` with a string, which needs `toString()` unless the
+    /// instance is already being converted.
+    fn concat(
+        &mut self,
+        instance: Rc<RefCell<Instance>>,
+        text: Text,
+    ) -> Result<(), InterpreterError> {
+        let id = Rc::as_ptr(&instance) as *const ();
+        let method = instance.borrow().class.find_method(self.names.to_string);
+        if method.is_none() && !self.stringifying.contains(&id) {
+            return Err(self.error(
+                ErrorKind::InvalidOperand,
+                "Operands must be two numbers or two strings",
+            ));
+        }
+
+        self.stringify(Value::Instance(instance), text, self.stringifying.len())
+    }
+
+    /// Dispatches a binary operator to the left operand's special method, if its class has one.
+    /// Returns whether the operator was dispatched.
+    fn call_binary_operator(
+        &mut self,
+        instance: &Rc<RefCell<Instance>>,
+        op: Op,
+        right: &Value,
+    ) -> Result<bool, InterpreterError> {
+        let name = match op {
+            Op::Add => self.names.add,
+            Op::Subtract => self.names.sub,
+            Op::Multiply => self.names.mul,
+            Op::Divide => self.names.div,
+            Op::Equal => self.names.eq,
+            Op::NotEqual => self.names.ne,
+            Op::Less => self.names.lt,
+            Op::LessEqual => self.names.le,
+            Op::Greater => self.names.gt,
+            Op::GreaterEqual => self.names.ge,
+            _ => return Ok(false),
+        };
+
+        if self.call_special_method(instance, name, vec![right.clone()], Resume::Value)? {
+            return Ok(true);
+        }
+        if op == Op::NotEqual {
+            return self.call_special_method(
+                instance,
+                self.names.eq,
+                vec![right.clone()],
+                Resume::Not,
+            );
+        }
+        Ok(false)
+    }
+
+    /// Calls the method `name` on `instance` if its class defines it, returning whether it does.
+    /// The method runs in a new frame, and `resume` says what to do with its result.
+    fn call_special_method(
+        &mut self,
+        instance: &Rc<RefCell<Instance>>,
+        name: Symbol,
+        arguments: Vec<Value>,
+        resume: Resume,
+    ) -> Result<bool, InterpreterError> {
+        let method = instance.borrow().class.find_method(name);
+        match method {
+            Some(method) => self
+                .call_method(instance, method, arguments, resume)
+                .map(|()| true),
+            None => Ok(false),
+        }
+    }
+
+    fn call_method(
+        &mut self,
+        instance: &Rc<RefCell<Instance>>,
+        method: Rc<Closure>,
+        arguments: Vec<Value>,
+        resume: Resume,
+    ) -> Result<(), InterpreterError> {
+        if arguments.len() != method.function.arity {
+            return Err(self.error(
+                ErrorKind::ArityMismatch,
+                format!(
+                    "Expected {} arguments for method '{}' but got {}",
+                    method.function.arity,
+                    method.function.name,
+                    arguments.len()
+                ),
+            ));
+        }
+
+        let count = arguments.len();
+        let span = self.span();
+        self.stack.push(Value::Instance(instance.clone()));
+        self.stack.extend(arguments);
+        self.call_closure(method, count, resume, span)
+    }
+
+    /// Fails if the run was interrupted or is past its deadline. Checked at loop back-edges and
+    /// calls, which every long running script goes through.
+    fn check_interrupt(&mut self) -> Result<(), InterpreterError> {
+        if self.interrupt.take() {
+            return Err(InterpreterError::interrupted());
+        }
+        match (self.deadline, self.timeout) {
+            (Some(deadline), Some(timeout)) if Instant::now() >= deadline => {
+                Err(InterpreterError::timed_out(timeout))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts one instruction, failing once the fuel runs out.
+    fn consume_fuel(&mut self) -> Result<(), InterpreterError> {
+        if let Some(fuel) = self.fuel {
+            if self.fuel_used >= fuel {
+                return Err(InterpreterError::out_of_fuel(fuel));
+            }
+        }
+        self.fuel_used += 1;
+        Ok(())
+    }
+
+    fn track<T: Trace + 'static>(&mut self, object: &Rc<T>) {
+        self.heap.track(Rc::downgrade(object) as Weak<dyn Trace>);
+        if self.heap.should_collect() {
+            self.heap.collect();
+        }
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("stack should not be empty")
+    }
+
+    fn peek(&self, distance: usize) -> &Value {
+        &self.stack[self.stack.len() - 1 - distance]
+    }
+
+    fn frame(&self) -> &CallFrame {
+        self.frames.last().expect("a frame should be running")
+    }
+
+    fn frame_mut(&mut self) -> &mut CallFrame {
+        self.frames.last_mut().expect("a frame should be running")
+    }
+
+    fn chunk_constant(&self, index: u32) -> &Constant {
+        &self.frame().closure.function.chunk.constants[index as usize]
+    }
+
+    fn chunk_name(&self, index: u32) -> Symbol {
+        self.frame().closure.function.chunk.name(index)
+    }
+
+    /// Source code of the instruction being run.
+    fn span(&self) -> Span {
+        let frame = self.frame();
+        frame.closure.function.chunk.spans[frame.ip - 1]
+    }
+
+    /// The calls running, from the outermost to the innermost. The script's own frame is not a
+    /// call.
+    fn stack_trace(&self) -> StackTrace {
+        StackTrace::new(
+            self.frames
+                .iter()
+                .skip(1)
+                .map(|frame| {
+                    StackFrame::new(frame.closure.function.name, frame.line, frame.tail_calls)
+                })
+                .collect(),
+        )
+    }
+
+    fn error<T: ToString>(&self, kind: ErrorKind, message: T) -> InterpreterError {
+        InterpreterError::evaluating(kind, message, self.span())
+    }
+}
+
+fn arity_error(function: &dyn Display, arity: usize, count: usize, span: Span) -> InterpreterError {
+    InterpreterError::evaluating(
+        ErrorKind::ArityMismatch,
+        format!(
+            "Expected {} arguments for function '{}' but got {}",
+            arity, function, count
+        ),
+        span,
+    )
+}
+
+fn operator_lexeme(op: Op) -> &'static str {
+    match op {
+        Op::Greater => ">",
+        Op::GreaterEqual => ">=",
+        Op::Less => "<",
+        Op::LessEqual => "<=",
+        _ => panic!("{:?} is not a comparison", op),
+    }
+}
+
+#[derive(Clone)]
+enum Value {
+    Nil,
+    Boolean(bool),
+    Number(f64),
+    String(Rc<String>),
+    Range(Range),
+    Native(Rc<Native>),
+    Closure(Rc<Closure>),
+    BoundMethod(Rc<BoundMethod>),
+    Class(Rc<Class>),
+    Instance(Rc<RefCell<Instance>>),
+    /// State of a `for in` loop, never visible to programs.
+    Iterator(Rc<RefCell<Iter>>),
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Nil => "nil",
+            Value::Boolean(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Range(_) => "range",
+            Value::Native(_) | Value::Closure(_) | Value::BoundMethod(_) => "function",
+            Value::Class(_) => "class",
+            Value::Instance(_) => "instance",
+            Value::Iterator(_) => "iterator",
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Nil => false,
+            Value::Boolean(b) => *b,
+            _ => true,
+        }
+    }
+}
+
+/// Primitives compare by value; functions, classes and instances compare by identity. Strings are
+/// not interned, but equal literals share one string, which is compared by pointer first.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Nil, Value::Nil) => true,
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::String(a), Value::String(b)) => Rc::ptr_eq(a, b) || a == b,
+            (Value::Range(a), Value::Range(b)) => a == b,
+            (Value::Native(a), Value::Native(b)) => Rc::ptr_eq(a, b),
+            (Value::Closure(a), Value::Closure(b)) => Rc::ptr_eq(a, b),
+            (Value::BoundMethod(a), Value::BoundMethod(b)) => {
+                Rc::ptr_eq(&a.receiver, &b.receiver) && Rc::ptr_eq(&a.method, &b.method)
+            }
+            (Value::Class(a), Value::Class(b)) => Rc::ptr_eq(a, b),
+            (Value::Instance(a), Value::Instance(b)) => Rc::ptr_eq(a, b),
+            (Value::Iterator(a), Value::Iterator(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Nil => write!(f, "nil"),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::String(s) => write!(f, "{}", s),
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::Range(range) => write!(f, "{}", range),
+            Value::Native(native) => write!(f, "{}", native),
+            Value::Closure(closure) => write!(f, "{}", closure.function),
+            Value::BoundMethod(method) => write!(f, "{}", method.method.function),
+            Value::Class(class) => write!(f, "class {} {{...}}", class.name),
+            Value::Instance(instance) => write!(f, "{}", instance.borrow()),
+            Value::Iterator(_) => write!(f, "<iterator>"),
+        }
+    }
+}
+
+impl Trace for Value {
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+        match self {
+            Value::Closure(closure) => visit(Rc::as_ptr(closure) as *const ()),
+            Value::BoundMethod(method) => visit(Rc::as_ptr(method) as *const ()),
+            Value::Class(class) => visit(Rc::as_ptr(class) as *const ()),
+            Value::Instance(instance) => visit(Rc::as_ptr(instance) as *const ()),
+            Value::Nil
+            | Value::Boolean(_)
+            | Value::Number(_)
+            | Value::String(_)
+            | Value::Range(_)
+            | Value::Native(_)
+            | Value::Iterator(_) => {}
+        }
+    }
+}
+
+enum Native {
+    Clock,
+    Env,
+}
+
+impl Native {
+    fn arity(&self) -> usize {
+        match self {
+            Native::Clock => 0,
+            Native::Env => 1,
+        }
+    }
+
+    fn call(&self, arguments: Vec<Value>, span: Span) -> Result<Value, InterpreterError> {
+        match self {
+            Native::Clock => match SystemTime::now().duration_since(UNIX_EPOCH) {
+                Ok(duration) => Ok(Value::Number(duration.as_secs() as f64)),
+                Err(error) => Err(InterpreterError::RuntimeError(ErrorMessage::new(
+                    ErrorKind::NativeFunctionFailed,
+                    format!("System time error: {}", error),
+                    Some(span),
+                ))),
+            },
+            Native::Env => match arguments.as_slice() {
+                [Value::String(key)] => match env::var(key.as_str()) {
+                    Ok(value) => Ok(Value::String(Rc::new(value))),
+                    Err(_) => Ok(Value::Nil),
+                },
+                _ => Err(InterpreterError::RuntimeError(ErrorMessage::new(
+                    ErrorKind::NativeFunctionFailed,
+                    "Invalid argument to 'env' function",
+                    Some(span),
+                ))),
+            },
+        }
+    }
+}
+
+impl Display for Native {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Native::Clock => write!(f, "clock"),
+            Native::Env => write!(f, "env"),
+        }
+    }
+}
+
+struct Closure {
+    function: Rc<FunctionProto>,
+    upvalues: Vec<Rc<RefCell<Upvalue>>>,
+}
+
+impl Trace for Closure {
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+        for upvalue in self.upvalues.iter() {
+            visit(Rc::as_ptr(upvalue) as *const ());
+        }
+    }
+}
+
+/// A variable captured by a closure: a stack slot while the variable is in scope, then the value
+/// itself once the slot is popped.
+enum Upvalue {
+    Open(usize),
+    Closed(Value),
+}
+
+impl Trace for RefCell<Upvalue> {
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+        if let Ok(upvalue) = self.try_borrow() {
+            if let Upvalue::Closed(value) = upvalue.deref() {
+                value.trace(visit);
+            }
+        }
+    }
+
+    fn clear(&self) {
+        if let Ok(mut upvalue) = self.try_borrow_mut() {
+            if let Upvalue::Closed(value) = &mut *upvalue {
+                *value = Value::Nil;
+            }
+        }
+    }
+}
+
+struct BoundMethod {
+    receiver: Rc<RefCell<Instance>>,
+    method: Rc<Closure>,
+}
+
+impl Trace for BoundMethod {
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+        visit(Rc::as_ptr(&self.receiver) as *const ());
+        visit(Rc::as_ptr(&self.method) as *const ());
+    }
+}
+
+/// A class, holding its own methods as well as the ones it inherits.
+struct Class {
+    name: Symbol,
+    methods: RefCell<HashMap<Symbol, Rc<Closure>>>,
+}
+
+impl Class {
+    fn find_method(&self, name: Symbol) -> Option<Rc<Closure>> {
+        self.methods.borrow().get(&name).cloned()
+    }
+}
+
+impl Trace for Class {
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+        if let Ok(methods) = self.methods.try_borrow() {
+            for method in methods.values() {
+                visit(Rc::as_ptr(method) as *const ());
+            }
+        }
+    }
+
+    fn clear(&self) {
+        if let Ok(mut methods) = self.methods.try_borrow_mut() {
+            methods.clear();
+        }
+    }
+}
+
+struct Instance {
+    class: Rc<Class>,
+    fields: HashMap<Symbol, Value>,
+}
+
+impl Trace for RefCell<Instance> {
+    fn trace(&self, visit: &mut dyn FnMut(*const ())) {
+        if let Ok(instance) = self.try_borrow() {
+            visit(Rc::as_ptr(&instance.class) as *const ());
+            for value in instance.fields.values() {
+                value.trace(visit);
+            }
+        }
+    }
+
+    fn clear(&self) {
+        if let Ok(mut instance) = self.try_borrow_mut() {
+            instance.fields.clear();
+        }
+    }
+}
+
+impl Display for Instance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} instance", self.class.name)
+    }
+}
+
+enum Iter {
+    Range(std::ops::Range<i64>),
+    Chars(std::vec::IntoIter<char>),
+}
+
+impl Iterator for Iter {
+    type Item = Value;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Iter::Range(range) => range.next().map(|i| Value::Number(i as f64)),
+            Iter::Chars(chars) => chars.next().map(|c| Value::String(Rc::new(c.to_string()))),
+        }
+    }
+}
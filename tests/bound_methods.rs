@@ -0,0 +1,156 @@
+use crate::common::interpreter;
+
+mod common;
+
+#[test]
+fn stored_method_keeps_its_receiver() {
+    let (output, err) = interpreter::run_content(
+        r#"class Person {
+    init(name) { this.name = name; }
+    greet() { print "I am " + this.name; }
+}
+
+var a = Person("a");
+var b = Person("b");
+var greet = a.greet;
+b.greet;
+greet();
+b.greet();"#,
+    );
+    assert_none!(err);
+    assert_eq!("I am a\nI am b\n", output);
+}
+
+#[test]
+fn method_as_callback() {
+    let (output, err) = interpreter::run_content(
+        r#"class Counter {
+    init() { this.count = 0; }
+    increment() { this.count = this.count + 1; }
+}
+
+fun twice(f) {
+    f();
+    f();
+}
+
+var a = Counter();
+var b = Counter();
+twice(a.increment);
+twice(b.increment);
+twice(a.increment);
+print a.count;
+print b.count;"#,
+    );
+    assert_none!(err);
+    assert_eq!("4\n2\n", output);
+}
+
+#[test]
+fn repeated_reads_do_not_nest() {
+    let (output, err) = interpreter::run_content(
+        r#"class Foo {
+    init() { this.value = "foo"; }
+    get() { return this.value; }
+}
+
+var foo = Foo();
+var method;
+for (var i in 0..1000) {
+    method = foo.get;
+}
+print method();"#,
+    );
+    assert_none!(err);
+    assert_eq!("foo\n", output);
+}
+
+#[test]
+fn recursive_method() {
+    let (output, err) = interpreter::run_content(
+        r#"class Math {
+    fib(n) {
+        if (n < 2) return n;
+        return this.fib(n - 1) + this.fib(n - 2);
+    }
+}
+
+print Math().fib(10);"#,
+    );
+    assert_none!(err);
+    assert_eq!("55\n", output);
+}
+
+#[test]
+fn method_calling_same_method_on_other_instance() {
+    let (output, err) = interpreter::run_content(
+        r#"class Node {
+    init(name, next) {
+        this.name = name;
+        this.next = next;
+    }
+
+    names() {
+        if (this.next == nil) return this.name;
+        return this.name + this.next.names();
+    }
+}
+
+print Node("a", Node("b", Node("c", nil))).names();"#,
+    );
+    assert_none!(err);
+    assert_eq!("abc\n", output);
+}
+
+#[test]
+fn stored_super_method() {
+    let (output, err) = interpreter::run_content(
+        r#"class Base {
+    describe() { return "base of " + this.name; }
+}
+
+class Derived < Base {
+    init(name) { this.name = name; }
+    parent() { return super.describe; }
+}
+
+var a = Derived("a");
+var b = Derived("b");
+var describe = a.parent();
+b.parent();
+print describe();"#,
+    );
+    assert_none!(err);
+    assert_eq!("base of a\n", output);
+}
+
+#[test]
+fn print_bound_method() {
+    let (output, err) = interpreter::run_content(
+        r#"class Foo {
+    bar() {}
+}
+
+print Foo().bar;"#,
+    );
+    assert_none!(err);
+    assert_eq!("<fn bar>\n", output);
+}
+
+#[test]
+fn bound_method_equality() {
+    let (output, err) = interpreter::run_content(
+        r#"class Foo {
+    bar() {}
+    baz() {}
+}
+
+var a = Foo();
+var b = Foo();
+print a.bar == a.bar;
+print a.bar == b.bar;
+print a.bar == a.baz;"#,
+    );
+    assert_none!(err);
+    assert_eq!("true\nfalse\nfalse\n", output);
+}
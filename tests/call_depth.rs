@@ -0,0 +1,79 @@
+use interpreter_starter_rust::errors::InterpreterError;
+
+use crate::common::interpreter;
+
+mod common;
+
+#[test]
+fn calls_up_to_the_limit() {
+    let (output, err) = interpreter::run_content_with_max_call_depth(
+        r#"fun count(n) {
+    if (n == 1) return 1;
+    return 1 + count(n - 1);
+}
+print count(20);"#,
+        20,
+    );
+    assert_none!(err);
+    assert_eq!("20\n", output);
+}
+
+#[test]
+fn unbounded_recursion_overflows() {
+    let (output, err) = interpreter::run_content_with_max_call_depth(
+        r#"fun recurse(n) {
+    return 1 + recurse(n + 1);
+}
+print "before";
+recurse(0);
+print "after";"#,
+        20,
+    );
+    assert_eq!("before\n", output);
+    assert!(matches!(err, Some(InterpreterError::RuntimeError(_))));
+    assert_eq!("[line 2] Error: Stack overflow.", err.unwrap().to_string());
+}
+
+#[test]
+fn recursive_methods_overflow() {
+    let (_, err) = interpreter::run_content_with_max_call_depth(
+        r#"class Node {
+    depth() {
+        return 1 + this.depth();
+    }
+}
+Node().depth();"#,
+        20,
+    );
+    assert_some!(err);
+    assert_eq!("[line 3] Error: Stack overflow.", err.unwrap().to_string());
+}
+
+#[test]
+fn recursive_operators_overflow() {
+    let (_, err) = interpreter::run_content_with_max_call_depth(
+        r#"class Loop {
+    __add__(other) { return this + other; }
+}
+print Loop() + 1;"#,
+        20,
+    );
+    assert_some!(err);
+    assert_eq!("[line 2] Error: Stack overflow.", err.unwrap().to_string());
+}
+
+#[test]
+fn depth_is_released_after_returning() {
+    let (output, err) = interpreter::run_content_with_max_call_depth(
+        r#"fun count(n) {
+    if (n == 1) return 1;
+    return 1 + count(n - 1);
+}
+for (var i in 0..3) {
+    print count(15);
+}"#,
+        15,
+    );
+    assert_none!(err);
+    assert_eq!("15\n15\n15\n", output);
+}
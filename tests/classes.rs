@@ -314,3 +314,41 @@ foo.bar();"#,
     assert_none!(err);
     assert_eq!("Foo.bar\n", output);
 }
+
+#[test]
+fn init_arity() {
+    let (output, err) = interpreter::run_content(
+        r#"class Point {
+    init(x, y) {
+        print x + y;
+    }
+}
+
+Point(1, 2);
+Point(1);"#,
+    );
+    assert_some!(err);
+    assert_eq!("3\n", output);
+    assert_eq!(
+        "[line 8] Error: Expected 2 arguments for function '<fn init>' but got 1.",
+        err.unwrap().to_string()
+    );
+}
+
+#[test]
+fn fields_and_methods_share_names() {
+    let (output, err) = interpreter::run_content(
+        r#"class Point {
+    init(x) { this.x = x; }
+    x() { return "method"; }
+    name() { return "point"; }
+}
+var p = Point(1);
+var name = "global";
+print p.x;
+print p.name();
+print name;"#,
+    );
+    assert_none!(err);
+    assert_eq!("1\npoint\nglobal\n", output);
+}
@@ -1,20 +1,107 @@
 use std::{cell::RefCell, io::BufReader, rc::Rc};
 
-use interpreter_starter_rust::{errors::InterpreterError, interpreter::Interpreter};
+use interpreter_starter_rust::{
+    errors::InterpreterError,
+    interpreter::{Interpreter, DEFAULT_MAX_CALL_DEPTH},
+    vm::Vm,
+};
 
 use super::reader::StrReader;
 
+/// Runs `content` on both the tree-walking interpreter and the VM, checking that they print the
+/// same output and fail with the same error.
 #[allow(dead_code)]
 pub fn run_content(content: &'static str) -> (String, Option<InterpreterError>) {
+    let (output, err) = run_tree_walker(content);
+    let (vm_output, vm_err) = run_vm(content);
+
+    assert_eq!(output, vm_output, "backends printed different output");
+    assert_eq!(
+        err.as_ref().map(|i| i.to_string()),
+        vm_err.as_ref().map(|i| i.to_string()),
+        "backends failed differently"
+    );
+    assert_eq!(
+        err.as_ref().map(|i| i.kind()),
+        vm_err.as_ref().map(|i| i.kind()),
+        "backends failed with different kinds of errors"
+    );
+    assert_eq!(
+        err.as_ref().and_then(|i| i.trace()),
+        vm_err.as_ref().and_then(|i| i.trace()),
+        "backends failed with different stack traces"
+    );
+
+    (output, err)
+}
+
+/// Like [`run_content`], with calls nested deeper than `max_call_depth` failing.
+#[allow(dead_code)]
+pub fn run_content_with_max_call_depth(
+    content: &'static str,
+    max_call_depth: usize,
+) -> (String, Option<InterpreterError>) {
+    let (output, err) = run_tree_walker_with_max_call_depth(content, max_call_depth);
+    let (vm_output, vm_err) = run_vm_with_max_call_depth(content, max_call_depth);
+
+    assert_eq!(output, vm_output, "backends printed different output");
+    assert_eq!(
+        err.as_ref().map(|i| i.to_string()),
+        vm_err.as_ref().map(|i| i.to_string()),
+        "backends failed differently"
+    );
+    assert_eq!(
+        err.as_ref().map(|i| i.kind()),
+        vm_err.as_ref().map(|i| i.kind()),
+        "backends failed with different kinds of errors"
+    );
+    assert_eq!(
+        err.as_ref().and_then(|i| i.trace()),
+        vm_err.as_ref().and_then(|i| i.trace()),
+        "backends failed with different stack traces"
+    );
+
+    (output, err)
+}
+
+#[allow(dead_code)]
+pub fn run_tree_walker(content: &'static str) -> (String, Option<InterpreterError>) {
+    run_tree_walker_with_max_call_depth(content, DEFAULT_MAX_CALL_DEPTH)
+}
+
+#[allow(dead_code)]
+pub fn run_tree_walker_with_max_call_depth(
+    content: &'static str,
+    max_call_depth: usize,
+) -> (String, Option<InterpreterError>) {
     let output = Rc::new(RefCell::new(Vec::new()));
     let mut interpreter =
         Interpreter::build(BufReader::new(StrReader::new(content)), output.clone()).unwrap();
+    interpreter.set_max_call_depth(max_call_depth);
 
     let res = interpreter.run();
     let output = String::from_utf8_lossy(output.borrow().as_slice()).to_string();
     (output, res.err())
 }
 
+#[allow(dead_code)]
+pub fn run_vm(content: &'static str) -> (String, Option<InterpreterError>) {
+    run_vm_with_max_call_depth(content, DEFAULT_MAX_CALL_DEPTH)
+}
+
+fn run_vm_with_max_call_depth(
+    content: &'static str,
+    max_call_depth: usize,
+) -> (String, Option<InterpreterError>) {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let mut vm = Vm::build(BufReader::new(StrReader::new(content)), output.clone()).unwrap();
+    vm.set_max_call_depth(max_call_depth);
+
+    let res = vm.run();
+    let output = String::from_utf8_lossy(output.borrow().as_slice()).to_string();
+    (output, res.err())
+}
+
 #[allow(dead_code)]
 pub fn evaluate_content(content: &'static str) -> (String, Option<InterpreterError>) {
     let output = Rc::new(RefCell::new(Vec::new()));
@@ -25,3 +112,50 @@ pub fn evaluate_content(content: &'static str) -> (String, Option<InterpreterErr
     let output = String::from_utf8_lossy(output.borrow().as_slice()).to_string();
     (output, res.err())
 }
+
+#[allow(dead_code)]
+pub fn run_content_with_interpreter(
+    content: &'static str,
+) -> (Interpreter, String, Option<InterpreterError>) {
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let mut interpreter =
+        Interpreter::build(BufReader::new(StrReader::new(content)), output.clone()).unwrap();
+
+    let res = interpreter.run();
+    let output = String::from_utf8_lossy(output.borrow().as_slice()).to_string();
+    (interpreter, output, res.err())
+}
+
+/// Runs `content` on the tree-walking interpreter once `configure` has set it up.
+#[allow(dead_code)]
+pub fn run_tree_walker_with<F>(
+    content: &'static str,
+    configure: F,
+) -> (Interpreter, String, Option<InterpreterError>)
+where
+    F: FnOnce(&mut Interpreter),
+{
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let mut interpreter =
+        Interpreter::build(BufReader::new(StrReader::new(content)), output.clone()).unwrap();
+    configure(&mut interpreter);
+
+    let res = interpreter.run();
+    let output = String::from_utf8_lossy(output.borrow().as_slice()).to_string();
+    (interpreter, output, res.err())
+}
+
+/// Runs `content` on the VM once `configure` has set it up.
+#[allow(dead_code)]
+pub fn run_vm_with<F>(content: &'static str, configure: F) -> (Vm, String, Option<InterpreterError>)
+where
+    F: FnOnce(&mut Vm),
+{
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let mut vm = Vm::build(BufReader::new(StrReader::new(content)), output.clone()).unwrap();
+    configure(&mut vm);
+
+    let res = vm.run();
+    let output = String::from_utf8_lossy(output.borrow().as_slice()).to_string();
+    (vm, output, res.err())
+}
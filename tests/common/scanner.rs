@@ -1,6 +1,9 @@
 use std::io::BufReader;
 
-use interpreter_starter_rust::scanner::{Scanner, Token};
+use interpreter_starter_rust::{
+    scanner::{Scanner, Token},
+    span::{Position, Span},
+};
 
 use super::reader::StrReader;
 
@@ -13,3 +16,12 @@ pub fn scan_content(content: &'static str) -> Vec<Token> {
         .map(|i| i.unwrap())
         .collect::<Vec<_>>()
 }
+
+/// The span from `start` to `end`, both given as (line, column, offset).
+#[allow(dead_code)]
+pub fn span(start: (usize, usize, usize), end: (usize, usize, usize)) -> Span {
+    Span::new(
+        Position::new(start.0, start.1, start.2),
+        Position::new(end.0, end.1, end.2),
+    )
+}
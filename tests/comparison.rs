@@ -0,0 +1,119 @@
+use crate::common::interpreter;
+
+mod common;
+
+#[test]
+fn numbers() {
+    let (output, err) = interpreter::run_content(
+        r#"print 1 < 2;
+print 2 <= 2;
+print 1 > 2;
+print 1 >= 2;"#,
+    );
+    assert_none!(err);
+    assert_eq!("true\ntrue\nfalse\nfalse\n", output);
+}
+
+#[test]
+fn strings() {
+    let (output, err) = interpreter::run_content(
+        r#"print "apple" < "banana";
+print "apple" <= "apple";
+print "apple" > "apples";
+print "b" >= "abc";
+print "" < "a";"#,
+    );
+    assert_none!(err);
+    assert_eq!("true\ntrue\nfalse\ntrue\ntrue\n", output);
+}
+
+#[test]
+fn strings_are_case_sensitive() {
+    let (output, err) = interpreter::run_content(
+        r#"print "Zebra" < "apple";
+print "a" < "B";"#,
+    );
+    assert_none!(err);
+    assert_eq!("true\nfalse\n", output);
+}
+
+#[test]
+fn unicode_strings() {
+    let (output, err) = interpreter::run_content(
+        r#"print "z" < "é";
+print "é" < "ü";
+print "日本" > "中国";
+print "😀" > "日";"#,
+    );
+    assert_none!(err);
+    assert_eq!("true\ntrue\ntrue\ntrue\n", output);
+}
+
+#[test]
+fn number_and_string() {
+    let (_, err) = interpreter::run_content(
+        r#"var a = 1;
+print a < "2";"#,
+    );
+    assert_some!(err);
+    assert_eq!(
+        "[line 2] Error: Operator '<' cannot compare number and string.",
+        err.unwrap().to_string()
+    );
+}
+
+#[test]
+fn operator_line() {
+    let (_, err) = interpreter::run_content(
+        r#"print "a"
+  >=
+  nil;"#,
+    );
+    assert_some!(err);
+    assert_eq!(
+        "[line 2] Error: Operator '>=' cannot compare string and nil.",
+        err.unwrap().to_string()
+    );
+}
+
+#[test]
+fn other_types() {
+    let cases = [
+        (
+            "true > false",
+            "Operator '>' cannot compare boolean and boolean",
+        ),
+        ("nil <= nil", "Operator '<=' cannot compare nil and nil"),
+        (
+            "clock < 1",
+            "Operator '<' cannot compare function and number",
+        ),
+        (
+            "(0..1) < (0..2)",
+            "Operator '<' cannot compare range and range",
+        ),
+    ];
+
+    for (expression, message) in cases {
+        let (_, err) =
+            interpreter::run_content(Box::leak(format!("print {};", expression).into_boxed_str()));
+        assert_some!(err);
+        assert_eq!(
+            format!("[line 1] Error: {}.", message),
+            err.unwrap().to_string()
+        );
+    }
+}
+
+#[test]
+fn instances_and_classes() {
+    let (_, err) = interpreter::run_content(
+        r#"class Foo {}
+print Foo() > Foo;"#,
+    );
+    assert_some!(err);
+    assert_eq!(
+        "[line 2] Error: Operator '>' cannot compare instance and class.",
+        err.unwrap().to_string()
+    );
+}
@@ -0,0 +1,86 @@
+use crate::common::interpreter;
+
+mod common;
+
+// Tests run on threads with a small native stack, which the tree-walking interpreter used to
+// overflow after a few hundred calls.
+
+#[test]
+fn recursion_is_not_bounded_by_the_native_stack() {
+    let (output, err) = interpreter::run_content_with_max_call_depth(
+        r#"fun count(n) {
+    if (n == 0) return 0;
+    return 1 + count(n - 1);
+}
+print count(100000);"#,
+        200_000,
+    );
+    assert_none!(err);
+    assert_eq!("100000\n", output);
+}
+
+#[test]
+fn deep_mutual_recursion() {
+    let (output, err) = interpreter::run_content_with_max_call_depth(
+        r#"fun isEven(n) {
+    if (n == 0) return true;
+    return isOdd(n - 1);
+}
+fun isOdd(n) {
+    if (n == 0) return false;
+    return isEven(n - 1);
+}
+print isEven(50001);"#,
+        60_000,
+    );
+    assert_none!(err);
+    assert_eq!("false\n", output);
+}
+
+// The VM still calls special methods recursively, so this one only runs on the tree-walking
+// interpreter.
+#[test]
+fn deep_recursion_through_methods_and_operators() {
+    let (output, err) = interpreter::run_tree_walker_with_max_call_depth(
+        r#"class Counter {
+    init(n) { this.n = n; }
+    __add__(other) {
+        if (this.n == 0) return other;
+        return Counter(this.n - 1) + (other + 1);
+    }
+    toString() { return "counter"; }
+}
+print Counter(20000) + 0;
+print Counter(5);"#,
+        50_000,
+    );
+    assert_none!(err);
+    assert_eq!("20000\ncounter\n", output);
+}
+
+#[test]
+fn deep_recursion_inside_loops_and_blocks() {
+    let (output, err) = interpreter::run_content_with_max_call_depth(
+        r#"fun sum(n) {
+    var total = 0;
+    for (var i in 0..2) {
+        {
+            if (n > 0) total = total + sum(n - 1);
+        }
+    }
+    while (total < 0) {}
+    return total + 1;
+}
+print sum(12);
+fun down(n) {
+    for (var c in "ab") {
+        if (n == 0) return c;
+        return down(n - 1);
+    }
+}
+print down(30000);"#,
+        40_000,
+    );
+    assert_none!(err);
+    assert_eq!("8191\na\n", output);
+}
@@ -0,0 +1,213 @@
+mod common;
+
+use interpreter_starter_rust::{
+    diagnostic::{ColorChoice, ErrorFormat, Renderer},
+    error_kind::ErrorKind,
+    errors::ErrorMessage,
+    span::{Position, Span},
+};
+
+use crate::common::interpreter;
+
+fn render(content: &'static str) -> String {
+    let (_, err) = interpreter::run_content(content);
+    assert_some!(err);
+    Renderer::new("test.lox", content, false).render(&err.unwrap())
+}
+
+fn render_json(content: &'static str) -> String {
+    let (_, err) = interpreter::run_content(content);
+    assert_some!(err);
+    Renderer::new("test.lox", content, false)
+        .with_format(ErrorFormat::Json)
+        .render(&err.unwrap())
+}
+
+#[test]
+fn runtime_error_underlines_operator() {
+    assert_eq!(
+        "error[L0403]: Operands must be two numbers or two strings
+ --> test.lox:2:11
+  |
+2 | print \"a\" - 1;
+  |           ^
+",
+        render("print 1;\nprint \"a\" - 1;")
+    );
+}
+
+#[test]
+fn underline_spans_whole_token() {
+    assert_eq!(
+        "error[L0401]: Undefined variable in scope: 'bee'
+ --> test.lox:1:11
+  |
+1 | print 1 + bee;
+  |           ^^^
+",
+        render("print 1 + bee;")
+    );
+}
+
+#[test]
+fn redeclared_variable_points_at_declaration() {
+    assert_eq!(
+        "error[L0301]: Already a variable with name 'a' in this scope
+ --> test.lox:3:7
+  |
+2 |   var a = 42;
+  |       - variable declared here
+3 |   var a = 84;
+  |       ^
+  |
+  = help: remove 'var' to assign to the existing variable
+",
+        render("{\n  var a = 42;\n  var a = 84;\n}")
+    );
+}
+
+#[test]
+fn distant_lines_are_elided() {
+    assert_eq!(
+        "error[L0301]: Already a variable with name 'a' in this scope
+  --> test.lox:10:7
+   |
+ 1 | { var a = 1;
+   |       - variable declared here
+...
+10 |   var a = 2;
+   |       ^
+   |
+   = help: remove 'var' to assign to the existing variable
+",
+        render("{ var a = 1;\n\n\n\n\n\n\n\n\n  var a = 2;\n}")
+    );
+}
+
+#[test]
+fn every_parsing_error_is_rendered() {
+    assert_eq!(
+        "error[L0201]: Unexpected token: SEMICOLON
+ --> test.lox:1:9
+  |
+1 | var x = ;
+  |         ^
+
+error[L0101]: Unexpected character: @
+ --> test.lox:2:1
+  |
+2 | @
+  | ^
+",
+        render("var x = ;\n@")
+    );
+}
+
+#[test]
+fn underline_keeps_tabs_aligned() {
+    assert_eq!(
+        "error[L0401]: Undefined variable in scope: 'b'
+ --> test.lox:1:8
+  |
+1 | \tprint b;
+  | \t      ^
+",
+        render("\tprint b;")
+    );
+}
+
+#[test]
+fn error_without_span_has_no_excerpt() {
+    let message = ErrorMessage::new(ErrorKind::OutOfFuel, "Out of fuel", None)
+        .with_note("ran 10 steps", None);
+    assert_eq!(
+        "error[L0501]: Out of fuel\n = note: ran 10 steps\n",
+        Renderer::new("test.lox", "", false).render_message(&message)
+    );
+}
+
+#[test]
+fn colors_wrap_styled_parts() {
+    let span = Span::new(Position::new(1, 1, 0), Position::new(1, 2, 1));
+    let message = ErrorMessage::new(ErrorKind::Internal, "Oops", Some(span));
+    let rendered = Renderer::new("test.lox", "x", true).render_message(&message);
+    assert!(rendered.starts_with("\x1b[1;31merror[L0000]\x1b[0m\x1b[1m: Oops\x1b[0m\n"));
+    assert!(rendered.contains("\x1b[1;31m^\x1b[0m"));
+}
+
+#[test]
+fn color_choice_parses_flag_values() {
+    assert_eq!(Ok(ColorChoice::Never), "never".parse());
+    assert_eq!(Ok(ColorChoice::Always), "always".parse());
+    assert_eq!(Ok(ColorChoice::Auto), "auto".parse());
+    assert_eq!(Err(()), "sometimes".parse::<ColorChoice>());
+    assert!(!ColorChoice::Never.enabled());
+    assert!(ColorChoice::Always.enabled());
+}
+
+#[test]
+fn json_has_one_line_per_error_with_its_phase() {
+    assert_eq!(
+        concat!(
+            r#"{"severity":"error","phase":"parse","code":"L0201","message":"Unexpected token: SEMICOLON","#,
+            r#""span":{"file":"test.lox","start":{"line":1,"column":9,"offset":8},"#,
+            r#""end":{"line":1,"column":10,"offset":9}},"notes":[],"help":null}"#,
+            "\n",
+            r#"{"severity":"error","phase":"scan","code":"L0101","message":"Unexpected character: @","#,
+            r#""span":{"file":"test.lox","start":{"line":2,"column":1,"offset":10},"#,
+            r#""end":{"line":2,"column":2,"offset":11}},"notes":[],"help":null}"#,
+            "\n",
+        ),
+        render_json("var x = ;\n@")
+    );
+}
+
+#[test]
+fn json_has_notes_and_help() {
+    assert_eq!(
+        concat!(
+            r#"{"severity":"error","phase":"resolve","code":"L0301","#,
+            r#""message":"Already a variable with name 'a' in this scope","#,
+            r#""span":{"file":"test.lox","start":{"line":2,"column":5,"offset":17},"#,
+            r#""end":{"line":2,"column":6,"offset":18}},"#,
+            r#""notes":[{"message":"variable declared here","#,
+            r#""span":{"file":"test.lox","start":{"line":1,"column":7,"offset":6},"#,
+            r#""end":{"line":1,"column":8,"offset":7}}}],"#,
+            r#""help":"remove 'var' to assign to the existing variable"}"#,
+            "\n",
+        ),
+        render_json("{ var a = 1;\nvar a = 2; }")
+    );
+}
+
+#[test]
+fn json_reports_runtime_errors() {
+    let rendered = render_json("print 1;\nprint \"a\" - 1;");
+    assert!(rendered.starts_with(r#"{"severity":"error","phase":"runtime","#));
+}
+
+#[test]
+fn json_escapes_strings() {
+    let message = ErrorMessage::new(
+        ErrorKind::UnexpectedCharacter,
+        "a \"quoted\"\tback\\slash\n\u{1}",
+        None,
+    );
+    assert_eq!(
+        concat!(
+            r#"{"severity":"error","phase":"scan","code":"L0101","#,
+            r#""message":"a \"quoted\"\tback\\slash\n\u0001","span":null,"notes":[],"help":null}"#,
+            "\n",
+        ),
+        Renderer::new("test.lox", "", false)
+            .with_format(ErrorFormat::Json)
+            .render_message(&message)
+    );
+}
+
+#[test]
+fn error_format_parses_flag_values() {
+    assert_eq!(Ok(ErrorFormat::Human), "human".parse());
+    assert_eq!(Ok(ErrorFormat::Json), "json".parse());
+    assert_eq!(Err(()), "xml".parse::<ErrorFormat>());
+}
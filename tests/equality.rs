@@ -0,0 +1,165 @@
+use crate::common::interpreter;
+
+mod common;
+
+/// One value of each type, every one of them distinct from the others.
+const VALUES: &[&str] = &[
+    "nil", "true", "1", "\"1\"", "0..1", "clock", "f", "Foo", "Foo()",
+];
+
+#[test]
+fn each_pair_of_types() {
+    let mut script = "fun f() {}\nclass Foo {}\n".to_string();
+    for (i, value) in VALUES.iter().enumerate() {
+        script.push_str(&format!("var v{} = {};\n", i, value));
+    }
+
+    let mut expected = String::new();
+    for i in 0..VALUES.len() {
+        for j in 0..VALUES.len() {
+            script.push_str(&format!(
+                "print v{} == v{};\nprint v{} != v{};\n",
+                i, j, i, j
+            ));
+            expected.push_str(&format!("{}\n{}\n", i == j, i != j));
+        }
+    }
+
+    let (output, err) = interpreter::run_content(Box::leak(script.into_boxed_str()));
+    assert_none!(err);
+    assert_eq!(expected, output);
+}
+
+#[test]
+fn nil() {
+    let (output, err) = interpreter::run_content(
+        r#"print nil == nil;
+print nil != nil;
+print nil == false;"#,
+    );
+    assert_none!(err);
+    assert_eq!("true\nfalse\nfalse\n", output);
+}
+
+#[test]
+fn booleans() {
+    let (output, err) = interpreter::run_content(
+        r#"print true == true;
+print true == false;
+print true != false;"#,
+    );
+    assert_none!(err);
+    assert_eq!("true\nfalse\ntrue\n", output);
+}
+
+#[test]
+fn numbers() {
+    let (output, err) = interpreter::run_content(
+        r#"print 1 == 1.0;
+print 1 == 2;
+print 1 != 2;
+print 0 / 0 == 0 / 0;
+print 0 / 0 != 0 / 0;"#,
+    );
+    assert_none!(err);
+    assert_eq!("true\nfalse\ntrue\nfalse\ntrue\n", output);
+}
+
+#[test]
+fn strings() {
+    let (output, err) = interpreter::run_content(
+        r#"print "ab" == "a" + "b";
+print "ab" != "a" + "b";
+print "a" == "b";"#,
+    );
+    assert_none!(err);
+    assert_eq!("true\nfalse\nfalse\n", output);
+}
+
+#[test]
+fn mixed_primitives() {
+    let (output, err) = interpreter::run_content(
+        r#"print 1 == "1";
+print 1 != "1";
+print 0 == false;
+print "" != nil;"#,
+    );
+    assert_none!(err);
+    assert_eq!("false\ntrue\nfalse\ntrue\n", output);
+}
+
+#[test]
+fn ranges() {
+    let (output, err) = interpreter::run_content(
+        r#"print 0..2 == 0..2;
+print 0..2 == 0..=2;
+print 0..2 != 0..3;"#,
+    );
+    assert_none!(err);
+    assert_eq!("true\nfalse\ntrue\n", output);
+}
+
+#[test]
+fn functions() {
+    let (output, err) = interpreter::run_content(
+        r#"fun make() {
+    fun f() {}
+    return f;
+}
+
+var f = make();
+var g = f;
+print f == g;
+print f != g;
+print make() == make();
+print clock == clock;"#,
+    );
+    assert_none!(err);
+    assert_eq!("true\nfalse\nfalse\ntrue\n", output);
+}
+
+#[test]
+fn classes() {
+    let (output, err) = interpreter::run_content(
+        r#"class Foo {}
+class Bar {}
+
+var Baz = Foo;
+print Foo == Baz;
+print Foo == Bar;
+print Foo != Bar;"#,
+    );
+    assert_none!(err);
+    assert_eq!("true\nfalse\ntrue\n", output);
+}
+
+#[test]
+fn instances() {
+    let (output, err) = interpreter::run_content(
+        r#"class Foo {}
+
+var foo = Foo();
+var same = foo;
+print foo == same;
+print foo != same;
+print Foo() == Foo();
+print Foo() != Foo();"#,
+    );
+    assert_none!(err);
+    assert_eq!("true\nfalse\nfalse\ntrue\n", output);
+}
+
+#[test]
+fn interned_strings() {
+    let (output, err) = interpreter::run_content(
+        r#"var a = "foo";
+var b = "foo";
+var empty = "";
+print a == b;
+print a + empty == b;
+print empty + "fo" + "o" == a;
+print a != "bar";"#,
+    );
+    assert_none!(err);
+    assert_eq!("true\ntrue\ntrue\ntrue\n", output);
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use interpreter_starter_rust::{error_kind::ErrorKind, errors::Phase};
+
+use crate::common::interpreter;
+
+fn kind_of(content: &'static str) -> ErrorKind {
+    let (_, err) = interpreter::run_content(content);
+    assert_some!(err);
+    err.unwrap().kind()
+}
+
+#[test]
+fn scanning_errors_have_kinds() {
+    assert_eq!(ErrorKind::UnexpectedCharacter, kind_of("var a = 1 @;"));
+    assert_eq!(ErrorKind::UnterminatedString, kind_of("print \"abc;"));
+}
+
+#[test]
+fn parsing_errors_have_kinds() {
+    assert_eq!(ErrorKind::UnexpectedToken, kind_of("var a = * 2;"));
+    assert_eq!(ErrorKind::MissingSyntax, kind_of("print 1"));
+    assert_eq!(
+        ErrorKind::InvalidAssignmentTarget,
+        kind_of("var a; var b; a + b = 1;")
+    );
+}
+
+#[test]
+fn resolving_errors_have_kinds() {
+    assert_eq!(ErrorKind::AlreadyDeclared, kind_of("{ var a; var a; }"));
+    assert_eq!(
+        ErrorKind::ReadInOwnInitializer,
+        kind_of("var a; { var a = a; }")
+    );
+    assert_eq!(ErrorKind::ReturnFromTopLevel, kind_of("return 1;"));
+    assert_eq!(
+        ErrorKind::ReturnValueFromInitializer,
+        kind_of("class A { init() { return 1; } }")
+    );
+    assert_eq!(ErrorKind::ThisOutsideClass, kind_of("print this;"));
+    assert_eq!(ErrorKind::SuperOutsideClass, kind_of("super.f();"));
+    assert_eq!(
+        ErrorKind::SuperWithoutSuperclass,
+        kind_of("class A { f() { super.f(); } }")
+    );
+    assert_eq!(ErrorKind::InheritFromSelf, kind_of("class A < A {}"));
+}
+
+#[test]
+fn runtime_errors_have_kinds() {
+    assert_eq!(ErrorKind::UndefinedVariable, kind_of("print a;"));
+    assert_eq!(ErrorKind::UndefinedVariable, kind_of("a = 1;"));
+    assert_eq!(
+        ErrorKind::UndefinedSuperMethod,
+        kind_of("class A {} class B < A { f() { super.f(); } } B().f();")
+    );
+    assert_eq!(ErrorKind::InvalidOperand, kind_of("print -\"a\";"));
+    assert_eq!(ErrorKind::InvalidOperand, kind_of("print \"a\" * 2;"));
+    assert_eq!(ErrorKind::InvalidOperand, kind_of("print 1 < \"a\";"));
+    assert_eq!(ErrorKind::NotAnInstance, kind_of("print 1.a;"));
+    assert_eq!(ErrorKind::NotCallable, kind_of("\"a\"();"));
+    assert_eq!(ErrorKind::ArityMismatch, kind_of("fun f(a) {} f();"));
+    assert_eq!(ErrorKind::NotIndexable, kind_of("print 1[0];"));
+    assert_eq!(ErrorKind::InvalidIndex, kind_of("print \"abc\"[0.5];"));
+    assert_eq!(ErrorKind::IndexOutOfBounds, kind_of("print \"abc\"[3];"));
+    assert_eq!(ErrorKind::NotIterable, kind_of("for (var i in 1) print i;"));
+    assert_eq!(
+        ErrorKind::SuperclassNotAClass,
+        kind_of("var A = 1; class B < A {}")
+    );
+    assert_eq!(
+        ErrorKind::StackOverflow,
+        kind_of("fun f() { return 1 + f(); } f();")
+    );
+}
+
+#[test]
+fn exit_codes_follow_phases() {
+    assert_eq!(65, ErrorKind::UnexpectedCharacter.exit_code());
+    assert_eq!(65, ErrorKind::MissingSyntax.exit_code());
+    assert_eq!(65, ErrorKind::AlreadyDeclared.exit_code());
+    assert_eq!(70, ErrorKind::UndefinedVariable.exit_code());
+    assert_eq!(70, ErrorKind::OutOfFuel.exit_code());
+
+    let (_, err) = interpreter::run_content("{ var a; var a; }");
+    assert_eq!(65, err.unwrap().exit_code());
+}
+
+#[test]
+fn codes_are_unique_and_found_back() {
+    for kind in ErrorKind::ALL {
+        assert_eq!(Some(*kind), ErrorKind::from_code(kind.code()));
+        assert_eq!(
+            1,
+            ErrorKind::ALL
+                .iter()
+                .filter(|i| i.code() == kind.code())
+                .count()
+        );
+    }
+    assert_eq!(
+        Some(ErrorKind::InvalidAssignmentTarget),
+        ErrorKind::from_code("l0203")
+    );
+    assert_none!(ErrorKind::from_code("L9999"));
+}
+
+#[test]
+fn codes_are_grouped_by_phase() {
+    for kind in ErrorKind::ALL {
+        let group = match kind.phase() {
+            Phase::Scan => "L01",
+            Phase::Parse => "L02",
+            Phase::Resolve => "L03",
+            Phase::Runtime => match kind {
+                ErrorKind::Internal => "L00",
+                ErrorKind::OutOfFuel | ErrorKind::OutOfMemory | ErrorKind::Interrupted => "L05",
+                _ => "L04",
+            },
+        };
+        assert!(kind.code().starts_with(group), "{:?}", kind);
+    }
+}
+
+#[test]
+fn every_kind_is_explained() {
+    for kind in ErrorKind::ALL {
+        assert!(!kind.explanation().is_empty(), "{:?}", kind);
+    }
+}
@@ -0,0 +1,94 @@
+use interpreter_starter_rust::errors::InterpreterError;
+
+use crate::common::interpreter;
+
+mod common;
+
+#[test]
+fn infinite_loops_run_out_of_fuel() {
+    let (interpreter, output, err) = interpreter::run_tree_walker_with(
+        r#"print "start";
+while (true) {}"#,
+        |i| i.set_fuel(1000),
+    );
+    assert_eq!("start\n", output);
+    assert!(matches!(err, Some(InterpreterError::OutOfFuel(_))));
+    assert_eq!(
+        "Error: Out of fuel after 1000 steps.",
+        err.unwrap().to_string()
+    );
+    assert_eq!(1000, interpreter.fuel_used());
+}
+
+#[test]
+fn infinite_recursion_runs_out_of_fuel() {
+    let (_, _, err) = interpreter::run_tree_walker_with(
+        r#"fun f() { return f(); }
+f();"#,
+        |i| i.set_fuel(500),
+    );
+    assert!(matches!(err, Some(InterpreterError::OutOfFuel(_))));
+}
+
+#[test]
+fn scripts_within_budget_run_to_completion() {
+    let (interpreter, output, err) = interpreter::run_tree_walker_with(
+        r#"var a = 1;
+print a + 2;"#,
+        |i| i.set_fuel(100),
+    );
+    assert_none!(err);
+    assert_eq!("3\n", output);
+    // The declaration, its initializer, the print statement and its three expressions.
+    assert_eq!(6, interpreter.fuel_used());
+}
+
+#[test]
+fn fuel_is_counted_without_a_budget() {
+    let (interpreter, output, err) = interpreter::run_content_with_interpreter(
+        r#"for (var i in 0..10) {
+    print i;
+}"#,
+    );
+    assert_none!(err);
+    assert_eq!(10, output.lines().count());
+    assert!(interpreter.fuel_used() > 20);
+}
+
+#[test]
+fn infinite_loops_run_out_of_fuel_on_the_vm() {
+    let (vm, output, err) = interpreter::run_vm_with(
+        r#"print "start";
+while (true) {}"#,
+        |vm| vm.set_fuel(1000),
+    );
+    assert_eq!("start\n", output);
+    assert!(matches!(err, Some(InterpreterError::OutOfFuel(_))));
+    assert_eq!(
+        "Error: Out of fuel after 1000 steps.",
+        err.unwrap().to_string()
+    );
+    assert_eq!(1000, vm.fuel_used());
+}
+
+#[test]
+fn infinite_recursion_runs_out_of_fuel_on_the_vm() {
+    let (_, _, err) = interpreter::run_vm_with(
+        r#"fun f() { return f(); }
+f();"#,
+        |vm| vm.set_fuel(500),
+    );
+    assert!(matches!(err, Some(InterpreterError::OutOfFuel(_))));
+}
+
+#[test]
+fn scripts_within_budget_run_to_completion_on_the_vm() {
+    let (vm, output, err) = interpreter::run_vm_with(
+        r#"var a = 1;
+print a + 2;"#,
+        |vm| vm.set_fuel(100),
+    );
+    assert_none!(err);
+    assert_eq!("3\n", output);
+    assert!(vm.fuel_used() > 0 && vm.fuel_used() < 100);
+}
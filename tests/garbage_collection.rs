@@ -0,0 +1,102 @@
+use crate::common::interpreter;
+
+mod common;
+
+#[test]
+fn self_referencing_instances_are_collected() {
+    let (mut interpreter, output, err) = interpreter::run_content_with_interpreter(
+        r#"class Node {}
+
+fun cycle() {
+    var a = Node();
+    var b = Node();
+    a.other = b;
+    b.other = a;
+    a.self = a;
+}
+
+for (var i in 0..100) {
+    cycle();
+}
+print "done";"#,
+    );
+    assert_none!(err);
+    assert_eq!("done\n", output);
+
+    interpreter.collect_garbage();
+    let stats = interpreter.heap_stats();
+    assert!(stats.collected >= 200, "{:?}", stats);
+    assert!(stats.live < 20, "{:?}", stats);
+}
+
+#[test]
+fn recursive_closures_are_collected() {
+    let (mut interpreter, output, err) = interpreter::run_content_with_interpreter(
+        r#"fun make() {
+    fun countdown(n) {
+        if (n > 0) countdown(n - 1);
+    }
+    countdown(3);
+}
+
+for (var i in 0..100) {
+    make();
+}
+print "done";"#,
+    );
+    assert_none!(err);
+    assert_eq!("done\n", output);
+
+    interpreter.collect_garbage();
+    let stats = interpreter.heap_stats();
+    assert!(stats.collected >= 100, "{:?}", stats);
+    assert!(stats.live < 20, "{:?}", stats);
+}
+
+#[test]
+fn reachable_objects_survive() {
+    let (mut interpreter, output, err) = interpreter::run_content_with_interpreter(
+        r#"class Node {
+    init(name) { this.name = name; }
+}
+
+var a = Node("a");
+var b = Node("b");
+a.other = b;
+b.other = a;"#,
+    );
+    assert_none!(err);
+    assert_eq!("", output);
+
+    interpreter.collect_garbage();
+    let live = interpreter.heap_stats().live;
+    interpreter.collect_garbage();
+    assert_eq!(live, interpreter.heap_stats().live);
+    assert!(live >= 3);
+}
+
+#[test]
+fn collection_during_execution_keeps_program_correct() {
+    let (interpreter, output, err) = interpreter::run_content_with_interpreter(
+        r#"class Node {
+    init(value) { this.value = value; }
+}
+
+var kept = Node(0);
+kept.self = kept;
+var total = 0;
+for (var i in 0..5000) {
+    var node = Node(i);
+    node.self = node;
+    total = total + node.value;
+}
+print total;
+print kept.self.self.value;"#,
+    );
+    assert_none!(err);
+    assert_eq!("12497500\n0\n", output);
+
+    let stats = interpreter.heap_stats();
+    assert!(stats.collections > 0, "{:?}", stats);
+    assert!(stats.collected > 0, "{:?}", stats);
+}
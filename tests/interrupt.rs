@@ -0,0 +1,160 @@
+use std::{thread, time::Duration};
+
+use interpreter_starter_rust::{errors::InterpreterError, interrupt::InterruptHandle};
+
+use crate::common::interpreter;
+
+mod common;
+
+#[test]
+fn interrupting_from_another_thread_stops_a_loop() {
+    let (_, output, err) = interpreter::run_tree_walker_with(
+        r#"print "start";
+while (true) {}"#,
+        |interpreter| {
+            let handle = interpreter.interrupt_handle();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                handle.interrupt();
+            });
+        },
+    );
+    assert_eq!("start\n", output);
+    assert!(matches!(err, Some(InterpreterError::Interrupted(_))));
+    assert_eq!("Error: Interrupted.", err.unwrap().to_string());
+}
+
+#[test]
+fn interrupting_stops_recursion() {
+    let (interpreter, _, err) = interpreter::run_tree_walker_with(
+        r#"fun f() { return f(); }
+f();"#,
+        |interpreter| {
+            let handle = interpreter.interrupt_handle();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                handle.interrupt();
+            });
+        },
+    );
+    assert!(matches!(err, Some(InterpreterError::Interrupted(_))));
+    assert!(!interpreter.interrupt_handle().is_interrupted());
+}
+
+#[test]
+fn interrupting_before_running_stops_the_run() {
+    let (interpreter, output, err) = interpreter::run_tree_walker_with(
+        r#"print "start";
+while (true) {}"#,
+        |interpreter| interpreter.interrupt_handle().interrupt(),
+    );
+    assert_eq!("start\n", output);
+    assert!(matches!(err, Some(InterpreterError::Interrupted(_))));
+    assert!(!interpreter.interrupt_handle().is_interrupted());
+}
+
+#[test]
+fn interrupts_do_not_outlive_their_run() {
+    let handle = InterruptHandle::new();
+    let (_, _, err) = interpreter::run_tree_walker_with(r#"while (true) {}"#, |interpreter| {
+        interpreter.set_interrupt_handle(handle.clone());
+        let handle = handle.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            handle.interrupt();
+        });
+    });
+    assert!(matches!(err, Some(InterpreterError::Interrupted(_))));
+
+    // Without loops or calls, the run ends before checking for the interrupt.
+    let (_, output, err) = interpreter::run_tree_walker_with(r#"print "done";"#, |interpreter| {
+        interpreter.set_interrupt_handle(handle.clone());
+        handle.interrupt();
+    });
+    assert_none!(err);
+    assert_eq!("done\n", output);
+    assert!(!handle.is_interrupted());
+
+    let (_, output, err) =
+        interpreter::run_tree_walker_with(r#"for (var i in 0..3) print i;"#, |interpreter| {
+            interpreter.set_interrupt_handle(handle.clone())
+        });
+    assert_none!(err);
+    assert_eq!("0\n1\n2\n", output);
+}
+
+#[test]
+fn runs_time_out() {
+    let (_, _, err) =
+        interpreter::run_tree_walker_with(r#"for (var i in 0..1000000000) {}"#, |interpreter| {
+            interpreter.set_timeout(Duration::from_millis(20))
+        });
+    assert!(matches!(err, Some(InterpreterError::Interrupted(_))));
+    assert_eq!("Error: Timed out after 20ms.", err.unwrap().to_string());
+}
+
+#[test]
+fn runs_within_the_timeout_complete() {
+    let (_, output, err) =
+        interpreter::run_tree_walker_with(r#"for (var i in 0..3) print i;"#, |interpreter| {
+            interpreter.set_timeout(Duration::from_secs(10))
+        });
+    assert_none!(err);
+    assert_eq!("0\n1\n2\n", output);
+}
+
+#[test]
+fn interrupting_the_vm_stops_a_loop() {
+    let (_, output, err) = interpreter::run_vm_with(
+        r#"print "start";
+while (true) {}"#,
+        |vm| {
+            let handle = vm.interrupt_handle();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                handle.interrupt();
+            });
+        },
+    );
+    assert_eq!("start\n", output);
+    assert!(matches!(err, Some(InterpreterError::Interrupted(_))));
+    assert_eq!("Error: Interrupted.", err.unwrap().to_string());
+}
+
+#[test]
+fn interrupting_the_vm_stops_recursion() {
+    let (vm, _, err) = interpreter::run_vm_with(
+        r#"fun f() { return f(); }
+f();"#,
+        |vm| {
+            let handle = vm.interrupt_handle();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                handle.interrupt();
+            });
+        },
+    );
+    assert!(matches!(err, Some(InterpreterError::Interrupted(_))));
+    assert!(!vm.interrupt_handle().is_interrupted());
+}
+
+#[test]
+fn interrupting_the_vm_before_running_stops_the_run() {
+    let (vm, output, err) = interpreter::run_vm_with(
+        r#"print "start";
+while (true) {}"#,
+        |vm| vm.interrupt_handle().interrupt(),
+    );
+    assert_eq!("start\n", output);
+    assert!(matches!(err, Some(InterpreterError::Interrupted(_))));
+    assert!(!vm.interrupt_handle().is_interrupted());
+}
+
+#[test]
+fn vm_runs_time_out() {
+    let (_, _, err) = interpreter::run_vm_with(r#"for (var i in 0..1000000000) {}"#, |vm| {
+        vm.set_timeout(Duration::from_millis(20))
+    });
+    assert!(matches!(err, Some(InterpreterError::Interrupted(_))));
+    assert_eq!("Error: Timed out after 20ms.", err.unwrap().to_string());
+}
@@ -4,7 +4,7 @@ use std::rc::Rc;
 
 use interpreter_starter_rust::scanner::{Literal, Token, TokenType};
 
-use crate::common::{interpreter, parser, scanner};
+use crate::common::{interpreter, parser, scanner, scanner::span};
 
 #[test]
 fn scan_string_literal() {
@@ -12,14 +12,14 @@ fn scan_string_literal() {
     use TokenType::*;
     assert_eq!(
         vec![
-            Token::new(Identifier, "foo".to_string(), 1),
+            Token::new(Identifier, "foo".to_string(), span((1, 1, 0), (1, 4, 3))),
             Token::with_literal(
                 String,
                 "\"hello\"".to_string(),
                 Literal::String(Rc::new("hello".to_string())),
-                1
+                span((1, 5, 4), (1, 12, 11))
             ),
-            Token::new(EOF, "", 2)
+            Token::new(EOF, "", span((2, 1, 12), (2, 1, 12)))
         ],
         tokens
     );
@@ -31,9 +31,14 @@ fn scan_digit_literal() {
     use TokenType::*;
     assert_eq!(
         vec![
-            Token::new(Identifier, "bar".to_string(), 1),
-            Token::with_literal(Number, "123.456".to_string(), Literal::Digit(123.456f64), 1),
-            Token::new(EOF, "", 2)
+            Token::new(Identifier, "bar".to_string(), span((1, 1, 0), (1, 4, 3))),
+            Token::with_literal(
+                Number,
+                "123.456".to_string(),
+                Literal::Digit(123.456f64),
+                span((1, 5, 4), (1, 12, 11))
+            ),
+            Token::new(EOF, "", span((2, 1, 12), (2, 1, 12)))
         ],
         tokens
     );
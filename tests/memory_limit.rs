@@ -0,0 +1,66 @@
+use interpreter_starter_rust::errors::InterpreterError;
+
+use crate::common::interpreter;
+
+mod common;
+
+#[test]
+fn growing_strings_run_out_of_memory() {
+    let (_, output, err) = interpreter::run_tree_walker_with(
+        r#"var s = "x";
+print "start";
+while (true) s = s + s;"#,
+        |interpreter| interpreter.set_max_memory(1 << 20),
+    );
+    assert_eq!("start\n", output);
+    assert!(matches!(err, Some(InterpreterError::OutOfMemory(_))));
+    assert_eq!(
+        "Error: Out of memory: the limit is 1048576 bytes.",
+        err.unwrap().to_string()
+    );
+}
+
+#[test]
+fn endless_instances_run_out_of_memory() {
+    let (_, _, err) = interpreter::run_tree_walker_with(
+        r#"class Node {
+    init(next) { this.next = next; }
+}
+var list = nil;
+while (true) list = Node(list);"#,
+        |interpreter| interpreter.set_max_memory(1 << 20),
+    );
+    assert!(matches!(err, Some(InterpreterError::OutOfMemory(_))));
+}
+
+#[test]
+fn deep_recursion_runs_out_of_memory() {
+    let (_, _, err) = interpreter::run_tree_walker_with(
+        r#"fun f(n) { return 1 + f(n + 1); }
+f(0);"#,
+        |interpreter| {
+            interpreter.set_max_call_depth(usize::MAX);
+            interpreter.set_max_memory(1 << 20);
+        },
+    );
+    assert!(matches!(err, Some(InterpreterError::OutOfMemory(_))));
+}
+
+#[test]
+fn freed_memory_is_not_counted() {
+    let (_, output, err) = interpreter::run_tree_walker_with(
+        r#"class Pair {
+    init(a, b) { this.a = a; this.b = b; }
+}
+var kept;
+for (var i in 0..20000) {
+    var s = "item " + "number";
+    kept = Pair(s, i);
+    kept.self = kept;
+}
+print kept.b;"#,
+        |interpreter| interpreter.set_max_memory(256 * 1024),
+    );
+    assert_none!(err);
+    assert_eq!("19999\n", output);
+}
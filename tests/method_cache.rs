@@ -0,0 +1,106 @@
+use crate::common::interpreter;
+
+mod common;
+
+#[test]
+fn repeated_calls_hit_the_cache() {
+    let (interpreter, output, err) = interpreter::run_content_with_interpreter(
+        r#"class Counter {
+    init() { this.count = 0; }
+    bump() { this.count = this.count + 1; }
+}
+
+var counter = Counter();
+for (var i in 0..10) {
+    counter.bump();
+}
+print counter.count;"#,
+    );
+    assert_none!(err);
+    assert_eq!("10\n", output);
+
+    let stats = interpreter.method_cache_stats();
+    assert_eq!(1, stats.misses);
+    assert_eq!(9, stats.hits);
+}
+
+#[test]
+fn inherited_methods_are_cached() {
+    let (interpreter, output, err) = interpreter::run_content_with_interpreter(
+        r#"class A { name() { return "a"; } }
+class B < A {}
+class C < B {
+    name() { return "c" + super.name(); }
+}
+
+var c = C();
+for (var i in 0..5) {
+    print c.name();
+}"#,
+    );
+    assert_none!(err);
+    assert_eq!("ca\nca\nca\nca\nca\n", output);
+
+    let stats = interpreter.method_cache_stats();
+    assert_eq!(2, stats.misses);
+    assert_eq!(8, stats.hits);
+}
+
+#[test]
+fn redefined_classes_miss_the_cache() {
+    let (interpreter, output, err) = interpreter::run_content_with_interpreter(
+        r#"fun describe(o) { return o.describe(); }
+
+class Thing { describe() { return "first"; } }
+print describe(Thing());
+print describe(Thing());
+
+class Thing { describe() { return "second"; } }
+print describe(Thing());"#,
+    );
+    assert_none!(err);
+    assert_eq!("first\nfirst\nsecond\n", output);
+
+    let stats = interpreter.method_cache_stats();
+    assert_eq!(2, stats.misses);
+    assert_eq!(1, stats.hits);
+}
+
+#[test]
+fn call_sites_seeing_different_classes() {
+    let (output, err) = interpreter::run_content(
+        r#"class Cat { speak() { return "meow"; } }
+class Dog { speak() { return "woof"; } }
+class Quiet {}
+
+var animals = "cdcqd";
+for (var i in 0..5) {
+    var animal;
+    if (animals[i] == "c") animal = Cat();
+    if (animals[i] == "d") animal = Dog();
+    if (animals[i] == "q") animal = Quiet();
+    print animal.speak;
+}"#,
+    );
+    assert_none!(err);
+    assert_eq!(
+        "<fn speak>\n<fn speak>\n<fn speak>\nnil\n<fn speak>\n",
+        output
+    );
+}
+
+#[test]
+fn fields_shadow_cached_methods() {
+    let (output, err) = interpreter::run_content(
+        r#"class Box {
+    get() { return "method"; }
+}
+
+var box = Box();
+print box.get();
+box.get = "field";
+print box.get;"#,
+    );
+    assert_none!(err);
+    assert_eq!("method\nfield\n", output);
+}
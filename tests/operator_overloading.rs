@@ -0,0 +1,198 @@
+use crate::common::interpreter;
+
+mod common;
+
+#[test]
+fn add() {
+    let (output, err) = interpreter::run_content(
+        r#"class Vector {
+    init(x, y) {
+        this.x = x;
+        this.y = y;
+    }
+
+    __add__(other) {
+        return Vector(this.x + other.x, this.y + other.y);
+    }
+}
+
+var v = Vector(1, 2) + Vector(3, 4);
+print v.x;
+print v.y;"#,
+    );
+    assert_none!(err);
+    assert_eq!("4\n6\n", output);
+}
+
+#[test]
+fn arithmetic() {
+    let (output, err) = interpreter::run_content(
+        r#"class Money {
+    init(cents) {
+        this.cents = cents;
+    }
+
+    __sub__(other) { return Money(this.cents - other.cents); }
+    __mul__(factor) { return Money(this.cents * factor); }
+    __div__(factor) { return Money(this.cents / factor); }
+}
+
+print (Money(500) - Money(200)).cents;
+print (Money(500) * 3).cents;
+print (Money(500) / 4).cents;"#,
+    );
+    assert_none!(err);
+    assert_eq!("300\n1500\n125\n", output);
+}
+
+#[test]
+fn equality() {
+    let (output, err) = interpreter::run_content(
+        r#"class Money {
+    init(cents) {
+        this.cents = cents;
+    }
+
+    __eq__(other) {
+        return this.cents == other.cents;
+    }
+}
+
+print Money(100) == Money(100);
+print Money(100) == Money(200);
+print Money(100) != Money(100);
+print Money(100) != Money(200);"#,
+    );
+    assert_none!(err);
+    assert_eq!("true\nfalse\nfalse\ntrue\n", output);
+}
+
+#[test]
+fn not_equal_prefers_its_own_method() {
+    let (output, err) = interpreter::run_content(
+        r#"class Contrary {
+    __eq__(other) { return true; }
+    __ne__(other) { return "ne"; }
+}
+
+print Contrary() != Contrary();"#,
+    );
+    assert_none!(err);
+    assert_eq!("ne\n", output);
+}
+
+#[test]
+fn comparison() {
+    let (output, err) = interpreter::run_content(
+        r#"class Money {
+    init(cents) {
+        this.cents = cents;
+    }
+
+    __lt__(other) { return this.cents < other.cents; }
+    __le__(other) { return this.cents <= other.cents; }
+    __gt__(other) { return this.cents > other.cents; }
+    __ge__(other) { return this.cents >= other.cents; }
+}
+
+print Money(1) < Money(2);
+print Money(2) <= Money(2);
+print Money(1) > Money(2);
+print Money(1) >= Money(2);"#,
+    );
+    assert_none!(err);
+    assert_eq!("true\ntrue\nfalse\nfalse\n", output);
+}
+
+#[test]
+fn inherited_operator() {
+    let (output, err) = interpreter::run_content(
+        r#"class Base {
+    __add__(other) { return "added"; }
+}
+
+class Derived < Base {}
+
+print Derived() + 1;"#,
+    );
+    assert_none!(err);
+    assert_eq!("added\n", output);
+}
+
+#[test]
+fn negate() {
+    let (output, err) = interpreter::run_content(
+        r#"class Vector {
+    init(x) {
+        this.x = x;
+    }
+
+    __neg__() {
+        return Vector(-this.x);
+    }
+}
+
+print (-Vector(3)).x;"#,
+    );
+    assert_none!(err);
+    assert_eq!("-3\n", output);
+}
+
+#[test]
+fn index() {
+    let (output, err) = interpreter::run_content(
+        r#"class Squares {
+    __getitem__(i) {
+        return i * i;
+    }
+}
+
+var squares = Squares();
+print squares[4];"#,
+    );
+    assert_none!(err);
+    assert_eq!("16\n", output);
+}
+
+#[test]
+fn missing_operator() {
+    let (_, err) = interpreter::run_content(
+        r#"class Foo {}
+
+print Foo() + 1;"#,
+    );
+    assert_some!(err);
+    assert_eq!(
+        "[line 3] Error: Operands must be two numbers or two strings.",
+        err.unwrap().to_string()
+    );
+}
+
+#[test]
+fn missing_negate() {
+    let (_, err) = interpreter::run_content(
+        r#"class Foo {}
+print -Foo();"#,
+    );
+    assert_some!(err);
+    assert_eq!(
+        "[line 2] Error: Operand must be a number.",
+        err.unwrap().to_string()
+    );
+}
+
+#[test]
+fn operator_arity() {
+    let (_, err) = interpreter::run_content(
+        r#"class Foo {
+    __add__() { return 1; }
+}
+
+print Foo() + 1;"#,
+    );
+    assert_some!(err);
+    assert_eq!(
+        "[line 5] Error: Expected 0 arguments for method '__add__' but got 1.",
+        err.unwrap().to_string()
+    );
+}
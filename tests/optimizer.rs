@@ -0,0 +1,104 @@
+use common::{interpreter, parser};
+use interpreter_starter_rust::{optimizer::Optimizer, parser::Statement, resolver::Resolver};
+
+mod common;
+
+fn optimize(content: &'static str) -> Vec<String> {
+    let statements = parser::parse_content(content).unwrap();
+    let mut resolver = Resolver::new();
+    resolver.resolve(&statements).unwrap();
+    Optimizer::new(&mut resolver.resolve_table)
+        .optimize(&statements)
+        .iter()
+        .map(Statement::to_string)
+        .collect()
+}
+
+#[test]
+fn optimizer_folds_arithmetic() {
+    assert_eq!(
+        vec!["print 14.0", "print true", "print -3.0"],
+        optimize("print (1 + 2) * 4 + 2; print !(5 < 3); print -(1 + 2);")
+    );
+}
+
+#[test]
+fn optimizer_folds_string_concatenation() {
+    assert_eq!(
+        vec!["print foobar", "print true"],
+        optimize(r#"print "foo" + "bar"; print "a" + "b" == "ab";"#)
+    );
+}
+
+#[test]
+fn optimizer_keeps_failing_operations() {
+    assert_eq!(
+        vec!["print (- a 1.0)", "print (< 1.0 b)"],
+        optimize(r#"print "a" - 1; print 1 < "b";"#)
+    );
+}
+
+#[test]
+fn optimizer_removes_dead_branches() {
+    assert_eq!(
+        vec!["print 2.0", "print 3.0"],
+        optimize(
+            "if (false) print 1; else print 2; if (1 > 2) print 0; while (nil) print 0; print 3;"
+        )
+    );
+}
+
+#[test]
+fn optimizer_simplifies_logical_operators() {
+    assert_eq!(
+        vec![
+            "var IDENTIFIER a null=1.0",
+            "print (var \"a\")",
+            "print false",
+            "print 2.0"
+        ],
+        optimize("var a = 1; print false or a; print false and a; print nil or 1 + 1;")
+    );
+}
+
+#[test]
+fn optimizer_keeps_runtime_error_lines() {
+    let (_, err) = interpreter::run_content("var a = 1 + 2;\nprint \"a\" - 1;");
+    assert_some!(err);
+    assert_eq!(
+        "[line 2] Error: Operands must be two numbers or two strings.",
+        err.unwrap().to_string()
+    );
+}
+
+#[test]
+fn optimizer_keeps_resolved_assignments() {
+    let (output, err) = interpreter::run_content(
+        r#"var a = "global";
+{
+    var a = "outer";
+    fun f() {
+        a = "in" + "ner";
+        print a;
+    }
+    f();
+    print a;
+}
+print a;"#,
+    );
+    assert_none!(err);
+    assert_eq!("inner\ninner\nglobal\n", output);
+}
+
+#[test]
+fn optimizer_keeps_side_effects_of_live_branches() {
+    let (output, err) = interpreter::run_content(
+        r#"var count = 0;
+fun bump() { count = count + 1; return true; }
+if (true or bump()) print "taken";
+print true and bump();
+print count;"#,
+    );
+    assert_none!(err);
+    assert_eq!("taken\ntrue\n1\n", output);
+}
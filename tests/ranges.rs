@@ -0,0 +1,273 @@
+use interpreter_starter_rust::scanner::TokenType;
+
+use crate::common::{interpreter, scanner};
+
+mod common;
+
+#[test]
+fn scan_ranges() {
+    let tokens = scanner::scan_content("1..3 1..=3 s[0]");
+    use TokenType::*;
+    assert_eq!(
+        vec![
+            Number,
+            DotDot,
+            Number,
+            Number,
+            DotDotEqual,
+            Number,
+            Identifier,
+            LeftBracket,
+            Number,
+            RightBracket,
+            EOF
+        ],
+        tokens
+    );
+}
+
+#[test]
+fn print_range() {
+    let (output, err) = interpreter::run_content(
+        r#"print 1..3;
+print 1..=3;
+print 1 + 1..2 * 3;"#,
+    );
+    assert_none!(err);
+    assert_eq!("1..3\n1..=3\n2..6\n", output);
+}
+
+#[test]
+fn range_bounds_must_be_integers() {
+    let (_, err) = interpreter::run_content("print 1.5..3;");
+    assert_some!(err);
+    assert_eq!(
+        "[line 1] Error: Range bounds must be integers.",
+        err.unwrap().to_string()
+    );
+}
+
+#[test]
+fn range_bounds_must_be_exact_integers() {
+    for content in [
+        "var a = -9000000000000000000; var r = a..9000000000000000000; print r[0];",
+        "var r = 0..=100000000000000000000; print r[0];",
+        r#"print "abc"[0..=100000000000000000000];"#,
+    ] {
+        let (_, err) = interpreter::run_content(content);
+        assert_some!(err);
+        assert_eq!(
+            "[line 1] Error: Range bounds must be between -9007199254740992 and 9007199254740992.",
+            err.unwrap().to_string()
+        );
+    }
+}
+
+#[test]
+fn ranges_at_the_largest_bounds() {
+    let (output, err) = interpreter::run_content(
+        r#"var r = -9007199254740992..=9007199254740992;
+print r[0];
+print r[-1];
+print r[-2..-1];"#,
+    );
+    assert_none!(err);
+    assert_eq!(
+        "-9007199254740992\n9007199254740992\n9007199254740991..9007199254740992\n",
+        output
+    );
+}
+
+#[test]
+fn range_bounds_must_be_numbers() {
+    let (_, err) = interpreter::run_content("print \"a\"..3;");
+    assert_some!(err);
+    assert_eq!(
+        "[line 1] Error: Range bounds must be numbers.",
+        err.unwrap().to_string()
+    );
+}
+
+#[test]
+fn iterate_range() {
+    let (output, err) = interpreter::run_content(
+        r#"for (var i in 0..3) print i;
+for (var i in 0..=3) print i;"#,
+    );
+    assert_none!(err);
+    assert_eq!("0\n1\n2\n0\n1\n2\n3\n", output);
+}
+
+#[test]
+fn iterate_empty_range() {
+    let (output, err) = interpreter::run_content("for (var i in 3..1) print i;");
+    assert_none!(err);
+    assert_eq!("", output);
+}
+
+#[test]
+fn iterate_string() {
+    let (output, err) = interpreter::run_content(r#"for (var c in "héllo") print c;"#);
+    assert_none!(err);
+    assert_eq!("h\né\nl\nl\no\n", output);
+}
+
+#[test]
+fn iterate_in_closure() {
+    let (output, err) = interpreter::run_content(
+        r#"var printers = nil;
+fun make(i) {
+    fun print_it() {
+        print i;
+    }
+    return print_it;
+}
+for (var i in 1..3) {
+    printers = make(i);
+    printers();
+}"#,
+    );
+    assert_none!(err);
+    assert_eq!("1\n2\n", output);
+}
+
+#[test]
+fn return_from_for_in() {
+    let (output, err) = interpreter::run_content(
+        r#"fun first_over(limit) {
+    for (var i in 0..100) {
+        if (i > limit) return i;
+    }
+    return nil;
+}
+print first_over(41);"#,
+    );
+    assert_none!(err);
+    assert_eq!("42\n", output);
+}
+
+#[test]
+fn iterate_non_sequence() {
+    let (_, err) = interpreter::run_content("for (var i in 42) print i;");
+    assert_some!(err);
+    assert_eq!(
+        "[line 1] Error: Can only iterate over strings and ranges.",
+        err.unwrap().to_string()
+    );
+}
+
+#[test]
+fn index_string() {
+    let (output, err) = interpreter::run_content(
+        r#"var s = "hello";
+print s[0];
+print s[4];
+print s[-1];"#,
+    );
+    assert_none!(err);
+    assert_eq!("h\no\no\n", output);
+}
+
+#[test]
+fn slice_string() {
+    let (output, err) = interpreter::run_content(
+        r#"var s = "hello";
+print s[1..3];
+print s[1..=3];
+print s[0..0];
+print s[-3..-1];
+print s[-3..=-1];
+print s[0..5];"#,
+    );
+    assert_none!(err);
+    assert_eq!("el\nell\n\nll\nllo\nhello\n", output);
+}
+
+#[test]
+fn slice_unicode_string() {
+    let (output, err) = interpreter::run_content(r#"print "héllo wörld"[1..=7];"#);
+    assert_none!(err);
+    assert_eq!("éllo wö\n", output);
+}
+
+#[test]
+fn index_and_slice_range() {
+    let (output, err) = interpreter::run_content(
+        r#"var r = 10..20;
+print r[0];
+print r[-1];
+print r[2..4];
+print r[2..4][1];"#,
+    );
+    assert_none!(err);
+    assert_eq!("10\n19\n12..14\n13\n", output);
+}
+
+#[test]
+fn index_out_of_bounds() {
+    let (_, err) = interpreter::run_content(
+        r#"var s = "hello";
+print s[5];"#,
+    );
+    assert_some!(err);
+    assert_eq!(
+        "[line 2] Error: Index 5 out of bounds for length 5.",
+        err.unwrap().to_string()
+    );
+}
+
+#[test]
+fn negative_index_out_of_bounds() {
+    let (_, err) = interpreter::run_content(r#"print "hello"[-6];"#);
+    assert_some!(err);
+    assert_eq!(
+        "[line 1] Error: Index -6 out of bounds for length 5.",
+        err.unwrap().to_string()
+    );
+}
+
+#[test]
+fn slice_out_of_bounds() {
+    let (_, err) = interpreter::run_content(
+        r#"var s = "hello";
+
+print s[
+  2..=5
+];"#,
+    );
+    assert_some!(err);
+    assert_eq!(
+        "[line 5] Error: Slice 2..=5 out of bounds for length 5.",
+        err.unwrap().to_string()
+    );
+}
+
+#[test]
+fn slice_reversed_bounds() {
+    let (_, err) = interpreter::run_content(r#"print "hello"[3..1];"#);
+    assert_some!(err);
+    assert_eq!(
+        "[line 1] Error: Slice 3..1 out of bounds for length 5.",
+        err.unwrap().to_string()
+    );
+}
+
+#[test]
+fn index_must_be_integer() {
+    let (_, err) = interpreter::run_content(r#"print "hello"[1.5];"#);
+    assert_some!(err);
+    assert_eq!(
+        "[line 1] Error: Index must be an integer.",
+        err.unwrap().to_string()
+    );
+}
+
+#[test]
+fn index_non_sequence() {
+    let (_, err) = interpreter::run_content("print 42[0];");
+    assert_some!(err);
+    assert_eq!(
+        "[line 1] Error: Only strings and ranges can be indexed.",
+        err.unwrap().to_string()
+    );
+}
@@ -1,5 +1,5 @@
 use common::parser;
-use interpreter_starter_rust::resolver::Resolver;
+use interpreter_starter_rust::{error_kind::ErrorKind, resolver::Resolver};
 
 mod common;
 
@@ -27,3 +27,33 @@ fn resolver_one_variable() {
     assert!(res.is_ok());
     assert_eq!(1, sut.resolve_table.keys().count());
 }
+
+#[test]
+fn resolver_collects_every_error() {
+    let res = parser::parse_content(
+        r#"return 1;
+{
+    var a = 1;
+    var a = 2;
+}
+print this;"#,
+    );
+    assert!(res.is_ok());
+    let mut sut = Resolver::new();
+    let res = sut.resolve(&res.unwrap());
+    assert!(res.is_err());
+    let kinds = res
+        .unwrap_err()
+        .messages()
+        .iter()
+        .map(|i| i.kind())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            ErrorKind::ReturnFromTopLevel,
+            ErrorKind::AlreadyDeclared,
+            ErrorKind::ThisOutsideClass
+        ],
+        kinds
+    );
+}
@@ -1,4 +1,5 @@
 use crate::common::interpreter;
+use interpreter_starter_rust::error_kind::ErrorKind;
 
 mod common;
 
@@ -42,3 +43,181 @@ fn multiple_variables_with_same_name_in_local_scope() {
     );
     assert_some!(err);
 }
+
+#[test]
+fn locals_in_nested_scopes() {
+    let (output, err) = interpreter::run_content(
+        r#"fun outer(a, b) {
+  var c = a + b;
+  {
+    var d = c * 2;
+    {
+      var a = d + 1;
+      print a;
+      print b;
+    }
+    print a;
+  }
+  return c;
+}
+
+print outer(1, 2);"#,
+    );
+    assert_none!(err);
+    assert_eq!("7\n2\n1\n3\n", output);
+}
+
+#[test]
+fn closures_capture_their_own_frame() {
+    let (output, err) = interpreter::run_content(
+        r#"fun counter(start) {
+  var count = start;
+  fun increment() {
+    count = count + 1;
+    return count;
+  }
+  return increment;
+}
+
+var a = counter(0);
+var b = counter(10);
+a();
+print a();
+print b();"#,
+    );
+    assert_none!(err);
+    assert_eq!("2\n11\n", output);
+}
+
+#[test]
+fn local_class() {
+    let (output, err) = interpreter::run_content(
+        r#"{
+  class Point {
+    init(x) { this.x = x; }
+    copy() { return Point(this.x); }
+  }
+
+  print Point(1).copy().x;
+}"#,
+    );
+    assert_none!(err);
+    assert_eq!("1\n", output);
+}
+
+#[test]
+fn assignment_in_closure_ignores_later_shadowing() {
+    let (output, err) = interpreter::run_content(
+        r#"var a = "global";
+{
+  fun set() { a = "assigned"; }
+  var a = "local";
+  set();
+  print a;
+}
+print a;"#,
+    );
+    assert_none!(err);
+    assert_eq!("local\nassigned\n", output);
+}
+
+#[test]
+fn assignment_in_nested_closure_targets_enclosing_function() {
+    let (output, err) = interpreter::run_content(
+        r#"fun outer() {
+  var x = "outer";
+  fun make() {
+    fun assign() { x = "assigned"; }
+    var x = "shadow";
+    assign();
+    print x;
+  }
+  make();
+  print x;
+}
+
+outer();"#,
+    );
+    assert_none!(err);
+    assert_eq!("shadow\nassigned\n", output);
+}
+
+#[test]
+fn assignment_and_read_use_same_binding() {
+    let (output, err) = interpreter::run_content(
+        r#"fun counter() {
+  var count = 0;
+  fun increment() {
+    var before = count;
+    count = count + 1;
+    return before + count;
+  }
+  return increment;
+}
+
+var count = 100;
+var increment = counter();
+print increment();
+print increment();
+print count;"#,
+    );
+    assert_none!(err);
+    assert_eq!("1\n3\n100\n", output);
+}
+
+#[test]
+fn assigning_variable_from_deeper_scope() {
+    let (output, err) = interpreter::run_content(
+        r#"{
+  var x = "outer x";
+  {
+    var y = "inner y";
+    x = y;
+    print x;
+  }
+  print x;
+}"#,
+    );
+    assert_none!(err);
+    assert_eq!("inner y\ninner y\n", output);
+}
+
+#[test]
+fn every_error_is_reported_before_running() {
+    let (output, err) = interpreter::run_content(
+        "print \"ran\";
+var a = @;
+return 1;
+fun f() {
+  var b;
+  var b;
+}
+var c = ;",
+    );
+    assert_eq!("", output);
+    assert_some!(err);
+    let err = err.unwrap();
+    let lines = err
+        .messages()
+        .iter()
+        .map(|i| (i.kind(), i.line()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            (ErrorKind::UnexpectedCharacter, Some(2)),
+            (ErrorKind::UnexpectedToken, Some(8)),
+            (ErrorKind::ReturnFromTopLevel, Some(3)),
+            (ErrorKind::AlreadyDeclared, Some(6)),
+        ],
+        lines
+    );
+    assert_eq!(65, err.exit_code());
+}
+
+#[test]
+fn resolving_errors_alone_prevent_running() {
+    let (output, err) = interpreter::run_content("print \"ran\";\nreturn 1;\nprint this;");
+    assert_eq!("", output);
+    assert_some!(err);
+    assert_eq!(2, err.unwrap().messages().len());
+}
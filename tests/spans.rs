@@ -0,0 +1,98 @@
+mod common;
+
+use crate::common::{interpreter, parser, scanner, scanner::span};
+
+#[test]
+fn tokens_span_columns_and_byte_offsets() {
+    let tokens = scanner::scan_content("var é = 1;\n  print é;");
+    let spans = tokens.iter().map(|i| i.span).collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            span((1, 1, 0), (1, 4, 3)),
+            span((1, 5, 4), (1, 6, 6)),
+            span((1, 7, 7), (1, 8, 8)),
+            span((1, 9, 9), (1, 10, 10)),
+            span((1, 10, 10), (1, 11, 11)),
+            span((2, 3, 14), (2, 8, 19)),
+            span((2, 9, 20), (2, 10, 22)),
+            span((2, 10, 22), (2, 11, 23)),
+            span((3, 1, 24), (3, 1, 24)),
+        ],
+        spans
+    );
+}
+
+#[test]
+fn multiline_string_spans_its_lines() {
+    let tokens = scanner::scan_content("\"a\nb\"");
+    assert_eq!(span((1, 1, 0), (2, 3, 5)), tokens[0].span);
+}
+
+#[test]
+fn expression_spans_its_tokens() {
+    let expr = parser::parse_content_to_expression("(1 + 2) * x").unwrap();
+    assert_eq!(span((1, 1, 0), (1, 12, 11)), expr.unwrap().span());
+}
+
+#[test]
+fn statement_spans_its_tokens() {
+    let statements = parser::parse_content(
+        "if (a) print 1; else { b; }
+fun f(x) {
+  return x;
+}",
+    )
+    .unwrap();
+    assert_eq!(span((1, 1, 0), (1, 28, 27)), statements[0].span());
+    assert_eq!(span((2, 1, 28), (4, 2, 52)), statements[1].span());
+}
+
+#[test]
+fn parsing_error_points_at_unexpected_token() {
+    let (_, err) = interpreter::run_content("var x = ;");
+    assert_some!(err);
+    let err = err.unwrap();
+    assert_eq!(Some(span((1, 9, 8), (1, 10, 9))), err.messages()[0].span());
+}
+
+#[test]
+fn scanning_error_points_at_unterminated_string() {
+    let (_, err) = interpreter::run_content("print \"abc");
+    assert_some!(err);
+    let err = err.unwrap();
+    assert_eq!(Some(span((1, 7, 6), (2, 1, 11))), err.messages()[0].span());
+}
+
+#[test]
+fn return_at_top_level_points_at_keyword() {
+    let (_, err) = interpreter::run_content("print 1;\n  return 1;");
+    assert_some!(err);
+    let err = err.unwrap();
+    assert_eq!(
+        "[line 2] Error: Can't return from top level code.",
+        err.messages()[0].to_string()
+    );
+    assert_eq!(Some(span((2, 3, 11), (2, 9, 17))), err.messages()[0].span());
+}
+
+#[test]
+fn runtime_error_points_at_operator_on_both_backends() {
+    let content = "print 1;\nprint \"a\" - 1;";
+    let (_, err) = interpreter::run_tree_walker(content);
+    let (_, vm_err) = interpreter::run_vm(content);
+    assert_some!(err);
+    assert_some!(vm_err);
+    let expected = Some(span((2, 11, 19), (2, 12, 20)));
+    assert_eq!(expected, err.unwrap().messages()[0].span());
+    assert_eq!(expected, vm_err.unwrap().messages()[0].span());
+}
+
+#[test]
+fn undefined_variable_points_at_name() {
+    let (_, err) = interpreter::run_content("var a = 1;\nprint a + bee;");
+    assert_some!(err);
+    assert_eq!(
+        Some(span((2, 11, 21), (2, 14, 24))),
+        err.unwrap().messages()[0].span()
+    );
+}
@@ -0,0 +1,122 @@
+use interpreter_starter_rust::errors::StackFrame;
+
+use crate::common::interpreter;
+
+mod common;
+
+#[test]
+fn runtime_errors_carry_the_running_calls() {
+    let (_, err) = interpreter::run_content(
+        r#"fun inner(x) {
+    return x - "a";
+}
+fun outer() {
+    var r = inner(1);
+    return r;
+}
+outer();"#,
+    );
+    let err = err.unwrap();
+    assert_eq!(
+        "[line 2] Error: Operands must be two numbers or two strings.",
+        err.to_string()
+    );
+    assert_eq!(
+        vec![
+            StackFrame::new("outer", 8, 0),
+            StackFrame::new("inner", 5, 0)
+        ],
+        err.trace().unwrap().frames
+    );
+    assert_eq!(
+        "Stack trace (most recent call first):
+    in inner called at [line 5]
+    in outer called at [line 8]",
+        err.trace().unwrap().to_string()
+    );
+}
+
+#[test]
+fn methods_and_natives_are_frames() {
+    let (_, err) = interpreter::run_content(
+        r#"class Config {
+    get(key) {
+        var value = env(key);
+        return value;
+    }
+}
+Config().get(42);"#,
+    );
+    let err = err.unwrap();
+    assert_eq!(
+        vec![StackFrame::new("get", 7, 0), StackFrame::new("env", 3, 0)],
+        err.trace().unwrap().frames
+    );
+}
+
+#[test]
+fn frames_replaced_by_tail_calls_are_counted() {
+    let (_, err) = interpreter::run_content(
+        r#"fun fail() {
+    var x = nil;
+    return x.field;
+}
+fun countdown(n) {
+    if (n == 0) return fail();
+    return countdown(n - 1);
+}
+fun start() {
+    var result = countdown(3);
+    return result;
+}
+start();"#,
+    );
+    let trace = err.unwrap().trace().unwrap().clone();
+    assert_eq!(
+        vec![
+            StackFrame::new("start", 13, 0),
+            StackFrame::new("fail", 6, 4)
+        ],
+        trace.frames
+    );
+    assert_eq!(
+        "Stack trace (most recent call first):
+    in fail called at [line 6] (4 tail calls elided)
+    in start called at [line 13]",
+        trace.to_string()
+    );
+}
+
+#[test]
+fn repeated_frames_are_collapsed() {
+    let (_, err) = interpreter::run_content(
+        r#"fun f(n) {
+    return 1 + f(n + 1);
+}
+f(0);"#,
+    );
+    assert_eq!(
+        "Stack trace (most recent call first):
+    in f called at [line 2]
+    ... repeated 998 more times
+    in f called at [line 4]",
+        err.unwrap().trace().unwrap().to_string()
+    );
+}
+
+#[test]
+fn errors_outside_calls_have_no_trace() {
+    let (_, err) = interpreter::run_content(r#"print -"a";"#);
+    assert!(err.unwrap().trace().is_none());
+}
+
+#[test]
+fn frames_are_released_after_returning() {
+    let (_, err) = interpreter::run_content(
+        r#"fun ok() { return 1; }
+ok();
+ok();
+print -"a";"#,
+    );
+    assert!(err.unwrap().trace().is_none());
+}
@@ -0,0 +1,121 @@
+use crate::common::interpreter;
+
+mod common;
+
+#[test]
+fn tail_recursion_runs_in_constant_space() {
+    let (output, err) = interpreter::run_content(
+        r#"fun loop(n, acc) {
+    if (n == 0) return acc;
+    return loop(n - 1, acc + n);
+}
+print loop(100000, 0);"#,
+    );
+    assert_none!(err);
+    assert_eq!("5000050000\n", output);
+}
+
+#[test]
+fn mutual_tail_recursion_runs_in_constant_space() {
+    let (output, err) = interpreter::run_content(
+        r#"fun isEven(n) {
+    if (n == 0) return true;
+    return isOdd(n - 1);
+}
+fun isOdd(n) {
+    if (n == 0) return false;
+    return isEven(n - 1);
+}
+print isEven(100000);
+print isOdd(7);"#,
+    );
+    assert_none!(err);
+    assert_eq!("true\ntrue\n", output);
+}
+
+#[test]
+fn tail_calls_to_methods() {
+    let (output, err) = interpreter::run_content(
+        r#"class List {
+    init(head, tail) {
+        this.head = head;
+        this.tail = tail;
+    }
+    length(acc) {
+        if (this.tail == nil) return acc + 1;
+        return this.tail.length(acc + 1);
+    }
+}
+var list = nil;
+for (var i in 0..500) list = List(i, list);
+print list.length(0);"#,
+    );
+    assert_none!(err);
+    assert_eq!("500\n", output);
+}
+
+#[test]
+fn tail_calls_to_classes_and_natives() {
+    let (output, err) = interpreter::run_content(
+        r#"class Point {
+    init(x) { this.x = x; }
+}
+fun make(x) { return Point(x); }
+fun lookup() { return env("LOX_TAIL_CALLS_UNDEFINED"); }
+print make(3).x;
+print lookup();"#,
+    );
+    assert_none!(err);
+    assert_eq!("3\nnil\n", output);
+}
+
+#[test]
+fn closures_keep_the_variables_of_replaced_calls() {
+    let (output, err) = interpreter::run_content(
+        r#"fun apply(f) { return f(); }
+fun capture(n) {
+    fun get() { return n; }
+    return apply(get);
+}
+print capture(42);"#,
+    );
+    assert_none!(err);
+    assert_eq!("42\n", output);
+}
+
+#[test]
+fn tail_calls_inside_loops_and_operators() {
+    let (output, err) = interpreter::run_content(
+        r#"fun first(s) {
+    for (var c in s) {
+        return id(c);
+    }
+}
+fun id(x) { return x; }
+class Box {
+    init(n) { this.n = n; }
+    __add__(other) { return add(this.n, other); }
+}
+fun add(a, b) { return a + b; }
+print first("xyz");
+print Box(1) + 2;"#,
+    );
+    assert_none!(err);
+    assert_eq!("x\n3\n", output);
+}
+
+#[test]
+fn tail_calls_report_errors_at_the_call() {
+    let (_, err) = interpreter::run_content(
+        r#"fun f(a) { return a; }
+fun g() {
+    return f();
+}
+g();"#,
+    );
+    assert_some!(err);
+    assert_eq!(
+        "[line 3] Error: Expected 1 arguments for function '<fn f>' but got 0.",
+        err.unwrap().to_string()
+    );
+}
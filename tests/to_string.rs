@@ -0,0 +1,166 @@
+use crate::common::interpreter;
+
+mod common;
+
+#[test]
+fn print_without_to_string() {
+    let (output, err) = interpreter::run_content(
+        r#"class Point {}
+print Point();"#,
+    );
+    assert_none!(err);
+    assert_eq!("Point instance\n", output);
+}
+
+#[test]
+fn print_with_to_string() {
+    let (output, err) = interpreter::run_content(
+        r#"class Pair {
+    init(x, y) {
+        this.x = x;
+        this.y = y;
+    }
+
+    toString() {
+        return "(" + this.x + ", " + this.y + ")";
+    }
+}
+
+print Pair("a", "b");"#,
+    );
+    assert_none!(err);
+    assert_eq!("(a, b)\n", output);
+}
+
+#[test]
+fn inherited_to_string() {
+    let (output, err) = interpreter::run_content(
+        r#"class Animal {
+    toString() {
+        return "a " + this.kind;
+    }
+}
+
+class Dog < Animal {
+    init() {
+        this.kind = "dog";
+    }
+}
+
+print Dog();"#,
+    );
+    assert_none!(err);
+    assert_eq!("a dog\n", output);
+}
+
+#[test]
+fn concatenation() {
+    let (output, err) = interpreter::run_content(
+        r#"class Name {
+    toString() {
+        return "Bob";
+    }
+}
+
+print "Hello " + Name() + "!";
+print Name() + " says hi";"#,
+    );
+    assert_none!(err);
+    assert_eq!("Hello Bob!\nBob says hi\n", output);
+}
+
+#[test]
+fn concatenation_without_to_string() {
+    let (_, err) = interpreter::run_content(
+        r#"class Name {}
+print "Hello " + Name();"#,
+    );
+    assert_some!(err);
+    assert_eq!(
+        "[line 2] Error: Operands must be two numbers or two strings.",
+        err.unwrap().to_string()
+    );
+}
+
+#[test]
+fn nested_to_string() {
+    let (output, err) = interpreter::run_content(
+        r#"class Leaf {
+    toString() { return "leaf"; }
+}
+
+class Node {
+    init(child) { this.child = child; }
+    toString() { return "node(" + this.child + ")"; }
+}
+
+print Node(Node(Leaf()));"#,
+    );
+    assert_none!(err);
+    assert_eq!("node(node(leaf))\n", output);
+}
+
+#[test]
+fn to_string_returning_instance() {
+    let (output, err) = interpreter::run_content(
+        r#"class Inner {
+    toString() { return "inner"; }
+}
+
+class Outer {
+    toString() { return Inner(); }
+}
+
+print Outer();"#,
+    );
+    assert_none!(err);
+    assert_eq!("inner\n", output);
+}
+
+#[test]
+fn to_string_returning_this() {
+    let (output, err) = interpreter::run_content(
+        r#"class Selfish {
+    toString() { return this; }
+}
+
+print Selfish();"#,
+    );
+    assert_none!(err);
+    assert_eq!("Selfish instance\n", output);
+}
+
+#[test]
+fn cyclic_to_string() {
+    let (output, err) = interpreter::run_content(
+        r#"class Person {
+    init(name) { this.name = name; }
+    toString() { return this.name + " knows " + this.friend; }
+}
+
+var alice = Person("Alice");
+var bob = Person("Bob");
+alice.friend = bob;
+bob.friend = alice;
+
+print alice;"#,
+    );
+    assert_none!(err);
+    assert_eq!("Alice knows Bob knows Person instance\n", output);
+}
+
+#[test]
+fn to_string_error() {
+    let (_, err) = interpreter::run_content(
+        r#"class Broken {
+    toString() { return -"oops"; }
+}
+
+print Broken();"#,
+    );
+    assert_some!(err);
+    assert_eq!(
+        "[line 2] Error: Operand must be a number.",
+        err.unwrap().to_string()
+    );
+}
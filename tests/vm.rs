@@ -0,0 +1,243 @@
+use std::thread;
+
+use crate::common::interpreter;
+
+mod common;
+
+// `interpreter::run_content` runs every script on both backends and checks that they agree; the
+// tests below focus on what the VM implements differently: upvalues, call frames and classes.
+
+#[test]
+fn closures_share_captured_variable() {
+    let (output, err) = interpreter::run_content(
+        r#"fun counter() {
+    var n = 0;
+    fun increment() { n = n + 1; }
+    fun get() { return n; }
+    class Pair {
+        init(increment, get) {
+            this.increment = increment;
+            this.get = get;
+        }
+    }
+    return Pair(increment, get);
+}
+
+var c = counter();
+c.increment();
+c.increment();
+print c.get();"#,
+    );
+    assert_none!(err);
+    assert_eq!("2\n", output);
+}
+
+#[test]
+fn closures_capture_each_loop_iteration() {
+    let (output, err) = interpreter::run_content(
+        r#"var first;
+var last;
+for (var i in 0..3) {
+    fun get() { return i; }
+    if (first == nil) first = get;
+    last = get;
+}
+print first();
+print last();
+
+var j = 0;
+var captured;
+while (j < 3) {
+    var k = j;
+    fun get() { return k; }
+    if (j == 1) captured = get;
+    j = j + 1;
+}
+print captured();"#,
+    );
+    assert_none!(err);
+    assert_eq!("0\n2\n1\n", output);
+}
+
+#[test]
+fn variable_captured_through_several_functions() {
+    let (output, err) = interpreter::run_content(
+        r#"fun outer() {
+    var x = "before";
+    fun middle() {
+        fun inner() { return x; }
+        return inner;
+    }
+    var inner = middle();
+    x = "after";
+    return inner;
+}
+
+print outer()();"#,
+    );
+    assert_none!(err);
+    assert_eq!("after\n", output);
+}
+
+#[test]
+fn super_in_closure() {
+    let (output, err) = interpreter::run_content(
+        r#"class A {
+    name() { return "A"; }
+}
+
+class B < A {
+    name() {
+        fun later() { return super.name() + "B"; }
+        return later;
+    }
+}
+
+print B().name()();"#,
+    );
+    assert_none!(err);
+    assert_eq!("AB\n", output);
+}
+
+#[test]
+fn calling_init_directly() {
+    let (output, err) = interpreter::run_content(
+        r#"class Foo {
+    init(value) {
+        this.value = value;
+    }
+}
+
+var foo = Foo(1);
+print foo.init(2);
+print foo.value;"#,
+    );
+    assert_none!(err);
+    assert_eq!("nil\n2\n", output);
+}
+
+#[test]
+fn return_from_nested_loops() {
+    let (output, err) = interpreter::run_content(
+        r#"fun find(target) {
+    for (var i in 0..10) {
+        var j = 0;
+        while (j < 10) {
+            if (i * 10 + j == target) return i + j;
+            j = j + 1;
+        }
+    }
+    return nil;
+}
+
+print find(42);
+print find(100);"#,
+    );
+    assert_none!(err);
+    assert_eq!("6\nnil\n", output);
+}
+
+#[test]
+fn local_functions_and_classes() {
+    let (output, err) = interpreter::run_content(
+        r#"{
+    fun fib(n) {
+        if (n < 2) return n;
+        return fib(n - 1) + fib(n - 2);
+    }
+
+    class Box {
+        init(value) { this.value = value; }
+        toString() { return "Box(" + this.value + ")"; }
+    }
+
+    print fib(15);
+    print Box("a");
+}"#,
+    );
+    assert_none!(err);
+    assert_eq!("610\nBox(a)\n", output);
+}
+
+#[test]
+fn runtime_error_inside_special_method() {
+    let (output, err) = interpreter::run_content(
+        r#"class Vector {
+    init(x) { this.x = x; }
+    __add__(other) { return Vector(this.x + other.x); }
+}
+
+print "start";
+var v = Vector(1) + Vector("a");"#,
+    );
+    assert_some!(err);
+    assert_eq!("start\n", output);
+    assert_eq!(
+        "[line 3] Error: Operands must be two numbers or two strings.",
+        err.unwrap().to_string()
+    );
+}
+
+/// Runs `content` on the VM in a thread with a small native stack, returning what it printed and
+/// the error it failed with.
+fn run_vm_on_small_stack(content: &'static str) -> (String, Option<String>) {
+    thread::Builder::new()
+        .stack_size(2 * 1024 * 1024)
+        .spawn(move || {
+            let (output, err) = interpreter::run_vm(content);
+            (output, err.map(|i| i.to_string()))
+        })
+        .unwrap()
+        .join()
+        .unwrap()
+}
+
+#[test]
+fn special_methods_do_not_use_the_native_stack() {
+    let (output, err) = run_vm_on_small_stack(
+        r#"class Node {
+    init(depth, next) { this.depth = depth; this.next = next; }
+    __eq__(other) {
+        if (this.depth == 0) return other.depth == 0;
+        return this.next == other.next;
+    }
+    toString() {
+        if (this.depth == 0) return "0";
+        return this.next;
+    }
+}
+fun list(n) {
+    var node = Node(0, nil);
+    for (var i in 1..=n) node = Node(i, node);
+    return node;
+}
+print list(990) == list(990);
+print list(990) != list(990);
+print list(990);
+print "list " + list(990);"#,
+    );
+    assert_none!(err);
+    assert_eq!("true\nfalse\n0\nlist 0\n", output);
+}
+
+#[test]
+fn nested_special_methods_overflow_cleanly() {
+    let (output, err) = run_vm_on_small_stack(
+        r#"class Node {
+    init(depth, next) { this.depth = depth; this.next = next; }
+    __eq__(other) {
+        if (this.depth == 0) return other.depth == 0;
+        return this.next == other.next;
+    }
+}
+fun list(n) {
+    var node = Node(0, nil);
+    for (var i in 1..=n) node = Node(i, node);
+    return node;
+}
+print "before";
+print list(1100) == list(1100);"#,
+    );
+    assert_eq!("before\n", output);
+    assert_eq!(Some("[line 5] Error: Stack overflow.".to_string()), err);
+}